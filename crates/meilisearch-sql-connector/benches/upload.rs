@@ -0,0 +1,31 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use meilisearch_sql_connector::meilisearch::client::to_ndjson;
+use serde_json::{json, Value};
+
+// Rows shaped like a typical table sync: a handful of short text and number columns
+fn documents(count: usize) -> Vec<Value> {
+    (0..count)
+        .map(|id| json!({
+            "id": id,
+            "name": format!("user {}", id),
+            "email": format!("user{}@example.com", id),
+            "score": id as f64 * 0.5,
+            "active": id % 2 == 0,
+        }))
+        .collect()
+}
+
+// Compare building the request body of one batch for both upload paths
+fn upload_body(c: &mut Criterion) {
+    let documents = documents(10_000);
+    let mut group = c.benchmark_group("upload_body");
+    group.bench_function("json_array", |b| {
+        // The SDK path copies the batch, then serializes it as one array
+        b.iter(|| serde_json::to_vec(&black_box(&documents).to_vec()).unwrap())
+    });
+    group.bench_function("ndjson", |b| b.iter(|| to_ndjson(black_box(&documents))));
+    group.finish();
+}
+
+criterion_group!(benches, upload_body);
+criterion_main!(benches);
@@ -0,0 +1,370 @@
+use crate::config::Config;
+use crate::database::{ColumnInfo, DatabaseAdapter};
+use crate::error::{ConnectorError, Result};
+use crate::meilisearch::MeilisearchClientTrait;
+use futures_util::stream::{BoxStream, TryStreamExt};
+use meilisearch_sdk::settings::Settings;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// State of a `CircuitBreaker`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Calls go through
+    #[default]
+    Closed,
+    /// Calls are rejected until the cooldown is over
+    Open,
+    /// The cooldown is over, one trial call decides whether to close again
+    HalfOpen,
+}
+
+/// State and counters of a `CircuitBreaker`, as reported by `Connector::breaker_status`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BreakerStatus {
+    pub state: BreakerState,
+    /// Failed calls since the last successful one
+    pub consecutive_failures: u32,
+    /// Failed calls since startup
+    pub total_failures: u64,
+    /// Calls rejected without reaching the dependency because the circuit was open
+    pub rejected_calls: u64,
+    /// How often the circuit opened since startup
+    pub times_opened: u64,
+}
+
+/// Stops calls to a failing dependency for a while instead of retrying
+/// against it on every poll. After `failure_threshold` failed calls in a row
+/// the circuit opens and calls fail fast with `ConnectorError::CircuitOpen`.
+/// Once `cooldown` has passed it is half-open: one trial call goes through,
+/// closing the circuit if it succeeds and reopening it if it fails.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    name: String,
+    failure_threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<BreakerInner>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerInner {
+    status: BreakerStatus,
+    // When the circuit opened, or when the half-open trial call started
+    since: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// A closed breaker for the dependency `name`. A `failure_threshold` of 0
+    /// never opens the circuit, it only counts failures.
+    pub fn new(name: &str, failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            failure_threshold,
+            cooldown,
+            inner: Mutex::new(BreakerInner::default()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn status(&self) -> BreakerStatus {
+        self.inner.lock().unwrap().status.clone()
+    }
+
+    /// Whether a call may go through now. Moves an open circuit whose cooldown
+    /// is over to half-open and lets the caller make the trial call.
+    pub fn check(&self) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        let elapsed = inner.since.map(|since| since.elapsed()).unwrap_or_default();
+        match inner.status.state {
+            BreakerState::Closed => return Ok(()),
+            // A trial call that never finished (e.g. its sync was cancelled)
+            // doesn't keep the circuit half-open forever
+            BreakerState::Open | BreakerState::HalfOpen if elapsed >= self.cooldown => {
+                if inner.status.state == BreakerState::Open {
+                    info!(target: "circuit_breaker", "Circuit of {} is half-open, trying one call", self.name);
+                }
+                inner.status.state = BreakerState::HalfOpen;
+                inner.since = Some(Instant::now());
+                return Ok(());
+            }
+            BreakerState::Open | BreakerState::HalfOpen => inner.status.rejected_calls += 1,
+        }
+        Err(ConnectorError::CircuitOpen(format!(
+            "{} failed {} times in a row, retrying in {}s",
+            self.name,
+            inner.status.consecutive_failures,
+            self.cooldown.saturating_sub(elapsed).as_secs(),
+        )))
+    }
+
+    /// Record the outcome of a call let through by `check`. Only errors
+    /// talking to the dependency count as failures, not e.g. a rejected
+    /// configuration.
+    pub fn record<T>(&self, result: &Result<T>) {
+        let mut inner = self.inner.lock().unwrap();
+        match result {
+            Ok(_) => {
+                if inner.status.state != BreakerState::Closed {
+                    info!(target: "circuit_breaker", "Circuit of {} closed, calls resume", self.name);
+                }
+                inner.status.state = BreakerState::Closed;
+                inner.status.consecutive_failures = 0;
+                inner.since = None;
+            }
+            Err(e) if is_dependency_failure(e) => {
+                inner.status.consecutive_failures += 1;
+                inner.status.total_failures += 1;
+                let trips = inner.status.state == BreakerState::HalfOpen
+                    || (inner.status.state == BreakerState::Closed
+                        && self.failure_threshold > 0
+                        && inner.status.consecutive_failures >= self.failure_threshold);
+                if trips {
+                    warn!(target: "circuit_breaker", "Circuit of {} opened after {} failures in a row, pausing calls for {:?}: {}",
+                          self.name, inner.status.consecutive_failures, self.cooldown, e);
+                    inner.status.state = BreakerState::Open;
+                    inner.status.times_opened += 1;
+                    inner.since = Some(Instant::now());
+                }
+            }
+            Err(_) => {}
+        }
+    }
+
+    /// Run `call` through the breaker
+    pub async fn call<T>(&self, call: impl Future<Output = Result<T>>) -> Result<T> {
+        self.check()?;
+        let result = call.await;
+        self.record(&result);
+        result
+    }
+}
+
+fn is_dependency_failure(error: &ConnectorError) -> bool {
+    matches!(error, ConnectorError::Database(_) | ConnectorError::Meilisearch(_) | ConnectorError::Io(_))
+}
+
+/// The breakers of the connector's two dependencies, the database and Meilisearch
+#[derive(Debug, Clone)]
+pub struct CircuitBreakers {
+    pub database: Arc<CircuitBreaker>,
+    pub meilisearch: Arc<CircuitBreaker>,
+}
+
+impl CircuitBreakers {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            database: Arc::new(CircuitBreaker::new(
+                "database",
+                config.database.circuit_breaker_failures,
+                Duration::from_secs(config.database.circuit_breaker_cooldown_seconds),
+            )),
+            meilisearch: Arc::new(CircuitBreaker::new(
+                "meilisearch",
+                config.meilisearch.circuit_breaker_failures,
+                Duration::from_secs(config.meilisearch.circuit_breaker_cooldown_seconds),
+            )),
+        }
+    }
+
+    pub fn wrap_adapter(&self, adapter: Arc<Box<dyn DatabaseAdapter>>) -> Arc<Box<dyn DatabaseAdapter>> {
+        Arc::new(Box::new(BreakerAdapter::new(adapter, self.database.clone())))
+    }
+
+    pub fn wrap_client(&self, client: Arc<dyn MeilisearchClientTrait>) -> Arc<dyn MeilisearchClientTrait> {
+        Arc::new(BreakerMeilisearchClient::new(client, self.meilisearch.clone()))
+    }
+
+    /// Status of every breaker, by dependency name
+    pub fn status(&self) -> BTreeMap<String, BreakerStatus> {
+        [&self.database, &self.meilisearch].into_iter()
+            .map(|breaker| (breaker.name().to_string(), breaker.status()))
+            .collect()
+    }
+}
+
+/// Wraps a database adapter so that every query goes through a circuit breaker
+pub struct BreakerAdapter {
+    inner: Arc<Box<dyn DatabaseAdapter>>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl BreakerAdapter {
+    pub fn new(inner: Arc<Box<dyn DatabaseAdapter>>, breaker: Arc<CircuitBreaker>) -> Self {
+        Self { inner, breaker }
+    }
+}
+
+#[async_trait::async_trait]
+impl DatabaseAdapter for BreakerAdapter {
+    async fn fetch_all_records(&self, table: &str) -> Result<Vec<Value>> {
+        self.breaker.call(self.inner.fetch_all_records(table)).await
+    }
+
+    async fn fetch_records(&self, table: &str, columns: Option<&[String]>) -> Result<Vec<Value>> {
+        self.breaker.call(self.inner.fetch_records(table, columns)).await
+    }
+
+    fn fetch_records_stream<'a>(&'a self, table: &'a str, columns: Option<&'a [String]>) -> BoxStream<'a, Result<Value>> {
+        // The stream counts as one call, which fails with its first error
+        Box::pin(async_stream::try_stream! {
+            self.breaker.check()?;
+            let mut records = self.inner.fetch_records_stream(table, columns);
+            loop {
+                match records.try_next().await {
+                    Ok(Some(record)) => yield record,
+                    Ok(None) => {
+                        self.breaker.record(&Ok(()));
+                        break;
+                    }
+                    Err(e) => {
+                        let failure: Result<()> = Err(e);
+                        self.breaker.record(&failure);
+                        failure?;
+                    }
+                }
+            }
+        })
+    }
+
+    async fn fetch_records_ordered(&self, table: &str, columns: Option<&[String]>, order_by: &str) -> Result<Option<Vec<Value>>> {
+        self.breaker.call(self.inner.fetch_records_ordered(table, columns, order_by)).await
+    }
+
+    async fn fetch_records_since(&self, table: &str, columns: Option<&[String]>, column: &str, since: &Value) -> Result<Option<Vec<Value>>> {
+        self.breaker.call(self.inner.fetch_records_since(table, columns, column, since)).await
+    }
+
+    async fn get_all_tables(&self) -> Result<Vec<String>> {
+        self.breaker.call(self.inner.get_all_tables()).await
+    }
+
+    async fn get_table_columns(&self, table: &str) -> Result<Vec<(String, String, bool)>> {
+        self.breaker.call(self.inner.get_table_columns(table)).await
+    }
+
+    async fn get_primary_key(&self, table: &str) -> Result<String> {
+        self.breaker.call(self.inner.get_primary_key(table)).await
+    }
+
+    async fn get_all_views(&self) -> Result<Vec<String>> {
+        self.breaker.call(self.inner.get_all_views()).await
+    }
+
+    async fn get_table_schema(&self, table: &str) -> Result<Vec<(String, String)>> {
+        self.breaker.call(self.inner.get_table_schema(table)).await
+    }
+
+    async fn get_column_details(&self, table: &str) -> Result<Vec<ColumnInfo>> {
+        self.breaker.call(self.inner.get_column_details(table)).await
+    }
+
+    async fn count_records(&self, table: &str) -> Result<usize> {
+        self.breaker.call(self.inner.count_records(table)).await
+    }
+
+    async fn fetch_sample_records(&self, table: &str, limit: usize) -> Result<Vec<Value>> {
+        self.breaker.call(self.inner.fetch_sample_records(table, limit)).await
+    }
+
+    async fn fetch_random_records(&self, table: &str, limit: usize) -> Result<Vec<Value>> {
+        self.breaker.call(self.inner.fetch_random_records(table, limit)).await
+    }
+
+    async fn replication_lag(&self) -> Result<Option<Duration>> {
+        self.breaker.call(self.inner.replication_lag()).await
+    }
+
+    fn take_decode_errors(&self, table: &str) -> BTreeMap<String, usize> {
+        self.inner.take_decode_errors(table)
+    }
+}
+
+/// Wraps a Meilisearch client so that every request goes through a circuit
+/// breaker. Health checks and version probes bypass it: the former are how
+/// maintenance mode notices Meilisearch is back, the latter is diagnostic.
+pub struct BreakerMeilisearchClient {
+    inner: Arc<dyn MeilisearchClientTrait>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl BreakerMeilisearchClient {
+    pub fn new(inner: Arc<dyn MeilisearchClientTrait>, breaker: Arc<CircuitBreaker>) -> Self {
+        Self { inner, breaker }
+    }
+}
+
+#[async_trait::async_trait]
+impl MeilisearchClientTrait for BreakerMeilisearchClient {
+    async fn setup_index(&self, index_name: &str, settings: Settings, primary_key: Option<&str>) -> Result<()> {
+        self.breaker.call(self.inner.setup_index(index_name, settings, primary_key)).await
+    }
+
+    async fn get_all_documents(&self, index_name: &str) -> Result<Vec<Value>> {
+        self.breaker.call(self.inner.get_all_documents(index_name)).await
+    }
+
+    async fn get_all_documents_with_vectors(&self, index_name: &str) -> Result<Vec<Value>> {
+        self.breaker.call(self.inner.get_all_documents_with_vectors(index_name)).await
+    }
+
+    async fn get_all_documents_ordered(&self, index_name: &str, primary_key: &str) -> Result<Option<Vec<Value>>> {
+        self.breaker.call(self.inner.get_all_documents_ordered(index_name, primary_key)).await
+    }
+
+    async fn add_or_update_documents(&self, index_name: &str, documents: Vec<Value>, batch_size: Option<usize>) -> Result<()> {
+        self.breaker.call(self.inner.add_or_update_documents(index_name, documents, batch_size)).await
+    }
+
+    async fn delete_documents(&self, index_name: &str, ids: &[String], batch_size: Option<usize>) -> Result<()> {
+        self.breaker.call(self.inner.delete_documents(index_name, ids, batch_size)).await
+    }
+
+    async fn add_documents_checked(&self, index_name: &str, documents: Vec<Value>) -> Result<()> {
+        self.breaker.call(self.inner.add_documents_checked(index_name, documents)).await
+    }
+
+    async fn index_primary_key(&self, index_name: &str) -> Result<Option<String>> {
+        self.breaker.call(self.inner.index_primary_key(index_name)).await
+    }
+
+    async fn delete_index(&self, index_name: &str) -> Result<()> {
+        self.breaker.call(self.inner.delete_index(index_name)).await
+    }
+
+    async fn set_raw_settings(&self, index_name: &str, settings: &Value) -> Result<()> {
+        self.breaker.call(self.inner.set_raw_settings(index_name, settings)).await
+    }
+
+    async fn list_indexes(&self) -> Result<Vec<String>> {
+        self.breaker.call(self.inner.list_indexes()).await
+    }
+
+    async fn swap_indexes(&self, first: &str, second: &str) -> Result<()> {
+        self.breaker.call(self.inner.swap_indexes(first, second)).await
+    }
+
+    async fn wait_for_tasks(&self, index_name: &str) -> Result<()> {
+        self.breaker.call(self.inner.wait_for_tasks(index_name)).await
+    }
+
+    async fn get_document(&self, index_name: &str, id: &str) -> Result<Option<Value>> {
+        self.breaker.call(self.inner.get_document(index_name, id)).await
+    }
+
+    async fn health(&self) -> Result<()> {
+        self.inner.health().await
+    }
+
+    async fn version(&self) -> Result<Option<String>> {
+        self.inner.version().await
+    }
+}
@@ -1,4 +1,6 @@
-use clap::{Parser, Subcommand};
+use crate::report::ReportOrder;
+use crate::config::Config;
+use clap::{Args, Parser, Subcommand};
 use colored::Colorize;
 use std::path::PathBuf;
 
@@ -17,6 +19,17 @@ pub enum Commands {
         /// Path to the configuration file
         #[arg(short, long)]
         config: PathBuf,
+        /// Log a per-phase timing breakdown after every table sync
+        #[arg(long)]
+        profile: bool,
+        /// Show a live status line per table: changes of its last sync, its time and errors
+        #[arg(long)]
+        watch: bool,
+        /// Let syncs delete more than max_delete_ratio of a table's documents
+        #[arg(long)]
+        allow_mass_delete: bool,
+        #[command(flatten)]
+        overrides: Overrides,
     },
     /// Generate a configuration file from an existing database
     Generate {
@@ -35,6 +48,87 @@ pub enum Commands {
         /// Polling interval in seconds
         #[arg(short, long, default_value = "60")]
         poll_interval: u64,
+        /// Also generate entries for database views that have a detectable primary key
+        #[arg(long)]
+        include_views: bool,
+        /// TOML file of column roles (searchable, filterable, sortable, exclude) to apply to the generated tables
+        #[arg(long)]
+        rules: Option<PathBuf>,
+    },
+    /// Generate a configuration, validate it against the database and optionally run it
+    Init {
+        /// Database URL (e.g. sqlite://path/to/database.db)
+        #[arg(short, long)]
+        database_url: String,
+        /// Meilisearch host URL
+        #[arg(short, long)]
+        meilisearch_host: String,
+        /// Meilisearch API key (optional)
+        #[arg(short = 'k', long)]
+        meilisearch_key: Option<String>,
+        /// Output path for the configuration file
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Polling interval in seconds
+        #[arg(short, long, default_value = "60")]
+        poll_interval: u64,
+        /// Also generate entries for database views that have a detectable primary key
+        #[arg(long)]
+        include_views: bool,
+        /// TOML file of column roles (searchable, filterable, sortable, exclude) to apply to the generated tables
+        #[arg(long)]
+        rules: Option<PathBuf>,
+        /// Start the connector once the configuration has been validated
+        #[arg(long)]
+        run: bool,
+    },
+    /// Apply index settings from the configuration without syncing documents
+    ApplySettings {
+        /// Path to the configuration file
+        #[arg(short, long)]
+        config: PathBuf,
+        /// Only apply the settings of this table (defaults to all tables)
+        #[arg(short, long)]
+        table: Option<String>,
+    },
+    /// Sync every enabled table once and print a report per table
+    SyncOnce {
+        /// Path to the configuration file
+        #[arg(short, long)]
+        config: PathBuf,
+        /// Order of the tables in the output
+        #[arg(long, value_enum, default_value_t)]
+        sort_by: ReportOrder,
+        /// Work out the changes without writing anything to Meilisearch
+        #[arg(long)]
+        dry_run: bool,
+        /// Write the changes of a dry run to this JSON file
+        #[arg(short, long, requires = "dry_run")]
+        output: Option<PathBuf>,
+        /// Only write document ids to the output file, without the documents
+        #[arg(long, requires = "output")]
+        summary_only: bool,
+        #[command(flatten)]
+        overrides: Overrides,
+    },
+    /// Compute the changes a sync would make and write them to a JSON file, to
+    /// be reviewed and applied later
+    Plan {
+        /// Path to the configuration file
+        #[arg(short, long)]
+        config: PathBuf,
+        /// File to write the plan to
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Make the changes of a plan written by `plan`
+    Apply {
+        /// Path to the configuration file
+        #[arg(short, long)]
+        config: PathBuf,
+        /// Plan file to apply
+        #[arg(short, long)]
+        plan: PathBuf,
     },
     /// Validate a configuration file
     Validate {
@@ -42,13 +136,98 @@ pub enum Commands {
         #[arg(short, long)]
         config: PathBuf,
     },
+    /// Rebuild indexes as versioned copies and swap them in once complete
+    Cutover {
+        /// Path to the configuration file
+        #[arg(short, long)]
+        config: PathBuf,
+        /// Only cut over this table (defaults to all tables with use_versioned_indexes)
+        #[arg(short, long)]
+        table: Option<String>,
+        /// Keep the versioned index holding the previous documents, to swap back to
+        #[arg(long)]
+        keep_previous: bool,
+    },
+    /// Compare a random sample of database rows with their indexed documents
+    Verify {
+        /// Path to the configuration file
+        #[arg(short, long)]
+        config: PathBuf,
+        /// Only verify this table (defaults to all tables)
+        #[arg(short, long)]
+        table: Option<String>,
+        /// Number of rows to sample per table
+        #[arg(short, long, default_value = "10")]
+        sample: usize,
+    },
+    /// Show a table's columns, primary key, row count and sample rows as the connector reads them
+    Inspect {
+        /// Database URL (e.g. sqlite://path/to/database.db)
+        #[arg(short, long)]
+        database_url: String,
+        /// Table or view to inspect
+        #[arg(short, long)]
+        table: String,
+        /// Number of sample rows to print
+        #[arg(short, long, default_value = "5")]
+        limit: usize,
+    },
+    /// Print a starter configuration listing every option with a comment
+    Template {
+        /// Write the template to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print the JSON Schema of the configuration file
+    Schema {
+        /// Write the schema to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Performance knobs of `run` and `sync-once`, winning over both the file
+/// and the environment
+#[derive(Args, Debug, Default, Clone, Copy)]
+pub struct Overrides {
+    /// Override max_concurrent_batches from the configuration
+    #[arg(long)]
+    pub max_concurrent_batches: Option<usize>,
+    /// Override max_concurrent_queries, the most tables querying the database at once
+    #[arg(long)]
+    pub max_concurrent_tables: Option<u32>,
+    /// Override document_batch_size from the configuration
+    #[arg(long)]
+    pub batch_size: Option<usize>,
+}
+
+impl Overrides {
+    /// Apply the flags that were given to a loaded configuration
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(max_concurrent_batches) = self.max_concurrent_batches {
+            config.database.max_concurrent_batches = max_concurrent_batches;
+        }
+        if let Some(max_concurrent_tables) = self.max_concurrent_tables {
+            config.database.max_concurrent_queries = Some(max_concurrent_tables);
+        }
+        if let Some(batch_size) = self.batch_size {
+            config.database.document_batch_size = batch_size;
+        }
+    }
 }
 
 pub fn print_banner() {
     println!("\n{}", "Meilisearch SQL Connector".bold());
     println!("{}", "A connector that syncs your SQL database with Meilisearch".italic());
-    println!("{}", "Usage: meilisearch-sql-connector run --config config.toml".bold());
+    println!("{}", "Usage: meilisearch-sql-connector run --config config.toml [--allow-mass-delete] [--batch-size N] [--max-concurrent-batches N] [--max-concurrent-tables N]".bold());
     println!("{}", "Usage: meilisearch-sql-connector generate --database-url sqlite://path/to/database.db --meilisearch-host http://localhost:7701 [--meilisearch-key YOUR_KEY] --output config.toml --poll-interval 60".bold());
+    println!("{}", "Usage: meilisearch-sql-connector init --database-url sqlite://path/to/database.db --meilisearch-host http://localhost:7701 --output config.toml [--run]".bold());
     println!("{}", "Usage: meilisearch-sql-connector validate --config config.toml".bold());
+    println!("{}", "Usage: meilisearch-sql-connector apply-settings --config config.toml [--table TABLE]".bold());
+    println!("{}", "Usage: meilisearch-sql-connector cutover --config config.toml [--table TABLE] [--keep-previous]".bold());
+    println!("{}", "Usage: meilisearch-sql-connector verify --config config.toml [--table TABLE] [--sample 10]".bold());
+    println!("{}", "Usage: meilisearch-sql-connector inspect --database-url sqlite://path/to/database.db --table TABLE [--limit 5]".bold());
+    println!("{}", "Usage: meilisearch-sql-connector template [--output config.toml]".bold());
+    println!("{}", "Usage: meilisearch-sql-connector schema [--output config.schema.json]".bold());
     println!();
 }
@@ -2,40 +2,327 @@ use meilisearch_sdk::settings::Settings;
 use async_trait::async_trait;
 use serde_json::Value;
 
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
 use crate::{
-    error::Result,
+    error::{ConnectorError, Result},
     meilisearch::MeilisearchClientTrait,
-    database::DatabaseAdapter,
 };
+use crate::database::{compare_primary_keys, DatabaseAdapter};
+
+// --- In-memory Meilisearch Client ---
+
+#[derive(Debug, Default)]
+struct InMemoryIndex {
+    primary_key: Option<String>,
+    settings: Option<Settings>,
+    raw_settings: Option<Value>,
+    // Documents by primary key string, like Meilisearch document ids
+    documents: BTreeMap<String, Value>,
+}
 
-// --- Mock Meilisearch Client ---
-pub struct MockMeilisearchClient;
+/// A `MeilisearchClientTrait` keeping indices in memory, so tests can check
+/// what a sync actually left in the index. Adds replace documents with the
+/// same primary key, deletes remove them, and `get_all_documents` returns
+/// the current documents ordered by id.
+#[derive(Debug, Default)]
+pub struct InMemoryMeilisearchClient {
+    indices: Mutex<HashMap<String, InMemoryIndex>>,
+    version: Option<String>,
+}
 
-impl MockMeilisearchClient {
+impl InMemoryMeilisearchClient {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Report `version` as the server version
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_string());
+        self
+    }
+
+    /// IDs of the documents in an index, in order
+    pub fn document_ids(&self, index_name: &str) -> Vec<String> {
+        self.indices.lock().unwrap().get(index_name)
+            .map(|index| index.documents.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Settings last applied to an index by `setup_index`
+    pub fn settings(&self, index_name: &str) -> Option<Settings> {
+        self.indices.lock().unwrap().get(index_name).and_then(|index| index.settings.clone())
+    }
+
+    /// Raw settings last applied to an index
+    pub fn raw_settings(&self, index_name: &str) -> Option<Value> {
+        self.indices.lock().unwrap().get(index_name).and_then(|index| index.raw_settings.clone())
+    }
+}
+
+// String form of a primary key value, as used for document ids
+fn document_id(document: &Value, primary_key: &str) -> Result<String> {
+    match document.get(primary_key) {
+        Some(Value::String(id)) => Ok(id.clone()),
+        Some(id @ Value::Number(_)) => Ok(id.to_string()),
+        _ => Err(ConnectorError::Meilisearch(format!("Document has no valid primary key '{}'", primary_key))),
+    }
+}
+
+// Like Meilisearch, an index without a primary key takes the only field of
+// the first document whose name ends with `id`, and fails without one or
+// with several
+fn infer_primary_key(document: Option<&Value>) -> Result<String> {
+    let candidates: Vec<&String> = document.and_then(Value::as_object).into_iter()
+        .flat_map(|document| document.keys())
+        .filter(|field| field.to_lowercase().ends_with("id"))
+        .collect();
+    match candidates.as_slice() {
+        [primary_key] => Ok(primary_key.to_string()),
+        _ => Err(ConnectorError::Meilisearch(format!("Could not infer a primary key from {:?}", candidates))),
     }
 }
 
 #[async_trait]
-impl MeilisearchClientTrait for MockMeilisearchClient {
-    async fn setup_index(&self, _index_name: &str, _settings: Settings, _primary_key: Option<&str>) -> Result<()> {
+impl MeilisearchClientTrait for InMemoryMeilisearchClient {
+    async fn setup_index(&self, index_name: &str, settings: Settings, primary_key: Option<&str>) -> Result<()> {
+        let mut indices = self.indices.lock().unwrap();
+        let index = indices.entry(index_name.to_string()).or_default();
+        if index.primary_key.is_none() {
+            index.primary_key = primary_key.map(str::to_string);
+        }
+        index.settings = Some(settings);
         Ok(())
     }
 
-    async fn get_all_documents(&self, _index_name: &str) -> Result<Vec<Value>> {
-        Ok(vec![])
+    async fn index_primary_key(&self, index_name: &str) -> Result<Option<String>> {
+        Ok(self.indices.lock().unwrap().get(index_name).and_then(|index| index.primary_key.clone()))
     }
 
-    async fn add_or_update_documents(&self, _index_name: &str, _documents: Vec<Value>) -> Result<()> {
+    async fn version(&self) -> Result<Option<String>> {
+        Ok(self.version.clone())
+    }
+
+    async fn delete_index(&self, index_name: &str) -> Result<()> {
+        self.indices.lock().unwrap().remove(index_name);
+        Ok(())
+    }
+
+    async fn set_raw_settings(&self, index_name: &str, settings: &Value) -> Result<()> {
+        self.indices.lock().unwrap().entry(index_name.to_string()).or_default().raw_settings = Some(settings.clone());
+        Ok(())
+    }
+
+    async fn list_indexes(&self) -> Result<Vec<String>> {
+        let mut names: Vec<String> = self.indices.lock().unwrap().keys().cloned().collect();
+        names.sort();
+        Ok(names)
+    }
+
+    async fn swap_indexes(&self, first: &str, second: &str) -> Result<()> {
+        let mut indices = self.indices.lock().unwrap();
+        if !indices.contains_key(first) || !indices.contains_key(second) {
+            return Err(ConnectorError::Meilisearch(format!("Indexes {} and {} must both exist to be swapped", first, second)));
+        }
+        let a = indices.remove(first).unwrap_or_default();
+        let b = indices.remove(second).unwrap_or_default();
+        indices.insert(first.to_string(), b);
+        indices.insert(second.to_string(), a);
+        Ok(())
+    }
+
+    async fn get_document(&self, index_name: &str, id: &str) -> Result<Option<Value>> {
+        Ok(self.indices.lock().unwrap().get(index_name).and_then(|index| index.documents.get(id).cloned()))
+    }
+
+    async fn get_all_documents(&self, index_name: &str) -> Result<Vec<Value>> {
+        Ok(self.indices.lock().unwrap().get(index_name)
+            .map(|index| index.documents.values().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn get_all_documents_ordered(&self, index_name: &str, primary_key: &str) -> Result<Option<Vec<Value>>> {
+        let mut documents = self.get_all_documents(index_name).await?;
+        documents.sort_by(|a, b| compare_primary_keys(&a[primary_key], &b[primary_key]));
+        Ok(Some(documents))
+    }
+
+    async fn add_or_update_documents(&self, index_name: &str, documents: Vec<Value>, _batch_size: Option<usize>) -> Result<()> {
+        let mut indices = self.indices.lock().unwrap();
+        let index = indices.entry(index_name.to_string()).or_default();
+        let primary_key = match &index.primary_key {
+            Some(primary_key) => primary_key.clone(),
+            None => {
+                let primary_key = infer_primary_key(documents.first())?;
+                index.primary_key = Some(primary_key.clone());
+                primary_key
+            }
+        };
+        for document in documents {
+            index.documents.insert(document_id(&document, &primary_key)?, document);
+        }
         Ok(())
     }
 
-    async fn delete_documents(&self, _index_name: &str, _ids: &[String]) -> Result<()> {
+    async fn delete_documents(&self, index_name: &str, ids: &[String], _batch_size: Option<usize>) -> Result<()> {
+        if let Some(index) = self.indices.lock().unwrap().get_mut(index_name) {
+            for id in ids {
+                index.documents.remove(id);
+            }
+        }
         Ok(())
     }
 }
 
+// --- In-memory Database Adapter ---
+
+#[derive(Debug, Default)]
+struct InMemoryTable {
+    primary_key: String,
+    rows: Vec<Value>,
+}
+
+/// A `DatabaseAdapter` over tables kept in memory, whose rows tests can
+/// insert, update and delete between syncs. Column types are inferred from
+/// the JSON values of the rows.
+#[derive(Debug, Default)]
+pub struct InMemoryDatabaseAdapter {
+    tables: Mutex<HashMap<String, InMemoryTable>>,
+    replication_lag: Mutex<Option<Duration>>,
+}
+
+impl InMemoryDatabaseAdapter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a table, replacing any table of the same name
+    pub fn with_table(self, table: &str, primary_key: &str, rows: Vec<Value>) -> Self {
+        self.tables.lock().unwrap().insert(table.to_string(), InMemoryTable {
+            primary_key: primary_key.to_string(),
+            rows,
+        });
+        self
+    }
+
+    /// Append a row to a table
+    pub fn insert(&self, table: &str, row: Value) {
+        self.tables.lock().unwrap().entry(table.to_string()).or_default().rows.push(row);
+    }
+
+    /// Replace the row whose primary key is `id`, returning whether it existed
+    pub fn update(&self, table: &str, id: &Value, row: Value) -> bool {
+        let mut tables = self.tables.lock().unwrap();
+        let Some(table) = tables.get_mut(table) else {
+            return false;
+        };
+        let primary_key = &table.primary_key;
+        match table.rows.iter_mut().find(|r| r.get(primary_key) == Some(id)) {
+            Some(existing) => {
+                *existing = row;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Delete the row whose primary key is `id`, returning whether it existed
+    pub fn delete(&self, table: &str, id: &Value) -> bool {
+        let mut tables = self.tables.lock().unwrap();
+        let Some(table) = tables.get_mut(table) else {
+            return false;
+        };
+        let before = table.rows.len();
+        let primary_key = table.primary_key.clone();
+        table.rows.retain(|r| r.get(&primary_key) != Some(id));
+        table.rows.len() < before
+    }
+
+    /// Report the database as a replica `lag` behind its primary
+    pub fn set_replication_lag(&self, lag: Option<Duration>) {
+        *self.replication_lag.lock().unwrap() = lag;
+    }
+
+    fn with_table_data<T>(&self, table: &str, f: impl FnOnce(&InMemoryTable) -> T) -> Result<T> {
+        self.tables.lock().unwrap().get(table)
+            .map(f)
+            .ok_or_else(|| ConnectorError::Database(format!("no such table: {}", table)))
+    }
+}
+
+// SQL type of a JSON value, as the SQLite adapter would have read it
+fn sql_type(value: &Value) -> &'static str {
+    match value {
+        Value::Bool(_) => "BOOLEAN",
+        Value::Number(n) if n.is_f64() => "REAL",
+        Value::Number(_) => "INTEGER",
+        _ => "TEXT",
+    }
+}
+
+#[async_trait]
+impl DatabaseAdapter for InMemoryDatabaseAdapter {
+    async fn fetch_all_records(&self, table: &str) -> Result<Vec<Value>> {
+        self.with_table_data(table, |t| t.rows.clone())
+    }
+
+    async fn fetch_records_ordered(&self, table: &str, columns: Option<&[String]>, order_by: &str) -> Result<Option<Vec<Value>>> {
+        let mut records = self.fetch_records(table, columns).await?;
+        records.sort_by(|a, b| compare_primary_keys(&a[order_by], &b[order_by]));
+        Ok(Some(records))
+    }
+
+    async fn fetch_records_since(&self, table: &str, columns: Option<&[String]>, column: &str, since: &Value) -> Result<Option<Vec<Value>>> {
+        let mut records = self.fetch_records(table, columns).await?;
+        // Nulls and values of another type than `since` compare like SQLite's
+        records.retain(|record| compare_primary_keys(&record[column], since).is_ge());
+        Ok(Some(records))
+    }
+
+    async fn get_all_tables(&self) -> Result<Vec<String>> {
+        let mut tables: Vec<String> = self.tables.lock().unwrap().keys().cloned().collect();
+        tables.sort();
+        Ok(tables)
+    }
+
+    async fn get_table_columns(&self, table: &str) -> Result<Vec<(String, String, bool)>> {
+        self.with_table_data(table, |t| {
+            // Columns of all rows, typed by their first non-null value
+            let mut columns: Vec<(String, String, bool)> = Vec::new();
+            for row in &t.rows {
+                for (name, value) in row.as_object().into_iter().flatten() {
+                    match columns.iter_mut().find(|(column, _, _)| column == name) {
+                        Some(column) if column.1.is_empty() && !value.is_null() => column.1 = sql_type(value).to_string(),
+                        Some(_) => {}
+                        None => {
+                            let type_ = if value.is_null() { "" } else { sql_type(value) };
+                            columns.push((name.clone(), type_.to_string(), *name == t.primary_key));
+                        }
+                    }
+                }
+            }
+            for column in columns.iter_mut().filter(|(_, type_, _)| type_.is_empty()) {
+                column.1 = "TEXT".to_string();
+            }
+            columns
+        })
+    }
+
+    async fn get_primary_key(&self, table: &str) -> Result<String> {
+        self.with_table_data(table, |t| t.primary_key.clone())
+    }
+
+    async fn count_records(&self, table: &str) -> Result<usize> {
+        self.with_table_data(table, |t| t.rows.len())
+    }
+
+    async fn replication_lag(&self) -> Result<Option<Duration>> {
+        Ok(*self.replication_lag.lock().unwrap())
+    }
+}
+
 // --- Non-mockall implementation for MockSqliteAdapter ---
 #[cfg(not(feature = "mockall"))]
 pub struct MockSqliteAdapter;
@@ -66,10 +353,6 @@ impl DatabaseAdapter for MockSqliteAdapter {
         Ok(vec![])
     }
 
-    async fn fetch_record(&self, _table: &str, _id: &str) -> Result<Value> {
-        Ok(Value::Null)
-    }
-
     async fn get_table_schema(&self, _table: &str) -> Result<Vec<(String, String)>> {
         Ok(vec![("id".to_string(), "INTEGER".to_string())])
     }
@@ -95,7 +378,6 @@ pub mod mock_db {
             async fn get_table_columns(&self, table: &str) -> Result<Vec<(String, String, bool)>>;
             async fn get_primary_key(&self, table: &str) -> Result<String>;
             async fn fetch_all_records(&self, table: &str) -> Result<Vec<Value>>;
-            async fn fetch_record(&self, table: &str, id: &str) -> Result<Value>;
             async fn get_table_schema(&self, table: &str) -> Result<Vec<(String, String)>>;
         }
     }
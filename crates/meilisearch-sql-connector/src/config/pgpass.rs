@@ -0,0 +1,113 @@
+use crate::error::{ConnectorError, Result};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+use url::Url;
+
+// libpq's defaults for the parts of a connection a URL may leave out
+const DEFAULT_HOST: &str = "localhost";
+const DEFAULT_PORT: u16 = 5432;
+
+/// Fill in the password of a Postgres URL from a pgpass file when the URL
+/// has none, as libpq does. `file` defaults to `PGPASSFILE`, then
+/// `~/.pgpass`; a missing default file is not an error.
+pub(super) fn fill_password(url: &str, file: Option<&str>) -> Result<String> {
+    let mut parsed = Url::parse(url)
+        .map_err(|e| ConnectorError::Config(format!("Invalid database URL: {}", e)))?;
+    if parsed.password().is_some() {
+        return Ok(url.to_string());
+    }
+    let user = percent_decode(parsed.username());
+    if user.is_empty() {
+        debug!("Not looking up a pgpass password, the database URL has no user");
+        return Ok(url.to_string());
+    }
+    let Some(path) = file.map(PathBuf::from).or_else(default_path) else {
+        return Ok(url.to_string());
+    };
+    if file.is_none() && !path.exists() {
+        debug!("No pgpass file at {}", path.display());
+        return Ok(url.to_string());
+    }
+    check_permissions(&path)?;
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| ConnectorError::Config(format!("Could not read pgpass file {}: {}", path.display(), e)))?;
+
+    let host = parsed.host_str().unwrap_or(DEFAULT_HOST).to_string();
+    let port = parsed.port().unwrap_or(DEFAULT_PORT).to_string();
+    // libpq's default database is the one named after the user
+    let database = match percent_decode(parsed.path().trim_start_matches('/')) {
+        name if name.is_empty() => user.clone(),
+        name => name,
+    };
+    match find_password(&contents, &host, &port, &database, &user) {
+        Some(password) => {
+            debug!("Using the password of {}@{}:{}/{} from {}", user, host, port, database, path.display());
+            parsed.set_password(Some(&password))
+                .map_err(|_| ConnectorError::Config("Invalid database password in pgpass file".to_string()))?;
+            Ok(parsed.to_string())
+        }
+        None => {
+            debug!("No entry of {} matches {}@{}:{}/{}", path.display(), user, host, port, database);
+            Ok(url.to_string())
+        }
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    std::env::var_os("PGPASSFILE").map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".pgpass")))
+}
+
+// Like libpq, refuse a file other users could read
+#[cfg(unix)]
+fn check_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = std::fs::metadata(path)
+        .map_err(|e| ConnectorError::Config(format!("Could not read pgpass file {}: {}", path.display(), e)))?
+        .permissions()
+        .mode();
+    if mode & 0o077 != 0 {
+        return Err(ConnectorError::Config(format!(
+            "pgpass file {} has group or world access; restrict it with chmod 600", path.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+// Password of the first `host:port:database:user:password` line matching
+// the connection, where `*` matches anything
+fn find_password(contents: &str, host: &str, port: &str, database: &str, user: &str) -> Option<String> {
+    contents.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(split_fields)
+        .filter(|fields| fields.len() >= 5)
+        .find(|fields| {
+            [host, port, database, user].iter().zip(fields)
+                .all(|(value, field)| field == "*" || field == value)
+        })
+        .map(|mut fields| fields.swap_remove(4))
+}
+
+// Fields separated by `:`, where `\:` and `\\` stand for a literal colon
+// and backslash. The password is the rest of the line.
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' if fields.len() < 5 => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+fn percent_decode(value: &str) -> String {
+    percent_encoding::percent_decode_str(value).decode_utf8_lossy().into_owned()
+}
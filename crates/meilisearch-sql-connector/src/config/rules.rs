@@ -0,0 +1,93 @@
+use super::{glob_match, TableConfig};
+use crate::error::{ConnectorError, Result};
+use colored::Colorize;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Column roles `generate --rules` applies to the tables it generates, so
+/// similar databases get the same configuration without editing each one
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GenerateRules {
+    #[serde(default)]
+    pub rules: Vec<ColumnRule>,
+}
+
+/// Roles of the columns matching a pattern
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnRule {
+    /// Glob matching column names
+    pub column: String,
+    /// Glob limiting the rule to some tables, every table when unset
+    #[serde(default)]
+    pub table: Option<String>,
+    pub roles: Vec<ColumnRole>,
+}
+
+/// What a rule makes of a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnRole {
+    Searchable,
+    Filterable,
+    Sortable,
+    /// Left out of `fields_to_index`, whatever other rules say
+    Exclude,
+}
+
+impl ColumnRule {
+    fn matches(&self, table: &str, column: &str) -> bool {
+        glob_match(&self.column, column) && self.table.as_deref().is_none_or(|pattern| glob_match(pattern, table))
+    }
+}
+
+impl GenerateRules {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| ConnectorError::Config(e.to_string()))?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    /// Give the columns of `tables` their roles, in column order. Returns
+    /// the column patterns of the rules that matched no column.
+    pub fn apply(&self, tables: &mut [TableConfig]) -> Vec<String> {
+        let mut used = vec![false; self.rules.len()];
+        for table in tables {
+            let mut fields = Vec::new();
+            let mut searchable = Vec::new();
+            for column in std::mem::take(&mut table.fields_to_index) {
+                let mut roles = Vec::new();
+                for (rule, used) in self.rules.iter().zip(&mut used) {
+                    if rule.matches(&table.name, &column) {
+                        *used = true;
+                        roles.extend(&rule.roles);
+                    }
+                }
+                if roles.contains(&ColumnRole::Exclude) {
+                    if column != table.primary_key {
+                        continue;
+                    }
+                    eprintln!("{} Not excluding column '{}' of table '{}', it is the primary key",
+                              "Warning:".yellow().bold(), column, table.name);
+                }
+                if roles.contains(&ColumnRole::Searchable) {
+                    searchable.push(column.clone());
+                }
+                if roles.contains(&ColumnRole::Filterable) && !table.filterable_attributes.contains(&column) {
+                    table.filterable_attributes.push(column.clone());
+                }
+                if roles.contains(&ColumnRole::Sortable) && !table.sortable_attributes.contains(&column) {
+                    table.sortable_attributes.push(column.clone());
+                }
+                fields.push(column);
+            }
+            table.fields_to_index = fields;
+            if !searchable.is_empty() {
+                table.searchable_attributes = Some(searchable);
+            }
+        }
+        self.rules.iter().zip(used)
+            .filter(|(_, used)| !used)
+            .map(|(rule, _)| rule.column.clone())
+            .collect()
+    }
+}
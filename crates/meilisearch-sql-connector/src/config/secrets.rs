@@ -0,0 +1,99 @@
+use crate::error::{ConnectorError, Result};
+
+// Schemes of secret stores the connector doesn't ship a resolver for. A
+// reference to one of them without a registered resolver is an error rather
+// than being used as a literal value.
+const EXTERNAL_SCHEMES: &[&str] = &["vault", "aws-sm"];
+
+/// Resolves secret references of one scheme, e.g. `env:MEILI_API_KEY`.
+///
+/// The configuration can use a reference as the whole value of any string
+/// field. It is replaced by the resolved secret when the file is loaded.
+pub trait SecretResolver: Send + Sync {
+    /// Prefix of the references this resolver handles, without the colon
+    fn scheme(&self) -> &str;
+
+    /// Look up `reference`, the part after `<scheme>:`
+    fn resolve(&self, reference: &str) -> Result<String>;
+}
+
+/// Reads `env:NAME` references from environment variables
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvSecretResolver;
+
+impl SecretResolver for EnvSecretResolver {
+    fn scheme(&self) -> &str {
+        "env"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<String> {
+        std::env::var(reference).map_err(|_| ConnectorError::Config(format!(
+            "Environment variable {} referenced by the configuration is not set", reference
+        )))
+    }
+}
+
+/// The resolvers used when loading a configuration, by scheme. The default
+/// set only contains `EnvSecretResolver`.
+pub struct SecretResolvers {
+    resolvers: Vec<Box<dyn SecretResolver>>,
+}
+
+impl Default for SecretResolvers {
+    fn default() -> Self {
+        Self::empty().with_resolver(EnvSecretResolver)
+    }
+}
+
+impl SecretResolvers {
+    /// A set without any resolver, every value is used as written
+    pub fn empty() -> Self {
+        Self { resolvers: Vec::new() }
+    }
+
+    /// Add a resolver, replacing any registered for the same scheme
+    pub fn with_resolver(mut self, resolver: impl SecretResolver + 'static) -> Self {
+        self.resolvers.retain(|r| r.scheme() != resolver.scheme());
+        self.resolvers.push(Box::new(resolver));
+        self
+    }
+
+    /// The secret `value` refers to, or `None` if it isn't a reference to a
+    /// registered scheme (e.g. a plain string or a `sqlite://` URL)
+    pub fn resolve(&self, value: &str) -> Result<Option<String>> {
+        let Some((scheme, reference)) = value.split_once(':') else {
+            return Ok(None);
+        };
+        match self.resolvers.iter().find(|r| r.scheme() == scheme) {
+            Some(resolver) => resolver.resolve(reference).map(Some),
+            None if EXTERNAL_SCHEMES.contains(&scheme) => Err(ConnectorError::Config(format!(
+                "No secret resolver is registered for '{}:' references", scheme
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Replace every string in a parsed configuration that is a secret
+    /// reference with the secret
+    pub(crate) fn resolve_toml(&self, value: &mut toml::Value) -> Result<()> {
+        match value {
+            toml::Value::String(s) => {
+                if let Some(secret) = self.resolve(s)? {
+                    *s = secret;
+                }
+            }
+            toml::Value::Array(items) => {
+                for item in items {
+                    self.resolve_toml(item)?;
+                }
+            }
+            toml::Value::Table(table) => {
+                for (_, item) in table.iter_mut() {
+                    self.resolve_toml(item)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
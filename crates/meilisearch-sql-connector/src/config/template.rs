@@ -0,0 +1,179 @@
+use super::{Config, DatabaseConfig, MeilisearchConfig, TableConfig};
+
+// Every key of a section, in the order it is written: (key, comment,
+// example). Keys with an example are optional and written commented out with
+// it; the others get their value in the starter configuration, i.e. their
+// default. `test_config_template` checks these lists against the schema.
+type Keys = &'static [(&'static str, &'static str, Option<&'static str>)];
+
+const MEILISEARCH_KEYS: Keys = &[
+    ("host", "URL of the Meilisearch server", None),
+    ("api_key", "API key, or a secret reference such as env:MEILI_MASTER_KEY", Some("\"env:MEILI_MASTER_KEY\"")),
+    ("proxy_url", "HTTP(S) proxy requests to Meilisearch go through, with its credentials if needed; NO_PROXY still applies", Some("\"http://proxy.internal:3128\"")),
+    ("inter_batch_delay_ms", "Pause between consecutive document batches, 0 to disable", None),
+    ("health_check_interval_seconds", "Check Meilisearch's health this often while running, 0 to disable", None),
+    ("maintenance_after_failures", "Withhold writes after this many failed health checks in a row, until one passes", None),
+    ("circuit_breaker_failures", "Pause requests after this many failed ones in a row, 0 to disable", None),
+    ("circuit_breaker_cooldown_seconds", "How long requests stay paused before one is tried again", None),
+    ("document_page_size", "Documents fetched per request when reading an index to compare it with the tables", None),
+];
+
+const DATABASE_KEYS: Keys = &[
+    ("type", "Database type: sqlite, postgres or mysql", None),
+    ("connection_string", "Database URL or SQLite file path", None),
+    ("host", "Discrete connection settings, an alternative to connection_string", Some("\"localhost\"")),
+    ("port", "", Some("5432")),
+    ("user", "", Some("\"connector\"")),
+    ("password", "Like every string, it can be a secret reference such as env:DB_PASSWORD", Some("\"env:DB_PASSWORD\"")),
+    ("database_name", "", Some("\"app\"")),
+    ("use_pgpass", "Look up a Postgres password left out of the settings in a pgpass file", None),
+    ("pgpass_file", "That file, PGPASSFILE or ~/.pgpass by default", Some("\"/etc/connector/pgpass\"")),
+    ("read_connection_string", "Read rows from this database instead, e.g. a read replica", Some("\"postgres://connector@replica.internal/app\"")),
+    ("primary_connection_string", "Check deletions against this database when rows are read elsewhere", Some("\"postgres://connector@primary.internal/app\"")),
+    ("max_replica_lag_seconds", "Postpone deletions while the replica rows are read from lags more than this", Some("30")),
+    ("create_if_missing", "Create an empty SQLite database when the file doesn't exist, instead of failing", None),
+    ("poll_interval_seconds", "How often every table is checked for changes", None),
+    ("active_hours", "Time of day, in UTC, syncs may run in, e.g. off-peak hours", Some("\"22:00-06:00\"")),
+    ("connection_pool_size", "Number of database connections in the pool", None),
+    ("max_concurrent_queries", "Most table queries running at once across all tables, connection_pool_size by default", Some("4")),
+    ("max_concurrent_batches", "Most document batches sent to Meilisearch at once", None),
+    ("document_batch_size", "Documents per batch", None),
+    ("ndjson_upload", "Upload batches as NDJSON instead of a JSON array, faster for large initial loads", None),
+    ("upload_format", "json, or csv to send batches of flat documents as CSV", None),
+    ("large_int_as_string", "Emit integers beyond large_int_threshold as strings", None),
+    ("large_int_threshold", "2^53, the largest integer JavaScript represents exactly", None),
+    ("strict_decoding", "Fail syncs with column values that can't be decoded instead of indexing them as null", None),
+    ("on_transform_error", "skip documents that can't be transformed, e.g. too large ones, or fail to fail the table's sync", None),
+    ("dead_letter_path", "NDJSON file documents Meilisearch refuses are written to, isolated from the rest of their batch", Some("\"rejected.ndjson\"")),
+    ("warn_field_bytes", "Warn about document fields larger than this many bytes", Some("1000000")),
+    ("startup_ramp_seconds", "Delay the initial sync of the k-th table by k times this many seconds", Some("2")),
+    ("durable_queue_path", "SQLite file buffering document operations until Meilisearch accepts them", Some("\"queue.db\"")),
+    ("sync_retries", "Retry a failed table sync this many times before the next poll", None),
+    ("sync_retry_delay_seconds", "Delay between those retries", None),
+    ("db_connect_retries", "Retry the initial database connection this many times", None),
+    ("db_connect_retry_delay_seconds", "Delay before the first retry, doubled after each attempt up to 60s", None),
+    ("operation_order", "delete_then_add, or add_then_delete to add new documents before removing stale ones", None),
+    ("missing_attribute_handling", "warn, or error to refuse to start when searchable_attributes name unknown fields", None),
+    ("max_documents_per_sync", "Cap on the adds and deletes of one table sync, the rest waits for the next one", Some("10000")),
+    ("max_delete_ratio", "Refuse a sync's deletions beyond this fraction of the indexed documents", Some("0.5")),
+    ("auto_discover", "Sync tables created after startup, checked every poll interval", None),
+    ("table_pattern", "Glob limiting which tables auto_discover picks up", Some("\"tenant_*\"")),
+    ("heartbeat_interval_seconds", "Log a summary of every table's last sync this often, 0 to disable", None),
+    ("circuit_breaker_failures", "Pause queries after this many failed ones in a row, 0 to disable", None),
+    ("circuit_breaker_cooldown_seconds", "How long queries stay paused before one is tried again", None),
+];
+
+const TABLE_KEYS: Keys = &[
+    ("name", "Table or view to sync, or a pattern with match = \"glob\" or \"regex\"", None),
+    ("match", "exact, glob or regex; pattern blocks apply to every matching table", Some("\"glob\"")),
+    ("primary_key", "Column identifying documents, integer or string", None),
+    ("infer_primary_key", "Leave primary_key out and let Meilisearch infer it from the documents", None),
+    ("index_name", "Meilisearch index, the table name by default; patterns can use {table}", Some("\"users\"")),
+    ("fields_to_index", "Columns read and indexed, empty for all of them", None),
+    ("exclude_fields", "Columns never indexed, whatever fields_to_index says", Some("[\"password_hash\"]")),
+    ("filter", "SQL condition rows must meet to be indexed, inserted as-is into a WHERE clause", Some("\"published = 1\"")),
+    ("field_map", "Attribute names of columns in the documents, other columns keep theirs", Some("{ usr_nm = \"username\" }")),
+    ("watch_for_changes", "Required; every enabled table is currently polled whatever its value", None),
+    ("searchable_attributes", "Fields searched, most important first", Some("[\"name\", \"email\"]")),
+    ("filterable_attributes", "Fields search results can be filtered on", Some("[\"status\"]")),
+    ("sortable_attributes", "Fields search results can be sorted on", Some("[\"created_at\"]")),
+    ("ranking_rules", "", Some("[\"words\", \"typo\", \"proximity\", \"attribute\", \"sort\", \"exactness\"]")),
+    ("typo_tolerance", "Turn typo tolerance off for this index", Some("{ enabled = false }")),
+    ("search_cutoff_ms", "Stop searches after this many milliseconds and return the results found so far", Some("150")),
+    ("timestamp_fields", "Date/time columns that also get a sortable unix epoch companion field", None),
+    ("timestamp_suffix", "Suffix of those epoch fields", None),
+    ("enabled", "Set to false to skip this table without deleting its block", Some("false")),
+    ("cron", "Sync on this cron schedule, in UTC, instead of every poll_interval_seconds", Some("\"0 2 * * *\"")),
+    ("active_hours", "This table's own active_hours, overriding the database's", Some("\"00:00-06:00\"")),
+    ("constant_fields", "Fields added to every document of this table", Some("{ source = \"legacy_db\" }")),
+    ("constant_fields_override", "Let constant fields replace columns of the same name", None),
+    ("reindex_on_primary_key_change", "Delete and rebuild the index when it exists with a different primary key", None),
+    ("use_versioned_indexes", "Let cutover rebuild the index as <index>_v<N> and swap it in once complete", None),
+    ("raw_settings", "Index settings sent to Meilisearch as-is, with its camelCase names", Some("{ proximityPrecision = \"byAttribute\" }")),
+    ("vector_field", "Column holding a precomputed embedding, sent as _vectors.<embedder_name>", Some("\"embedding\"")),
+    ("embedder_name", "Embedder the vectors belong to", Some("\"default\"")),
+    ("vector_dimensions", "Expected embedding length, inferred from each sync's rows when unset", Some("384")),
+    ("id_prefix", "Prepended to document ids so several tables can share one index", Some("\"user-\"")),
+    ("primary_key_type", "auto, or string or integer to convert every primary key value", Some("\"string\"")),
+    ("invalid_primary_keys", "Primary key values whose rows are skipped, besides null and empty ones", Some("[\"-1\"]")),
+    ("max_connections", "Most pool connections this table's queries may hold at once", Some("2")),
+    ("array_split", "Columns holding several values, by separator", Some("{ tags = \",\" }")),
+    ("blob_reference_template", "Binary columns indexed as a reference to fetch them by, built from the row's columns", Some("{ photo = \"https://cdn.example.com/photos/{id}\" }")),
+    ("hash_fields", "Columns indexed as a salted hash instead of their value", Some("[\"email\"]")),
+    ("hash_algorithm", "sha256, sha512 or blake3", None),
+    ("hash_salt", "Salt mixed into every hash", Some("\"env:HASH_SALT\"")),
+    ("retention_column", "Date/time column; only rows within the retention window are indexed", Some("\"updated_at\"")),
+    ("retention_window_seconds", "Documents older than this are deleted from the index", Some("604800")),
+    ("updated_at_column", "Column with each row's modification time; after a first full sync only rows changed since are fetched", Some("\"updated_at\"")),
+    ("stream_records", "Read rows as a stream and send them in chunks, for tables larger than memory", None),
+    ("add_sync_timestamp", "Add the time of the sync that sent each document as a filterable, sortable _synced_at field", None),
+    ("add_source_table", "Add the table's name to each document as a filterable _source_table field", None),
+    ("null_handling", "empty, keep or omit: NULL values are indexed as an empty string, as null, or left out", None),
+    ("document_schema", "JSON Schema file transformed documents must match, others are handled by on_transform_error", Some("\"schemas/users.json\"")),
+    ("detect_duplicates", "Count and warn about documents of a sync with the same content under different ids", None),
+    ("suppress_duplicates", "Also index a _content_hash field as the distinct attribute, so searches return one of each duplicate", None),
+    ("field_null_handling", "Per-field overrides of null_handling", Some("{ middle_name = \"keep\", optional_score = \"omit\" }")),
+];
+
+const LOOKUP_KEYS: Keys = &[
+    ("table", "Reference table, read once per sync", Some("\"countries\"")),
+    ("local_key", "Column of the synced table holding the reference", Some("\"country_id\"")),
+    ("foreign_key", "Column of the reference table it matches", Some("\"id\"")),
+    ("fields", "Document field = column of the reference table", Some("{ country_name = \"name\" }")),
+    ("on_missing", "omit, or null to set the fields to null when no row matches", Some("\"omit\"")),
+];
+
+impl Config {
+    /// A starter configuration listing every option with a comment, set to
+    /// its default or commented out with an example when it has none
+    pub fn template() -> String {
+        let config = Config {
+            meilisearch: MeilisearchConfig { host: "http://localhost:7700".to_string(), ..Default::default() },
+            database: DatabaseConfig {
+                connection_string: "path/to/database.db".to_string(),
+                poll_interval_seconds: Some(60),
+                tables: vec![TableConfig {
+                    name: "users".to_string(),
+                    watch_for_changes: true,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        };
+        // The starter configuration only holds values TOML can represent
+        let value = toml::Value::try_from(&config).unwrap_or_else(|e| panic!("Invalid template configuration: {}", e));
+
+        let mut template = String::from(
+            "# Meilisearch SQL Connector configuration\n\
+             #\n\
+             # Every option is listed with its default. Commented-out options are\n\
+             # optional, uncomment them to use them. See the README for details.\n",
+        );
+        write_section(&mut template, "[meilisearch]", &value["meilisearch"], MEILISEARCH_KEYS, false);
+        write_section(&mut template, "[database]", &value["database"], DATABASE_KEYS, false);
+        write_section(&mut template, "[[database.tables]]", &value["database"]["tables"][0], TABLE_KEYS, false);
+        write_section(&mut template, "[[database.tables.lookups]]", &toml::Value::Table(Default::default()), LOOKUP_KEYS, true);
+        template
+    }
+}
+
+// Append a section: each key preceded by its comment, with its value in
+// `values` or commented out with its example
+fn write_section(template: &mut String, header: &str, values: &toml::Value, keys: Keys, commented: bool) {
+    template.push('\n');
+    if commented {
+        template.push_str("# ");
+    }
+    template.push_str(header);
+    template.push('\n');
+    for (key, comment, example) in keys {
+        if !comment.is_empty() {
+            template.push_str(&format!("# {}\n", comment));
+        }
+        match (values.get(key), example) {
+            (Some(value), _) if !commented => template.push_str(&format!("{} = {}\n", key, value)),
+            (_, Some(example)) => template.push_str(&format!("# {} = {}\n", key, example)),
+            _ => panic!("Template key {} has neither a value nor an example", key),
+        }
+    }
+}
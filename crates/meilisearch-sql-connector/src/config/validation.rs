@@ -0,0 +1,289 @@
+use super::{ActiveHours, Config, PrimaryKeyType, TableConfig, TableMatch};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A problem found by `Config::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// Table the issue belongs to, `None` for database or Meilisearch settings
+    pub table: Option<String>,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn global(message: impl Into<String>) -> Self {
+        Self { table: None, message: message.into() }
+    }
+
+    fn table(table: &str, message: impl Into<String>) -> Self {
+        Self { table: Some(table.to_string()), message: message.into() }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.table {
+            Some(table) => write!(f, "table '{}': {}", table, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Config {
+    /// Check the configuration for mistakes that don't need a database
+    /// connection to detect. An empty list means the configuration is valid.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let database = &self.database;
+
+        if self.meilisearch.host.trim().is_empty() {
+            issues.push(ValidationIssue::global("meilisearch.host must not be empty"));
+        }
+        if let Err(e) = database.resolved_connection_string() {
+            issues.push(ValidationIssue::global(e.to_string()));
+        }
+        if self.meilisearch.health_check_interval_seconds > 0 && self.meilisearch.maintenance_after_failures == 0 {
+            issues.push(ValidationIssue::global("maintenance_after_failures must be greater than 0"));
+        }
+        if self.meilisearch.circuit_breaker_failures > 0 && self.meilisearch.circuit_breaker_cooldown_seconds == 0 {
+            issues.push(ValidationIssue::global("meilisearch.circuit_breaker_cooldown_seconds must be greater than 0"));
+        }
+        if let Some(proxy_url) = &self.meilisearch.proxy_url {
+            let scheme = url::Url::parse(proxy_url).map(|url| url.scheme().to_string());
+            if !matches!(scheme.as_deref(), Ok("http" | "https")) {
+                issues.push(ValidationIssue::global("meilisearch.proxy_url must be an http:// or https:// URL"));
+            }
+        }
+        if self.meilisearch.document_page_size == 0 {
+            issues.push(ValidationIssue::global("meilisearch.document_page_size must be greater than 0"));
+        }
+        if database.circuit_breaker_failures > 0 && database.circuit_breaker_cooldown_seconds == 0 {
+            issues.push(ValidationIssue::global("database.circuit_breaker_cooldown_seconds must be greater than 0"));
+        }
+        if (database.use_pgpass || database.pgpass_file.is_some()) && !matches!(database.type_.as_str(), "postgres" | "postgresql") {
+            issues.push(ValidationIssue::global("use_pgpass and pgpass_file only apply to postgres databases"));
+        }
+        if database.pgpass_file.is_some() && !database.use_pgpass {
+            issues.push(ValidationIssue::global("pgpass_file only applies with use_pgpass = true"));
+        }
+        if database.poll_interval_seconds == Some(0) {
+            issues.push(ValidationIssue::global("poll_interval_seconds must be greater than 0"));
+        }
+        if let Some(Err(e)) = database.active_hours.as_deref().map(str::parse::<ActiveHours>) {
+            issues.push(ValidationIssue::global(format!("active_hours is invalid: {}", e)));
+        }
+        if database.connection_pool_size == 0 {
+            issues.push(ValidationIssue::global("connection_pool_size must be greater than 0"));
+        }
+        if database.max_concurrent_queries.is_some_and(|max| max == 0 || max > database.connection_pool_size) {
+            issues.push(ValidationIssue::global(format!(
+                "max_concurrent_queries must be between 1 and connection_pool_size ({})", database.connection_pool_size
+            )));
+        }
+        if database.max_concurrent_batches == 0 {
+            issues.push(ValidationIssue::global("max_concurrent_batches must be greater than 0"));
+        }
+        if database.document_batch_size == 0 {
+            issues.push(ValidationIssue::global("document_batch_size must be greater than 0"));
+        }
+        if database.max_delete_ratio.is_some_and(|ratio| !(0.0..=1.0).contains(&ratio)) {
+            issues.push(ValidationIssue::global("max_delete_ratio must be between 0 and 1"));
+        }
+        if database.table_pattern.is_some() && !database.auto_discover {
+            issues.push(ValidationIssue::global("table_pattern only applies with auto_discover = true"));
+        }
+        if database.max_documents_per_sync == Some(0) {
+            issues.push(ValidationIssue::global("max_documents_per_sync must be greater than 0"));
+        }
+
+        let mut index_owners: HashMap<&str, Vec<&TableConfig>> = HashMap::new();
+        for table in &database.tables {
+            if table.name.trim().is_empty() {
+                issues.push(ValidationIssue::global("a table has an empty name"));
+                continue;
+            }
+            match (table.primary_key.trim().is_empty(), table.infer_primary_key) {
+                (true, false) => issues.push(ValidationIssue::table(&table.name, "primary_key must not be empty")),
+                (false, true) => issues.push(ValidationIssue::table(&table.name, "infer_primary_key can't be used with a primary_key")),
+                _ => {}
+            }
+            if table.raw_settings.as_ref().is_some_and(|raw| !raw.is_object()) {
+                issues.push(ValidationIssue::table(&table.name, "raw_settings must be a table of Meilisearch settings"));
+            }
+            match (&table.vector_field, &table.embedder_name) {
+                (Some(_), None) => issues.push(ValidationIssue::table(&table.name, "vector_field requires an embedder_name")),
+                (None, Some(_)) => issues.push(ValidationIssue::table(&table.name, "embedder_name requires a vector_field")),
+                (Some(_), Some(embedder)) if embedder.trim().is_empty() => {
+                    issues.push(ValidationIssue::table(&table.name, "embedder_name must not be empty"));
+                }
+                _ => {}
+            }
+            if table.vector_dimensions == Some(0) {
+                issues.push(ValidationIssue::table(&table.name, "vector_dimensions must be greater than 0"));
+            }
+            for (field, separator) in &table.array_split {
+                if separator.is_empty() {
+                    issues.push(ValidationIssue::table(&table.name, format!("array_split separator of '{}' must not be empty", field)));
+                }
+                if *field == table.primary_key {
+                    issues.push(ValidationIssue::table(&table.name, "array_split can't be used on the primary key"));
+                }
+            }
+            if table.stream_records {
+                // These need every change of a sync before anything is sent
+                let conflicts = [
+                    ("updated_at_column", table.updated_at_column.is_some()),
+                    ("durable_queue_path", database.durable_queue_path.is_some()),
+                    ("max_documents_per_sync", database.max_documents_per_sync.is_some()),
+                ];
+                for (option, _) in conflicts.iter().filter(|(_, set)| *set) {
+                    issues.push(ValidationIssue::table(&table.name, format!("stream_records can't be used with {}", option)));
+                }
+            }
+            let mut renamed: Vec<(&String, &String)> = table.field_map.iter().collect();
+            renamed.sort();
+            let mut attributes = HashSet::new();
+            for (column, attribute) in renamed {
+                if attribute.trim().is_empty() {
+                    issues.push(ValidationIssue::table(&table.name, format!("field_map name of '{}' must not be empty", column)));
+                } else if !attributes.insert(attribute) {
+                    issues.push(ValidationIssue::table(&table.name, format!("field_map maps several columns to '{}'", attribute)));
+                }
+            }
+            match table.filter.as_deref().map(str::trim) {
+                Some("") => issues.push(ValidationIssue::table(&table.name, "filter must not be empty")),
+                // A single condition, not a way to run other statements
+                Some(filter) if filter.contains(';') => {
+                    issues.push(ValidationIssue::table(&table.name, "filter must not contain a semicolon"));
+                }
+                _ => {}
+            }
+            if let Some(Err(e)) = table.active_hours.as_deref().map(str::parse::<ActiveHours>) {
+                issues.push(ValidationIssue::table(&table.name, format!("active_hours is invalid: {}", e)));
+            }
+            if let Some(Err(e)) = table.schedule() {
+                issues.push(ValidationIssue::table(&table.name, format!("cron expression is invalid: {}", e)));
+            }
+            match (&table.retention_column, table.retention_window_seconds) {
+                (Some(_), None) => issues.push(ValidationIssue::table(&table.name, "retention_column requires a retention_window_seconds")),
+                (None, Some(_)) => issues.push(ValidationIssue::table(&table.name, "retention_window_seconds requires a retention_column")),
+                (Some(_), Some(0)) => issues.push(ValidationIssue::table(&table.name, "retention_window_seconds must be greater than 0")),
+                _ => {}
+            }
+            for (field, template) in &table.blob_reference_template {
+                if *field == table.primary_key {
+                    issues.push(ValidationIssue::table(&table.name, "blob_reference_template can't be used on the primary key"));
+                }
+                if template.is_empty() {
+                    issues.push(ValidationIssue::table(&table.name, format!("blob_reference_template of '{}' must not be empty", field)));
+                }
+            }
+            if let Some(path) = &table.document_schema {
+                if let Err(e) = crate::transform::load_document_schema(path) {
+                    issues.push(ValidationIssue::table(&table.name, e.to_string()));
+                }
+            }
+            if table.exclude_fields.contains(&table.primary_key) {
+                issues.push(ValidationIssue::table(&table.name, "exclude_fields can't include the primary key"));
+            }
+            for field in table.exclude_fields.iter().filter(|field| table.fields_to_index.contains(field)) {
+                issues.push(ValidationIssue::table(&table.name, format!("'{}' is in both fields_to_index and exclude_fields", field)));
+            }
+            if table.hash_fields.contains(&table.primary_key) {
+                issues.push(ValidationIssue::table(&table.name, "hash_fields can't include the primary key"));
+            }
+            // Meilisearch document ids only allow these characters
+            if let Some(prefix) = &table.id_prefix {
+                if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                    issues.push(ValidationIssue::table(
+                        &table.name,
+                        "id_prefix must be non-empty and only contain letters, digits, '-' and '_'",
+                    ));
+                }
+            }
+
+            for lookup in &table.lookups {
+                if lookup.table.trim().is_empty() || lookup.local_key.trim().is_empty() || lookup.foreign_key.trim().is_empty() {
+                    issues.push(ValidationIssue::table(&table.name, "lookups need a table, a local_key and a foreign_key"));
+                }
+                if lookup.fields.is_empty() {
+                    issues.push(ValidationIssue::table(&table.name, format!("lookup of '{}' has no fields", lookup.table)));
+                }
+                if lookup.fields.contains_key(&table.primary_key) {
+                    issues.push(ValidationIssue::table(
+                        &table.name,
+                        format!("lookup of '{}' can't set the primary key", lookup.table),
+                    ));
+                }
+            }
+            if let Some(max) = table.max_connections {
+                if max == 0 || max > database.connection_pool_size {
+                    issues.push(ValidationIssue::table(
+                        &table.name,
+                        format!("max_connections must be between 1 and connection_pool_size ({})", database.connection_pool_size),
+                    ));
+                }
+            }
+            if table.id_prefix.is_some() && table.primary_key_type == PrimaryKeyType::Integer {
+                issues.push(ValidationIssue::table(
+                    &table.name,
+                    "primary_key_type = \"integer\" can't be used with id_prefix, prefixed ids are strings",
+                ));
+            }
+
+            // Pattern blocks have no index of their own, each matched table gets one
+            match table.name_match {
+                TableMatch::Exact => {}
+                TableMatch::Glob | TableMatch::Regex => {
+                    if table.name_match == TableMatch::Regex && Regex::new(&table.name).is_err() {
+                        issues.push(ValidationIssue::table(&table.name, "name is not a valid regular expression"));
+                    }
+                    if table.index_name.as_ref().is_some_and(|name| !name.contains("{table}")) {
+                        issues.push(ValidationIssue::table(
+                            &table.name,
+                            "index_name of a table pattern must contain {table}, or every matched table would write to the same index",
+                        ));
+                    }
+                    continue;
+                }
+            }
+
+            let index_name = table.index_name.as_deref().unwrap_or(&table.name);
+            if index_name.trim().is_empty() {
+                issues.push(ValidationIssue::table(&table.name, "index_name must not be empty"));
+                continue;
+            }
+            let owners = index_owners.entry(index_name).or_default();
+            for other in owners.iter() {
+                if let Some(message) = shared_index_issue(index_name, table, other) {
+                    issues.push(ValidationIssue::table(&table.name, message));
+                }
+            }
+            owners.push(table);
+        }
+
+        issues
+    }
+}
+
+// Tables can only share an index when distinct id prefixes keep their
+// documents apart, since each sync reconciles the documents carrying its prefix
+fn shared_index_issue(index_name: &str, table: &TableConfig, other: &TableConfig) -> Option<String> {
+    let (Some(prefix), Some(other_prefix)) = (&table.id_prefix, &other.id_prefix) else {
+        return Some(format!("index '{}' is also used by table '{}'", index_name, other.name));
+    };
+    if prefix.starts_with(other_prefix.as_str()) || other_prefix.starts_with(prefix.as_str()) {
+        return Some(format!(
+            "id_prefix '{}' overlaps with id_prefix '{}' of table '{}', which also writes to index '{}'",
+            prefix, other_prefix, other.name, index_name
+        ));
+    }
+    if table.primary_key != other.primary_key {
+        return Some(format!(
+            "primary_key '{}' differs from '{}' of table '{}', which also writes to index '{}'",
+            table.primary_key, other.primary_key, other.name, index_name
+        ));
+    }
+    None
+}
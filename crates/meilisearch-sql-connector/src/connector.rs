@@ -1,92 +1,278 @@
-use crate::config::{Config, TableConfig};
-use crate::database::{DatabaseAdapter, create_db_adapter};
+use crate::breaker::{BreakerState, BreakerStatus, CircuitBreakers};
+use crate::config::{
+    ActiveHours, CONTENT_HASH_FIELD, Config, DatabaseConfig, LookupMissing, NullHandling, OperationOrder, PrimaryKeyType, SOURCE_TABLE_FIELD, SYNCED_AT_FIELD, TableConfig,
+    TransformErrorPolicy, ValidationIssue,
+};
+use crate::database::{
+    AdapterOptions, DatabaseAdapter, RowFilters, compare_primary_keys, create_db_adapter, format_decode_errors, limit_table_connections,
+    project_record,
+};
+use crate::dead_letter::DeadLetterFile;
 use crate::error::{ConnectorError, Result};
 use crate::meilisearch::{MeilisearchClient, MeilisearchClientTrait};
+use crate::plan::{PlanRecorder, SyncPlan, TableSignature};
+use crate::queue::{DurableQueue, QueuedOperation};
+use crate::report::{AppliedIndex, ApplyReport, CutoverReport, FieldMismatch, ReloadReport, RunReport, SyncReport, TableStatus, VerifyReport};
+use crate::transform::{
+    hash_value, load_document_schema, parse_timestamp, parse_vector, render_template, schema_violations, split_array,
+};
+use crate::watch::StatusView;
+use futures_util::TryStreamExt;
 use meilisearch_sdk::settings::Settings;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::{mpsc, watch};
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
-use tracing::{error, info, warn, debug};
+use tracing::{debug, error, info, info_span, instrument, trace, warn, Instrument, Span};
 use serde_json::Value;
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 #[derive(Clone)]
 pub struct Connector {
     db_adapter: Arc<Box<dyn DatabaseAdapter>>,
+    // Database deletions are checked against when rows are read from a replica
+    primary_adapter: Option<Arc<Box<dyn DatabaseAdapter>>>,
     meilisearch_client: Arc<dyn MeilisearchClientTrait>,
-    config: Config,
+    // Replaced by `reload`
+    config: Arc<RwLock<Config>>,
+    // Shared with the adapters, which apply them to their queries; updated
+    // by `reload` along with the configuration
+    row_filters: RowFilters,
     shutdown_tx: watch::Sender<bool>,
     task_handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    // Sync task of every table being synced, by table name
+    table_tasks: Arc<Mutex<HashMap<String, TableTask>>>,
+    profile: bool,
+    // Print a live per-table status while running
+    watch: bool,
+    require_tables: bool,
+    queue: Option<Arc<DurableQueue>>,
+    allow_mass_delete: bool,
+    // Latest sync outcome of every table, by table name
+    status: Arc<Mutex<BTreeMap<String, TableStatus>>>,
+    maintenance: Arc<Maintenance>,
+    breakers: CircuitBreakers,
+    // Version Meilisearch reported at startup, if it could be read
+    meilisearch_version: Arc<Mutex<Option<String>>>,
+    watermarks: Watermarks,
+}
+
+/// A running table sync task, stopped on its own when the table is removed
+/// or changed by `reload`
+struct TableTask {
+    stop_tx: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+/// Highest `updated_at_column` value sent by the syncs of each table and
+/// index, where the next sync of the pair resumes
+type Watermarks = Arc<Mutex<HashMap<(String, String), Value>>>;
+
+/// Whether writes to Meilisearch are withheld, either on request or because
+/// its health checks keep failing
+#[derive(Debug, Default)]
+struct Maintenance {
+    requested: AtomicBool,
+    unhealthy: AtomicBool,
+}
+
+impl Maintenance {
+    fn active(&self) -> bool {
+        self.requested.load(Ordering::SeqCst) || self.unhealthy.load(Ordering::SeqCst)
+    }
 }
 
 impl Connector {
-    pub async fn new(config: Config) -> Result<Self> {
-        let db_url = match config.database.type_.as_str() {
-            "sqlite" => {
-                // Handle different path formats for SQLite
-                let conn_string = &config.database.connection_string;
-                
-                // Check if it's a double-slash path like "//Users/..."
-                if conn_string.starts_with("//") {
-                    // Preserve first slash, remove second
-                    let fixed_path = format!("/{}", conn_string.trim_start_matches("//"));
-                    info!("Converting double-slash path to absolute path: {} -> {}", conn_string, fixed_path);
-                    format!("sqlite:{}", fixed_path)
-                }
-                // Regular absolute path
-                else if conn_string.starts_with('/') {
-                    format!("sqlite:{}", conn_string)
-                }
-                // Path with protocol or drive letter
-                else if conn_string.contains(':') {
-                    format!("sqlite:{}", conn_string)
-                }
-                // Relative path
-                else {
-                    format!("sqlite:./{}", conn_string)
-                }
-            },
-            _ => return Err(ConnectorError::UnsupportedDatabaseType(config.database.type_.clone())),
-        };
+    pub async fn new(mut config: Config) -> Result<Self> {
+        let issues = Self::validate_config(&config);
+        if !issues.is_empty() {
+            return Err(ConnectorError::Config(format!(
+                "Invalid configuration: {}",
+                issues.iter().map(|i| i.to_string()).collect::<Vec<_>>().join("; ")
+            )));
+        }
 
-        // Create database adapter with configured pool size
-        let db_adapter = create_db_adapter(&db_url, Some(config.database.connection_pool_size)).await?;
+        let db_url = config.database.read_adapter_url()?;
+        let primary_url = config.database.primary_adapter_url()?;
 
-        // We can add basic validation if needed using existing error types
-        for table_config in &config.database.tables {
-            // Get all tables from the database
-            let db_tables = db_adapter.get_all_tables().await?;
-            
-            // Check if the specified table exists
-            if !db_tables.contains(&table_config.name) {
-                return Err(ConnectorError::Config(format!("Table '{}' not found in database", table_config.name)));
-            }
+        // Create database adapter with configured pool size and row conversion settings
+        let adapter_options = AdapterOptions::from(&config.database);
+        let db_adapter = create_db_adapter(&db_url, &adapter_options).await?;
+        // Deletions only need checking when rows are read from another database
+        let primary_adapter = if primary_url != db_url {
+            Some(create_db_adapter(&primary_url, &adapter_options).await?)
+        } else {
+            None
+        };
+
+        // Expand table patterns, then check the tables they produced like the others
+        config.database.expand_table_patterns(db_adapter.as_ref().as_ref()).await?;
+        let issues = Self::validate_config(&config);
+        if !issues.is_empty() {
+            return Err(ConnectorError::Config(format!(
+                "Invalid configuration after expanding table patterns: {}",
+                issues.iter().map(|i| i.to_string()).collect::<Vec<_>>().join("; ")
+            )));
         }
 
+        // Make sure every configured table can actually be synced
+        config.database.verify_tables(db_adapter.as_ref().as_ref()).await?;
+        // Filters are looked up by the tables' names in the database
+        adapter_options.row_filters.set_tables(&config.database.tables);
+        let breakers = CircuitBreakers::new(&config);
+        let db_adapter = breakers.wrap_adapter(limit_table_connections(db_adapter, &config.database.tables, Some(config.database.query_limit())));
+        let primary_adapter = primary_adapter.map(|adapter| breakers.wrap_adapter(adapter));
+
         // Create Meilisearch client
-        let meilisearch_client: Arc<dyn MeilisearchClientTrait> = Arc::new(MeilisearchClient::new(
+        let meilisearch_client = breakers.wrap_client(Arc::new(MeilisearchClient::new_with_proxy(
             &config.meilisearch.host,
             config.meilisearch.api_key.as_deref(),
-        )?);
+            config.meilisearch.proxy_url.as_deref(),
+        )?
+            .with_ndjson_upload(config.database.ndjson_upload)
+            .with_upload_format(config.database.upload_format)
+            .with_document_page_size(config.meilisearch.document_page_size)
+            .with_inter_batch_delay(Duration::from_millis(config.meilisearch.inter_batch_delay_ms))));
+
+        // Open the durable queue so operations left over from a previous run get drained
+        let queue = match &config.database.durable_queue_path {
+            Some(path) => Some(Arc::new(DurableQueue::open(path).await?)),
+            None => None,
+        };
 
         // Create shutdown channel
         let (shutdown_tx, _) = watch::channel(false);
 
-        println!("Loaded config tables: {:#?}", config.database.tables);
+        debug!("Loaded tables: {}", config.database.tables.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", "));
 
         Ok(Self {
             db_adapter,
+            primary_adapter,
             meilisearch_client,
-            config,
+            config: Arc::new(RwLock::new(config)),
+            row_filters: adapter_options.row_filters,
             shutdown_tx,
             task_handles: Arc::new(Mutex::new(Vec::new())),
+            table_tasks: Arc::new(Mutex::new(HashMap::new())),
+            profile: false,
+            watch: false,
+            require_tables: true,
+            queue,
+            allow_mass_delete: false,
+            status: Arc::new(Mutex::new(BTreeMap::new())),
+            maintenance: Arc::new(Maintenance::default()),
+            breakers,
+            meilisearch_version: Arc::new(Mutex::new(None)),
+            watermarks: Watermarks::default(),
         })
     }
 
+    /// Validate a configuration without connecting to anything, see `Config::validate`
+    pub fn validate_config(config: &Config) -> Vec<ValidationIssue> {
+        config.validate()
+    }
+
+    /// Build a connector around already constructed clients, skipping the
+    /// database validation done by `new`. Useful for embedding and tests.
+    #[allow(dead_code)]
+    pub fn with_clients(
+        config: Config,
+        db_adapter: Box<dyn DatabaseAdapter>,
+        meilisearch_client: Arc<dyn MeilisearchClientTrait>,
+    ) -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
+        let breakers = CircuitBreakers::new(&config);
+        Self {
+            db_adapter: breakers.wrap_adapter(limit_table_connections(Arc::new(db_adapter), &config.database.tables, Some(config.database.query_limit()))),
+            primary_adapter: None,
+            meilisearch_client: breakers.wrap_client(meilisearch_client),
+            config: Arc::new(RwLock::new(config)),
+            row_filters: RowFilters::default(),
+            shutdown_tx,
+            task_handles: Arc::new(Mutex::new(Vec::new())),
+            table_tasks: Arc::new(Mutex::new(HashMap::new())),
+            profile: false,
+            watch: false,
+            require_tables: true,
+            queue: None,
+            allow_mass_delete: false,
+            status: Arc::new(Mutex::new(BTreeMap::new())),
+            maintenance: Arc::new(Maintenance::default()),
+            breakers,
+            meilisearch_version: Arc::new(Mutex::new(None)),
+            watermarks: Watermarks::default(),
+        }
+    }
+
+    /// Check the deletions a sync finds against `adapter` before sending them,
+    /// for a connector reading its rows from a replica of that database
+    #[allow(dead_code)]
+    pub fn with_primary_adapter(mut self, adapter: Box<dyn DatabaseAdapter>) -> Self {
+        self.primary_adapter = Some(self.breakers.wrap_adapter(Arc::new(adapter)));
+        self
+    }
+
+    /// Log a per-phase timing breakdown at info level after every table sync
+    pub fn with_profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Print every table's latest sync to the console while running
+    pub fn with_watch(mut self, watch: bool) -> Self {
+        self.watch = watch;
+        self
+    }
+
+    /// Buffer document operations in `queue` before sending them to Meilisearch
+    #[allow(dead_code)]
+    pub fn with_durable_queue(mut self, queue: DurableQueue) -> Self {
+        self.queue = Some(Arc::new(queue));
+        self
+    }
+
+    fn sync_options(&self) -> SyncOptions {
+        SyncOptions {
+            queue: self.queue.clone(),
+            primary_adapter: self.primary_adapter.clone(),
+            allow_mass_delete: self.allow_mass_delete,
+            maintenance: Some(self.maintenance.clone()),
+            watermarks: self.watermarks.clone(),
+            ..SyncOptions::from(&self.config().database)
+        }
+    }
+
+    /// Let syncs delete more than `max_delete_ratio` of a table's documents
+    pub fn with_allow_mass_delete(mut self, allow_mass_delete: bool) -> Self {
+        self.allow_mass_delete = allow_mass_delete;
+        self
+    }
+
+    /// Whether `start` refuses to run with no tables configured (the default).
+    /// Embedders that add tables later can turn this off to idle instead.
+    #[allow(dead_code)]
+    pub fn with_require_tables(mut self, require_tables: bool) -> Self {
+        self.require_tables = require_tables;
+        self
+    }
+
+    /// Have `reload` update `row_filters`, the filters given to the adapter
+    /// of a connector built with `with_clients`
+    #[allow(dead_code)]
+    pub fn with_row_filters(mut self, row_filters: RowFilters) -> Self {
+        self.row_filters = row_filters;
+        self
+    }
+
     pub async fn start(&self) -> Result<()> {
         info!("Starting connector...");
-        self.setup_indices().await?;
+        self.setup().await?;
         self.start_sync_tasks().await?;
 
         // Create a new receiver to keep this thread alive
@@ -104,77 +290,32 @@ impl Connector {
     }
 
     async fn start_sync_tasks(&self) -> Result<()> {
-        if self.config.database.tables.is_empty() {
+        let config = self.config();
+        if config.database.auto_discover {
+            self.spawn_discovery_task();
+        }
+        if config.database.heartbeat_interval_seconds > 0 {
+            self.spawn_heartbeat_task();
+        }
+        if config.meilisearch.health_check_interval_seconds > 0 {
+            self.spawn_health_check_task();
+        }
+        if self.watch {
+            self.spawn_watch_task();
+        }
+        if config.database.enabled_tables().next().is_none() {
             info!("No tables configured for synchronization");
             return Ok(());
         }
 
         let (completion_tx, mut completion_rx) = mpsc::channel::<()>(1);
-        let batch_size = self.config.database.document_batch_size;
-        let max_concurrent_batches = self.config.database.max_concurrent_batches;
-
-        // Create a receiver for each task
-        for table in &self.config.database.tables {
-            let poll_interval = self.config.database.poll_interval_seconds.unwrap_or(60);
-            let table_clone = table.clone();
-            let db_adapter = self.db_adapter.clone();
-            let meilisearch_client = self.meilisearch_client.clone();
-            let mut task_shutdown_rx = self.shutdown_tx.subscribe();
-            let table_name = table.name.clone();
-            let index_name = table.index_name.as_deref().unwrap_or(&table.name).to_string();
-            let completion_tx = completion_tx.clone();
-            let batch_size = batch_size;
-            let max_concurrent_batches = max_concurrent_batches;
-            
-            // Spawn sync task
-            let handle = tokio::spawn(async move {
-                info!("Starting sync task for table: {}", table_name);
-                
-                // Initial sync
-                info!("Performing initial sync for table: {}", table_name);
-                match sync_table_impl(&table_clone, &index_name, &db_adapter, &meilisearch_client, batch_size, max_concurrent_batches).await {
-                    Ok(_) => info!("Initial sync completed for table: {}", table_name),
-                    Err(e) => error!("Error during initial sync for table {}: {}", table_name, e),
-                }
-                
-                loop {
-                    // Check if shutdown signal received
-                    if *task_shutdown_rx.borrow() {
-                        info!("Shutdown signal received, stopping sync for table: {}", table_name);
-                        break;
-                    }
 
-                    // Sleep for the configured interval
-                    tokio::select! {
-                        _ = sleep(Duration::from_secs(poll_interval)) => {
-                            // Continue with sync
-                            info!("Polling for changes in table: {}", table_name);
-                        }
-                        _ = task_shutdown_rx.changed() => {
-                            info!("Shutdown signal received during wait, stopping sync for table: {}", table_name);
-                            break;
-                        }
-                    }
+        let ramp = Duration::from_secs_f64(config.database.startup_ramp_seconds.unwrap_or(0.0).max(0.0));
 
-                    // Sync the table
-                    match sync_table_impl(&table_clone, &index_name, &db_adapter, &meilisearch_client, batch_size, max_concurrent_batches).await {
-                        Ok(_) => {
-                            info!("Successfully synced table: {}", table_name);
-                        }
-                        Err(e) => {
-                            error!("Error syncing table {}: {}", table_name, e);
-                            // Continue loop despite error - will retry on next interval
-                        }
-                    }
-                }
-                
-                // Signal task completion
-                let _ = completion_tx.send(()).await;
-                info!("Sync task for table {} stopped", table_name);
-            });
-            
-            // Store handle for later joining
-            self.task_handles.lock().unwrap().push(handle);
+        // Create a receiver for each task
+        for (position, table) in config.database.enabled_tables().enumerate() {
+            let startup_delay = ramp * position as u32;
+            self.spawn_sync_task(table.clone(), startup_delay, Some(completion_tx.clone()));
         }
 
         // Drop our sender so channel can close when last task completes
@@ -200,6 +341,427 @@ impl Connector {
         Ok(())
     }
 
+    /// Spawn the initial sync and poll loop of one table
+    fn spawn_sync_task(&self, table: TableConfig, startup_delay: Duration, completion_tx: Option<mpsc::Sender<()>>) {
+        let config = self.config();
+        let poll_interval = config.database.poll_interval_seconds.unwrap_or(60);
+        let schedule = match table.schedule() {
+            Some(Ok(schedule)) => Some(schedule),
+            Some(Err(e)) => {
+                error!("Invalid cron expression for table {}, syncing every {}s instead: {}", table.name, poll_interval, e);
+                None
+            }
+            None => None,
+        };
+        let active_hours = match config.database.active_hours_of(&table) {
+            Some(Ok(hours)) => Some(hours),
+            Some(Err(e)) => {
+                error!("Invalid active_hours for table {}, syncing at any time instead: {}", table.name, e);
+                None
+            }
+            None => None,
+        };
+        let db_adapter = self.db_adapter.clone();
+        let meilisearch_client = self.meilisearch_client.clone();
+        // The task stops on shutdown, or when `reload` stops it alone
+        let (stop_tx, mut task_shutdown_rx) = watch::channel(*self.shutdown_tx.borrow());
+        let table_name = table.name.clone();
+        let task_name = table.name.clone();
+        let index_name = table.index_name.as_deref().unwrap_or(&table.name).to_string();
+        let options = self.sync_options();
+        let profile = self.profile;
+        let status = self.status.clone();
+        status.lock().unwrap().entry(table_name.clone()).or_default();
+        
+        // Spawn sync task
+        let handle = tokio::spawn(async move {
+            info!("Starting sync task for table: {}", table_name);
+            
+            // Stagger initial syncs so large configs don't hit the database and Meilisearch all at once
+            if !startup_delay.is_zero() {
+                info!("Delaying initial sync for table {} by {:?}", table_name, startup_delay);
+                tokio::select! {
+                    _ = sleep(startup_delay) => {}
+                    _ = task_shutdown_rx.changed() => {
+                        info!("Shutdown signal received during startup delay, stopping sync for table: {}", table_name);
+                        if let Some(completion_tx) = completion_tx {
+                            let _ = completion_tx.send(()).await;
+                        }
+                        return;
+                    }
+                }
+            }
+            
+            // Initial sync, once the active hours window is open
+            if wait_for_active_hours(active_hours.as_ref(), &table_name, &mut task_shutdown_rx).await {
+                info!("Performing initial sync for table: {}", table_name);
+                let result = sync_table_with_retries(&table, &index_name, &db_adapter, &meilisearch_client, &options, &mut task_shutdown_rx).await;
+                record_status(&status, &table_name, &result);
+                match result {
+                    Ok(report) => {
+                        info!("Initial sync completed for table: {}", table_name);
+                        log_sync_profile(&report, profile);
+                    }
+                    Err(e) => error!("Error during initial sync for table {}: {}", table_name, e),
+                }
+            }
+            
+            loop {
+                // Check if shutdown signal received
+                if *task_shutdown_rx.borrow() {
+                    info!("Shutdown signal received, stopping sync for table: {}", table_name);
+                    break;
+                }
+
+                // Sleep for the configured interval, or until the next occurrence of the schedule
+                let delay = match &schedule {
+                    Some(schedule) => match schedule.upcoming(chrono::Utc).next() {
+                        Some(next) => {
+                            info!("Next scheduled sync of table {} at {}", table_name, next);
+                            (next - chrono::Utc::now()).to_std().unwrap_or_default()
+                        }
+                        None => {
+                            info!("Cron schedule of table {} has no further occurrences, stopping its sync", table_name);
+                            break;
+                        }
+                    },
+                    None => Duration::from_secs(poll_interval),
+                };
+                tokio::select! {
+                    _ = sleep(delay) => {
+                        // Continue with sync
+                        info!("Polling for changes in table: {}", table_name);
+                    }
+                    _ = task_shutdown_rx.changed() => {
+                        info!("Shutdown signal received during wait, stopping sync for table: {}", table_name);
+                        break;
+                    }
+                }
+                if !wait_for_active_hours(active_hours.as_ref(), &table_name, &mut task_shutdown_rx).await {
+                    break;
+                }
+
+                // Sync the table
+                let result = sync_table_with_retries(&table, &index_name, &db_adapter, &meilisearch_client, &options, &mut task_shutdown_rx).await;
+                record_status(&status, &table_name, &result);
+                match result {
+                    Ok(report) => {
+                        info!("Successfully synced table: {}", table_name);
+                        log_sync_profile(&report, profile);
+                    }
+                    Err(e) => {
+                        error!("Error syncing table {}: {}", table_name, e);
+                        // Continue loop despite error - will retry on next interval
+                    }
+                }
+            }
+            
+            // Signal task completion
+            if let Some(completion_tx) = completion_tx {
+                let _ = completion_tx.send(()).await;
+            }
+            info!("Sync task for table {} stopped", table_name);
+        });
+        
+        // Store handle for later joining, or stopping the table alone
+        let previous = self.table_tasks.lock().unwrap().insert(task_name, TableTask { stop_tx, handle });
+        if let Some(previous) = previous {
+            let _ = previous.stop_tx.send(true);
+            self.task_handles.lock().unwrap().push(previous.handle);
+        }
+    }
+
+    /// Spawn the `auto_discover` loop, which looks for new tables at startup
+    /// and then every poll interval
+    fn spawn_discovery_task(&self) {
+        let connector = self.clone();
+        let poll_interval = self.config().database.poll_interval_seconds.unwrap_or(60);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        let handle = tokio::spawn(async move {
+            // Tables already picked up, or skipped because they can't be synced
+            let mut seen = HashSet::new();
+            loop {
+                if let Err(e) = connector.discover_tables(&mut seen).await {
+                    error!("Error discovering new tables: {}", e);
+                }
+                tokio::select! {
+                    _ = sleep(Duration::from_secs(poll_interval)) => {}
+                    _ = shutdown_rx.changed() => break,
+                }
+                if *shutdown_rx.borrow() {
+                    break;
+                }
+            }
+            info!("Table discovery stopped");
+        });
+        self.task_handles.lock().unwrap().push(handle);
+    }
+
+    /// Spawn the loop logging a summary of every table's last sync each
+    /// `heartbeat_interval_seconds`, so a quiet connector can be told from a stuck one
+    fn spawn_heartbeat_task(&self) {
+        let interval = Duration::from_secs(self.config().database.heartbeat_interval_seconds);
+        let status = self.status.clone();
+        let breakers = self.breakers.clone();
+        let meilisearch_version = self.meilisearch_version.clone();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        // Not a sync task, it only reads the status and ends with the shutdown signal
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = sleep(interval) => {}
+                    _ = shutdown_rx.changed() => break,
+                }
+                if *shutdown_rx.borrow() {
+                    break;
+                }
+                let now = SystemTime::now();
+                let status = status.lock().unwrap();
+                match meilisearch_version.lock().unwrap().as_deref() {
+                    Some(version) => info!(target: "heartbeat", "Connector alive, syncing {} tables to Meilisearch {}", status.len(), version),
+                    None => info!(target: "heartbeat", "Connector alive, syncing {} tables", status.len()),
+                }
+                for (table, table_status) in status.iter() {
+                    info!(target: "heartbeat", "  {}: {}", table, table_status.summary(now));
+                }
+                for (dependency, breaker) in breakers.status() {
+                    if breaker.state != BreakerState::Closed {
+                        info!(target: "heartbeat", "  circuit of {} is {:?}, {} calls rejected",
+                              dependency, breaker.state, breaker.rejected_calls);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Spawn the loop printing every table's status each second for `run
+    /// --watch`, redrawn in place on a terminal
+    fn spawn_watch_task(&self) {
+        let status = self.status.clone();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        let mut view = StatusView::new(std::io::stdout().is_terminal());
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = sleep(Duration::from_secs(1)) => {}
+                    _ = shutdown_rx.changed() => break,
+                }
+                if *shutdown_rx.borrow() {
+                    break;
+                }
+                let output = view.render(&status.lock().unwrap(), SystemTime::now());
+                let mut stdout = std::io::stdout().lock();
+                // A closed stdout only loses the view, the connector keeps syncing
+                let _ = stdout.write_all(output.as_bytes()).and_then(|()| stdout.flush());
+            }
+        });
+    }
+
+    /// Spawn the loop checking Meilisearch's health every
+    /// `health_check_interval_seconds`. After `maintenance_after_failures`
+    /// failures in a row writes are withheld, until a check passes again.
+    fn spawn_health_check_task(&self) {
+        let config = self.config();
+        let interval = Duration::from_secs(config.meilisearch.health_check_interval_seconds);
+        let max_failures = config.meilisearch.maintenance_after_failures;
+        let meilisearch_client = self.meilisearch_client.clone();
+        let maintenance = self.maintenance.clone();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            let mut failures = 0;
+            loop {
+                tokio::select! {
+                    _ = sleep(interval) => {}
+                    _ = shutdown_rx.changed() => break,
+                }
+                if *shutdown_rx.borrow() {
+                    break;
+                }
+                match meilisearch_client.health().await {
+                    Ok(()) => {
+                        failures = 0;
+                        if maintenance.unhealthy.swap(false, Ordering::SeqCst) {
+                            info!("Meilisearch is healthy again, resuming writes");
+                        }
+                    }
+                    Err(e) => {
+                        failures += 1;
+                        debug!("Meilisearch health check failed ({} in a row): {}", failures, e);
+                        if failures >= max_failures && !maintenance.unhealthy.swap(true, Ordering::SeqCst) {
+                            warn!("Meilisearch failed {} health checks in a row, withholding writes until it recovers: {}",
+                                  failures, e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Stop sending writes to Meilisearch, e.g. during its planned
+    /// maintenance. Tables are still polled and diffed; the changes are sent
+    /// by the first sync after `exit_maintenance`.
+    #[allow(dead_code)]
+    pub fn enter_maintenance(&self) {
+        info!("Entering maintenance mode, writes to Meilisearch are withheld");
+        self.maintenance.requested.store(true, Ordering::SeqCst);
+    }
+
+    /// Resume writes stopped by `enter_maintenance`. Writes stay withheld while
+    /// Meilisearch fails its health checks.
+    #[allow(dead_code)]
+    pub fn exit_maintenance(&self) {
+        info!("Leaving maintenance mode");
+        self.maintenance.requested.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether writes to Meilisearch are currently withheld
+    #[allow(dead_code)]
+    pub fn in_maintenance(&self) -> bool {
+        self.maintenance.active()
+    }
+
+    /// The configuration, as of the latest `reload`
+    pub fn config(&self) -> Config {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Latest sync outcome of every table being synced, by table name
+    #[allow(dead_code)]
+    pub fn table_status(&self) -> BTreeMap<String, TableStatus> {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// State and failure counters of the circuit breakers around the database
+    /// and Meilisearch, by dependency name
+    #[allow(dead_code)]
+    pub fn breaker_status(&self) -> BTreeMap<String, BreakerStatus> {
+        self.breakers.status()
+    }
+
+    /// Set up and start syncing every database table `auto_discover` picks up
+    /// that isn't in `seen` yet
+    async fn discover_tables(&self, seen: &mut HashSet<String>) -> Result<()> {
+        let config = self.config();
+        for name in self.db_adapter.get_all_tables().await? {
+            if seen.contains(&name) || config.database.tables.iter().any(|t| t.name_match.is_exact() && t.name == name) {
+                continue;
+            }
+            let adapter = self.db_adapter.as_ref().as_ref();
+            let generated = match config.database.pattern_for(&name) {
+                Some(block) => block.instantiate(adapter, &name).await,
+                None if config.database.discovers(&name) => TableConfig::generate(adapter, &name).await,
+                None => continue,
+            };
+            let table = match generated {
+                Ok(table) => table,
+                Err(ConnectorError::NoPrimaryKey(_)) => {
+                    warn!("Discovered table {} has no primary key, not syncing it", name);
+                    seen.insert(name);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            // A failed setup is retried on the next discovery pass
+            info!("Discovered new table {}, syncing it to index {}", name, table.index_name.as_deref().unwrap_or(&name));
+            self.setup_index(&table).await?;
+            self.spawn_sync_task(table, Duration::ZERO, None);
+            seen.insert(name);
+        }
+        Ok(())
+    }
+
+    /// Apply a new configuration to the running connector without
+    /// restarting it. Tables added to it are set up and start syncing, removed
+    /// ones stop once their sync in progress is done, and changed ones get
+    /// their index settings applied again and restart their sync with the new
+    /// options. Other tables keep syncing undisturbed. Changes outside
+    /// `tables` need a restart; they are left out and listed in the report.
+    pub async fn reload(&self, mut config: Config) -> Result<ReloadReport> {
+        let issues = Self::validate_config(&config);
+        if !issues.is_empty() {
+            return Err(ConnectorError::Config(format!(
+                "Invalid configuration: {}",
+                issues.iter().map(|i| i.to_string()).collect::<Vec<_>>().join("; ")
+            )));
+        }
+        let adapter = self.db_adapter.as_ref().as_ref();
+        config.database.expand_table_patterns(adapter).await?;
+        config.database.verify_tables(adapter).await?;
+
+        let current = self.config();
+        let mut report = ReloadReport { restart_required: changed_options(&current, &config), ..Default::default() };
+        for option in &report.restart_required {
+            warn!("{} changed, restart the connector to apply it", option);
+        }
+        let config = Config {
+            database: DatabaseConfig { tables: config.database.tables, ..current.database.clone() },
+            ..current.clone()
+        };
+
+        let before: HashMap<&str, &TableConfig> = current.database.enabled_tables().map(|t| (t.name.as_str(), t)).collect();
+        let after: HashSet<&str> = config.database.enabled_tables().map(|t| t.name.as_str()).collect();
+        report.removed = current.database.enabled_tables()
+            .filter(|t| !after.contains(t.name.as_str()))
+            .map(|t| t.name.clone())
+            .collect();
+        let mut started = Vec::new();
+        for table in config.database.enabled_tables() {
+            match before.get(table.name.as_str()) {
+                None => report.added.push(table.name.clone()),
+                // Compared as JSON, like the configuration files they come from
+                Some(previous) if serde_json::to_value(previous).ok() != serde_json::to_value(table).ok() => {
+                    report.changed.push(table.name.clone());
+                }
+                Some(_) => continue,
+            }
+            started.push(table.clone());
+        }
+        // Tasks started below read the new configuration, and the adapters
+        // the new filters and retention windows
+        self.row_filters.set_tables(&config.database.tables);
+        *self.config.write().unwrap() = config;
+
+        for name in report.removed.iter().chain(&report.changed) {
+            self.stop_table(name).await;
+        }
+        // A changed table reads every row again, so its documents all take
+        // the new options, not only the rows modified since the last sync
+        self.watermarks.lock().unwrap()
+            .retain(|(table, _), _| !report.removed.contains(table) && !report.changed.contains(table));
+        for name in &report.removed {
+            info!("Table {} was removed from the configuration, no longer syncing it", name);
+            self.status.lock().unwrap().remove(name);
+        }
+        for table in started {
+            // A table whose index can't be set up still syncs, and fails like any other sync
+            if let Err(e) = self.setup_index(&table).await {
+                error!("Error setting up the index of table {}: {}", table.name, e);
+                report.errors.insert(table.name.clone(), e.to_string());
+            }
+            info!("Syncing table {} with its reloaded configuration", table.name);
+            self.spawn_sync_task(table, Duration::ZERO, None);
+        }
+        Ok(report)
+    }
+
+    /// Stop the sync task of one table and wait for it to end
+    async fn stop_table(&self, name: &str) {
+        let Some(task) = self.table_tasks.lock().unwrap().remove(name) else {
+            return;
+        };
+        let _ = task.stop_tx.send(true);
+        if let Err(e) = task.handle.await {
+            error!("Error joining the sync task of table {}: {:?}", name, e);
+        }
+    }
+
+    /// Number of table sync tasks currently running
+    #[allow(dead_code)]
+    pub fn active_sync_tasks(&self) -> usize {
+        let tables = self.table_tasks.lock().unwrap().values().filter(|task| !task.handle.is_finished()).count();
+        tables + self.task_handles.lock().unwrap().iter().filter(|h| !h.is_finished()).count()
+    }
+
     pub async fn stop(&self) -> Result<()> {
         info!("Stopping connector...");
         
@@ -209,10 +771,15 @@ impl Connector {
         }
         
         // Wait for all tasks to complete
-        let handles = {
+        let tables: Vec<TableTask> = self.table_tasks.lock().unwrap().drain().map(|(_, task)| task).collect();
+        let mut handles = {
             let mut guard = self.task_handles.lock().unwrap();
             std::mem::take(&mut *guard)
         };
+        for task in tables {
+            let _ = task.stop_tx.send(true);
+            handles.push(task.handle);
+        }
         
         for handle in handles {
             if let Err(e) = handle.await {
@@ -227,93 +794,720 @@ impl Connector {
     }
 
     async fn setup_indices(&self) -> Result<()> {
-        for table in &self.config.database.tables {
-            let mut settings = Settings::new();
-            
-            if let Some(searchable_attrs) = &table.searchable_attributes {
-                settings = settings.with_searchable_attributes(searchable_attrs.iter().map(|s| s.as_str()));
-            }
-            if let Some(typo_tolerance) = &table.typo_tolerance {
-                let mut typo_settings = meilisearch_sdk::settings::TypoToleranceSettings::default();
-                typo_settings.enabled = Some(typo_tolerance.enabled);
-                settings = settings.with_typo_tolerance(typo_settings);
-            }
-            let index_name = table.index_name.as_deref().unwrap_or(&table.name);
-            
-            info!("Setting up index {} with primary key {}", index_name, &table.primary_key);
-            self.meilisearch_client.setup_index(index_name, settings, Some(&table.primary_key)).await?;
-            
-            // Wait a bit to ensure the index is created
-            sleep(Duration::from_secs(1)).await;
+        self.probe_meilisearch_version().await;
+        for table in self.config().database.enabled_tables() {
+            self.setup_index(table).await?;
         }
         Ok(())
     }
 
-    #[allow(dead_code)]
-    pub async fn sync_once(&self) -> Result<()> {
-        info!("Starting one-time sync...");
-        self.setup_indices().await?;
-        for table in &self.config.database.tables {
-            let index_name = table.index_name.as_deref().unwrap_or(&table.name);
-            sync_table_impl(
-                table,
-                index_name,
-                &self.db_adapter,
-                &self.meilisearch_client,
-                self.config.database.document_batch_size,
-                self.config.database.max_concurrent_batches,
-            ).await?;
+    /// Read and log the Meilisearch version once. Settings the server doesn't
+    /// support are skipped by the client, so an unreadable version only
+    /// warrants a warning.
+    async fn probe_meilisearch_version(&self) {
+        if self.meilisearch_version.lock().unwrap().is_some() {
+            return;
+        }
+        match self.meilisearch_client.version().await {
+            Ok(Some(version)) => {
+                info!("Connected to Meilisearch {}", version);
+                *self.meilisearch_version.lock().unwrap() = Some(version);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Could not read the Meilisearch version: {}", e),
         }
-        Ok(())
     }
-}
 
-fn ensure_valid_primary_key(
-    doc: &Value,
-    table: &TableConfig,
-) -> Option<(String, Value)> {
-    // Try to get the primary key value
-    if let Some(id) = doc.get(&table.primary_key) {
-        // Check if the ID is valid
-        if id.is_null() {
-            debug!("Document has null primary key: {}", table.primary_key);
-            return None;
+    /// Version of the Meilisearch server, once probed by `start`, `sync_once`
+    /// or `apply_settings`
+    pub fn meilisearch_version(&self) -> Option<String> {
+        self.meilisearch_version.lock().unwrap().clone()
+    }
+
+    async fn setup_index(&self, table: &TableConfig) -> Result<()> {
+        self.setup_named_index(table, table.index_name.as_deref().unwrap_or(&table.name)).await
+    }
+
+    // Set up `index_name` with the settings of `table`, which is the table's
+    // own index except for the versioned copies of `cutover`
+    async fn setup_named_index(&self, table: &TableConfig, index_name: &str) -> Result<()> {
+        let mut settings = Settings::new();
+        
+        if let Some(searchable_attrs) = &table.searchable_attributes {
+            settings = settings.with_searchable_attributes(searchable_attrs.iter().map(|s| s.as_str()));
+        }
+        // Constant fields and the source table exist to tell sources apart and
+        // split columns to be faceted on, so make them filterable
+        let mut filterable: Vec<&str> = table.constant_fields.keys()
+            .chain(table.array_split.keys())
+            .chain(&table.filterable_attributes)
+            .map(|k| k.as_str())
+            .chain(table.add_sync_timestamp.then_some(SYNCED_AT_FIELD))
+            .chain(table.add_source_table.then_some(SOURCE_TABLE_FIELD))
+            .collect();
+        if !filterable.is_empty() {
+            filterable.sort_unstable();
+            filterable.dedup();
+            settings = settings.with_filterable_attributes(filterable);
+        }
+        let mut sortable: Vec<&str> = table.sortable_attributes.iter()
+            .map(|k| k.as_str())
+            .chain(table.add_sync_timestamp.then_some(SYNCED_AT_FIELD))
+            .collect();
+        if !sortable.is_empty() {
+            sortable.sort_unstable();
+            sortable.dedup();
+            settings = settings.with_sortable_attributes(sortable);
+        }
+        if let Some(typo_tolerance) = &table.typo_tolerance {
+            let typo_settings = meilisearch_sdk::settings::TypoToleranceSettings {
+                enabled: Some(typo_tolerance.enabled),
+                ..Default::default()
+            };
+            settings = settings.with_typo_tolerance(typo_settings);
+        }
+        if let Some(search_cutoff_ms) = table.search_cutoff_ms {
+            settings = settings.with_search_cutoff(search_cutoff_ms);
+        }
+        if table.suppress_duplicates {
+            settings = settings.with_distinct_attribute(Some(CONTENT_HASH_FIELD));
+        }
+        
+        // Meilisearch keeps the primary key an index was created with, so a changed
+        // key would only surface later as failing document additions. An
+        // inferred key is whatever the index has.
+        let existing = if table.infer_primary_key {
+            None
+        } else {
+            self.meilisearch_client.index_primary_key(index_name).await?
+        };
+        if let Some(existing) = existing {
+            if existing != table.index_primary_key() {
+                if !table.reindex_on_primary_key_change {
+                    return Err(ConnectorError::Config(format!(
+                        "Index '{}' already exists with primary key '{}' but table '{}' is configured with '{}'. \
+                         Delete the index to reindex with the new key, or set reindex_on_primary_key_change = true",
+                        index_name, existing, table.name, table.index_primary_key()
+                    )));
+                }
+                warn!("Index {} has primary key '{}' instead of '{}', deleting it to reindex",
+                      index_name, existing, table.index_primary_key());
+                self.meilisearch_client.delete_index(index_name).await?;
+            }
         }
         
-        // Convert ID to string for mapping
-        let id_str = id.to_string().trim_matches('"').to_string();
-        if id_str.is_empty() || id_str == "null" || id_str == "0" {
-            debug!("Document has invalid primary key value: {}", id_str);
-            return None;
+        let raw_settings = raw_settings_overrides(table, &settings)?;
+        let primary_key = (!table.infer_primary_key).then_some(table.index_primary_key());
+        match primary_key {
+            Some(primary_key) => info!("Setting up index {} with primary key {}", index_name, primary_key),
+            None => info!("Setting up index {}, letting Meilisearch infer its primary key", index_name),
+        }
+        self.meilisearch_client.setup_index(index_name, settings, primary_key).await?;
+        if let Some(raw_settings) = raw_settings {
+            self.meilisearch_client.set_raw_settings(index_name, &raw_settings).await?;
         }
         
-        // Valid primary key found
-        return Some((id_str, doc.clone()));
-    } 
-    
-    // Primary key is missing
-    debug!("Document missing primary key field: {}", table.primary_key);
-    None
-}
+        // Wait a bit to ensure the index is created
+        sleep(Duration::from_secs(1)).await;
+        Ok(())
+    }
 
-fn process_document_obj(
-    table: &TableConfig,
-    doc: serde_json::Map<String, Value>,
-    display_id: String,
-    max_text_length: usize,
-    max_fields: usize,
-) -> Result<Value> {
-    println!("[process_document_obj] Processing doc ID: {}", display_id);
-    let mut processed_doc = serde_json::Map::new();
+    /// Push index settings for one configured table (or all enabled ones)
+    /// without syncing any documents. Returns the names of the indices updated.
+    pub async fn apply_settings(&self, table: Option<&str>) -> Result<Vec<String>> {
+        let config = self.config();
+        let tables: Vec<&TableConfig> = match table {
+            Some(name) => {
+                let table = config.database.tables.iter()
+                    .find(|t| t.name_match.is_exact() && t.name == name)
+                    .ok_or_else(|| ConnectorError::Config(format!("Table '{}' is not in the configuration", name)))?;
+                vec![table]
+            }
+            None => config.database.enabled_tables().collect(),
+        };
+
+        self.probe_meilisearch_version().await;
+        let mut indices = Vec::new();
+        for table in tables {
+            self.setup_index(table).await?;
+            indices.push(table.index_name.clone().unwrap_or_else(|| table.name.clone()));
+        }
+        Ok(indices)
+    }
+
+    /// Check that there is something to sync and set up the index of every
+    /// enabled table. `start` begins with it; library users scheduling syncs
+    /// themselves call it once before `sync_all_once`.
+    pub async fn setup(&self) -> Result<()> {
+        let config = self.config();
+        if self.require_tables && !config.database.auto_discover && config.database.enabled_tables().next().is_none() {
+            return Err(ConnectorError::Config(
+                "The configuration has no syncable tables; add at least one [[database.tables]] entry".to_string(),
+            ));
+        }
+        self.setup_indices().await
+    }
+
+    /// Sync every enabled table once, one after the other, without spawning
+    /// tasks or waiting for a poll interval. A failed table doesn't stop the
+    /// others; its error is in the report. Failed syncs are retried as in
+    /// the poll loop, and the outcome shows in `table_status`.
+    #[allow(dead_code)]
+    pub async fn sync_all_once(&self) -> RunReport {
+        let start = Instant::now();
+        let options = self.sync_options();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        let mut run = RunReport::default();
+        for table in self.config().database.enabled_tables() {
+            let index_name = table.index_name.as_deref().unwrap_or(&table.name);
+            let result = sync_table_with_retries(table, index_name, &self.db_adapter, &self.meilisearch_client, &options, &mut shutdown_rx).await;
+            record_status(&self.status, &table.name, &result);
+            match result {
+                Ok(report) => {
+                    log_sync_profile(&report, self.profile);
+                    run.reports.push(report);
+                }
+                Err(e) => {
+                    error!("Error syncing table {}: {}", table.name, e);
+                    run.errors.insert(table.name.clone(), e.to_string());
+                }
+            }
+        }
+        run.duration = start.elapsed();
+        run
+    }
+
+    /// Work out what `sync_all_once` would change without changing anything:
+    /// index setup and document writes go to a `PlanRecorder` instead of
+    /// Meilisearch, which also serves the indexed documents the changes are
+    /// computed from. With `summary_only` the plan keeps only document ids.
+    pub async fn plan(&self, summary_only: bool) -> Result<(SyncPlan, RunReport)> {
+        // Fingerprint the rows first, so rows changing while the plan is
+        // computed show up when it's applied
+        let mut tables = Vec::new();
+        if !summary_only {
+            for table in self.config().database.enabled_tables() {
+                tables.push(TableSignature::compute(table, self.db_adapter.as_ref().as_ref()).await?);
+            }
+        }
+        let recorder = Arc::new(PlanRecorder::new(self.meilisearch_client.clone(), summary_only));
+        let dry_run = Connector {
+            meilisearch_client: recorder.clone(),
+            // Draining the queue would send its operations
+            queue: None,
+            // Nothing is sent, so real syncs must not resume from a dry run
+            watermarks: Watermarks::default(),
+            status: Arc::new(Mutex::new(BTreeMap::new())),
+            ..self.clone()
+        };
+        dry_run.setup().await?;
+        let run = dry_run.sync_all_once().await;
+        Ok((SyncPlan { tables, ..recorder.plan() }, run))
+    }
+
+    /// Make the changes of a plan computed by `plan`, from the documents it
+    /// holds rather than the database's rows. Tables whose rows changed since
+    /// are reported, and the plan is applied anyway. Applying a plan again
+    /// leaves the indexes as they are.
+    pub async fn apply(&self, plan: &SyncPlan) -> Result<ApplyReport> {
+        let config = self.config();
+        if plan.summary_only {
+            return Err(ConnectorError::Config("A plan without its documents (summary only) can't be applied".to_string()));
+        }
+        let mut report = ApplyReport::default();
+        for signature in &plan.tables {
+            let Some(table) = config.database.enabled_tables().find(|t| t.name == signature.table) else {
+                warn!("Table {} of the plan is not in the configuration", signature.table);
+                continue;
+            };
+            if TableSignature::compute(table, self.db_adapter.as_ref().as_ref()).await? != *signature {
+                warn!("Rows of table {} changed since the plan was computed", table.name);
+                report.changed_tables.push(table.name.clone());
+            }
+        }
+
+        self.setup().await?;
+        let batch_size = Some(config.database.document_batch_size);
+        for index in &plan.indexes {
+            let documents: Vec<Value> = index.added.iter().filter_map(|added| added.document.clone())
+                .chain(index.updated.iter().filter_map(|updated| updated.after.clone()))
+                .collect();
+            let add = async {
+                if !documents.is_empty() {
+                    self.meilisearch_client.add_or_update_documents(&index.index, documents, batch_size).await?;
+                }
+                Ok::<_, ConnectorError>(())
+            };
+            let delete = async {
+                if !index.deleted.is_empty() {
+                    self.meilisearch_client.delete_documents(&index.index, &index.deleted, None).await?;
+                }
+                Ok::<_, ConnectorError>(())
+            };
+            match config.database.operation_order {
+                OperationOrder::DeleteThenAdd => {
+                    delete.await?;
+                    add.await?;
+                }
+                OperationOrder::AddThenDelete => {
+                    add.await?;
+                    delete.await?;
+                }
+            }
+            self.meilisearch_client.wait_for_tasks(&index.index).await?;
+            info!(
+                "Applied plan to index {}: {} added, {} updated, {} deleted",
+                index.index, index.added.len(), index.updated.len(), index.deleted.len()
+            );
+            report.indexes.push(AppliedIndex {
+                index: index.index.clone(),
+                documents_added: index.added.len(),
+                documents_updated: index.updated.len(),
+                documents_deleted: index.deleted.len(),
+            });
+        }
+        Ok(report)
+    }
+
+    /// Set up the indexes and sync every enabled table once, in order,
+    /// stopping at the first table that fails. Each report holds the table's
+    /// counts; `sync_all_once` syncs the other tables past a failure.
+    #[allow(dead_code)]
+    pub async fn sync_once(&self) -> Result<Vec<SyncReport>> {
+        info!("Starting one-time sync...");
+        self.setup_indices().await?;
+        let options = self.sync_options();
+        let mut reports = Vec::new();
+        for table in self.config().database.enabled_tables() {
+            let index_name = table.index_name.as_deref().unwrap_or(&table.name);
+            let report = sync_table_impl(
+                table,
+                index_name,
+                &self.db_adapter,
+                &self.meilisearch_client,
+                &options,
+            ).await?;
+            log_sync_profile(&report, self.profile);
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+
+    /// Compare up to `sample` random rows of one configured table (or all
+    /// enabled ones) with their indexed documents, field by field. Nothing
+    /// is written to the index.
+    pub async fn verify(&self, table: Option<&str>, sample: usize) -> Result<Vec<VerifyReport>> {
+        let config = self.config();
+        let tables: Vec<&TableConfig> = match table {
+            Some(name) => {
+                let table = config.database.tables.iter()
+                    .find(|t| t.name_match.is_exact() && t.name == name)
+                    .ok_or_else(|| ConnectorError::Config(format!("Table '{}' is not in the configuration", name)))?;
+                vec![table]
+            }
+            None => config.database.enabled_tables().collect(),
+        };
+
+        let mut reports = Vec::new();
+        for table in tables {
+            let index_name = table.index_name.as_deref().unwrap_or(&table.name);
+            let resolved;
+            let table = if table.infer_primary_key {
+                resolved = resolve_primary_key(table, index_name, &self.db_adapter, &self.meilisearch_client).await?.0;
+                &resolved
+            } else {
+                table
+            };
+            let columns = table.selected_columns();
+            let mut records = self.db_adapter.fetch_random_records(&table.name, sample).await?;
+            // Rows outside the retention window are rightly missing from the index
+            retain_window(table, &mut records, SystemTime::now());
+            let records = records.into_iter()
+                .map(|record| match &columns {
+                    Some(columns) => project_record(record, columns),
+                    None => record,
+                });
+            let lookups = load_lookups(table, &self.db_adapter).await?;
+            let mut report = VerifyReport {
+                table: table.name.clone(),
+                index: index_name.to_string(),
+                ..Default::default()
+            };
+            for mut record in records {
+                if let Value::Object(obj) = &mut record {
+                    apply_lookups(table, &lookups, obj);
+                }
+                let Some((id, expected)) = transform_record(table, record) else {
+                    continue;
+                };
+                report.checked += 1;
+                match self.meilisearch_client.get_document(index_name, &id).await? {
+                    Some(indexed) => report.mismatches.extend(compare_documents(&id, &expected, &indexed)),
+                    None => report.mismatches.push(FieldMismatch {
+                        id,
+                        field: None,
+                        database: Some(expected),
+                        index: None,
+                    }),
+                }
+            }
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+
+    /// Rebuild the index of one configured table (or of every enabled table
+    /// with `use_versioned_indexes`) as a new versioned copy,
+    /// `<index>_v<N>`, with a full sync, and once Meilisearch has processed
+    /// it swap it with the index searches use. The copy then holds the
+    /// previous documents and is deleted unless `keep_previous` is set.
+    pub async fn cutover(&self, table: Option<&str>, keep_previous: bool) -> Result<Vec<CutoverReport>> {
+        let config = self.config();
+        let tables: Vec<&TableConfig> = match table {
+            Some(name) => {
+                let table = config.database.tables.iter()
+                    .find(|t| t.name_match.is_exact() && t.name == name)
+                    .ok_or_else(|| ConnectorError::Config(format!("Table '{}' is not in the configuration", name)))?;
+                if !table.use_versioned_indexes {
+                    return Err(ConnectorError::Config(format!(
+                        "Table '{}' doesn't set use_versioned_indexes = true", name
+                    )));
+                }
+                vec![table]
+            }
+            None => config.database.enabled_tables().filter(|t| t.use_versioned_indexes).collect(),
+        };
+        if self.in_maintenance() {
+            return Err(ConnectorError::Config("Can't cut over indexes in maintenance mode".to_string()));
+        }
+
+        self.probe_meilisearch_version().await;
+        let options = self.sync_options();
+        let mut reports = Vec::new();
+        for table in tables {
+            let index_name = table.index_name.as_deref().unwrap_or(&table.name);
+            let existing = self.meilisearch_client.list_indexes().await?;
+            let version = existing.iter()
+                .filter_map(|name| index_version(index_name, name))
+                .max()
+                .unwrap_or(0) + 1;
+            let versioned_index = format!("{}_v{}", index_name, version);
+
+            info!("Rebuilding index {} of table {} as {}", index_name, table.name, versioned_index);
+            self.setup_named_index(table, &versioned_index).await?;
+            let sync = sync_table_impl(table, &versioned_index, &self.db_adapter, &self.meilisearch_client, &options).await?;
+            if sync.batches_failed > 0 {
+                return Err(ConnectorError::Meilisearch(format!(
+                    "{} batches of documents could not be added to {}, not swapping it with {}",
+                    sync.batches_failed, versioned_index, index_name
+                )));
+            }
+            self.meilisearch_client.wait_for_tasks(&versioned_index).await?;
+            // Swapping needs both indexes, a table synced for the first time has none yet
+            if !existing.iter().any(|name| name == index_name) {
+                self.setup_index(table).await?;
+            }
+            self.meilisearch_client.swap_indexes(index_name, &versioned_index).await?;
+            info!("Index {} now serves the {} documents of {}", index_name, sync.documents_added, versioned_index);
+            if !keep_previous {
+                self.meilisearch_client.delete_index(&versioned_index).await?;
+            }
+            reports.push(CutoverReport {
+                table: table.name.clone(),
+                index: index_name.to_string(),
+                versioned_index,
+                kept_previous: keep_previous,
+                sync,
+            });
+        }
+        Ok(reports)
+    }
+}
+
+/// Options outside `tables` that differ between two configurations, as
+/// `section.option`
+fn changed_options(old: &Config, new: &Config) -> Vec<String> {
+    let sections = |config: &Config| {
+        let mut value = serde_json::to_value(config).unwrap_or_default();
+        if let Some(database) = value.get_mut("database").and_then(Value::as_object_mut) {
+            database.remove("tables");
+        }
+        value
+    };
+    let (old, new) = (sections(old), sections(new));
+    let empty = serde_json::Map::new();
+    let mut changed = Vec::new();
+    for section in ["meilisearch", "database"] {
+        let old = old[section].as_object().unwrap_or(&empty);
+        let new = new[section].as_object().unwrap_or(&empty);
+        let options: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+        changed.extend(options.into_iter()
+            .filter(|option| old.get(*option) != new.get(*option))
+            .map(|option| format!("{}.{}", section, option)));
+    }
+    changed
+}
+
+/// Version of `name` if it's a versioned copy of `index_name`, as `cutover`
+/// names them
+fn index_version(index_name: &str, name: &str) -> Option<u64> {
+    name.strip_prefix(index_name)?.strip_prefix("_v")?.parse().ok()
+}
+
+/// Transform one database row the way a sync would, returning its document
+/// ID and the document. `None` if the row has no valid primary key or fails
+/// the transform.
+fn transform_record(table: &TableConfig, record: Value) -> Option<(String, Value)> {
+    let (id_str, record) = ensure_valid_primary_key(&record, table).ok()?;
+    let Value::Object(obj) = record else {
+        return None;
+    };
+    let document = process_document_obj(
+        table, obj, id_str.clone(), MAX_TEXT_LENGTH, MAX_FIELDS, None, None, &mut SyncReport::default(),
+    ).ok()?;
+    Some((table.document_id(&id_str), document))
+}
+
+/// Field level differences between the expected and the indexed document.
+/// `_vectors` is skipped, Meilisearch only returns it when asked to.
+fn compare_documents(id: &str, expected: &Value, indexed: &Value) -> Vec<FieldMismatch> {
+    let empty = serde_json::Map::new();
+    let expected = expected.as_object().unwrap_or(&empty);
+    let indexed = indexed.as_object().unwrap_or(&empty);
+    let fields: std::collections::BTreeSet<&String> = expected.keys().chain(indexed.keys())
+        // Embeddings aren't returned by default, the sync time differs on
+        // every sync and the content hash is only computed when sending
+        .filter(|field| !["_vectors", SYNCED_AT_FIELD, CONTENT_HASH_FIELD].contains(&field.as_str()))
+        .collect();
+    fields.into_iter()
+        .filter(|field| expected.get(*field) != indexed.get(*field))
+        .map(|field| FieldMismatch {
+            id: id.to_string(),
+            field: Some(field.clone()),
+            database: expected.get(field).cloned(),
+            index: indexed.get(field).cloned(),
+        })
+        .collect()
+}
+
+/// The `raw_settings` of a table without the settings the connector already
+/// sets from its own fields, which take precedence. `None` if nothing is left.
+fn raw_settings_overrides(table: &TableConfig, settings: &Settings) -> Result<Option<Value>> {
+    let Some(raw) = &table.raw_settings else {
+        return Ok(None);
+    };
+    let raw = raw.as_object().ok_or_else(|| ConnectorError::Config(format!(
+        "raw_settings of table '{}' must be a table of Meilisearch settings", table.name
+    )))?;
+    let modeled = serde_json::to_value(settings)
+        .map_err(|e| ConnectorError::Config(format!("Failed to serialize index settings: {}", e)))?;
+
+    let mut extra = serde_json::Map::new();
+    for (key, value) in raw {
+        if modeled.get(key).is_some() {
+            warn!("Ignoring raw_settings.{} of table {}, the connector already sets it", key, table.name);
+        } else {
+            extra.insert(key.clone(), value.clone());
+        }
+    }
+    Ok((!extra.is_empty()).then_some(Value::Object(extra)))
+}
+
+/// Per-sync tuning knobs taken from the database configuration
+#[derive(Clone)]
+struct SyncOptions {
+    batch_size: usize,
+    max_concurrent_batches: usize,
+    warn_field_bytes: Option<usize>,
+    queue: Option<Arc<DurableQueue>>,
+    primary_adapter: Option<Arc<Box<dyn DatabaseAdapter>>>,
+    max_replica_lag: Option<Duration>,
+    sync_retries: u32,
+    sync_retry_delay: Duration,
+    operation_order: OperationOrder,
+    on_transform_error: TransformErrorPolicy,
+    dead_letters: Option<DeadLetterFile>,
+    max_documents_per_sync: Option<usize>,
+    max_delete_ratio: Option<f64>,
+    allow_mass_delete: bool,
+    maintenance: Option<Arc<Maintenance>>,
+    watermarks: Watermarks,
+}
+
+impl SyncOptions {
+    fn in_maintenance(&self) -> bool {
+        self.maintenance.as_ref().is_some_and(|m| m.active())
+    }
+}
+
+impl From<&DatabaseConfig> for SyncOptions {
+    fn from(config: &DatabaseConfig) -> Self {
+        Self {
+            batch_size: config.document_batch_size,
+            max_concurrent_batches: config.max_concurrent_batches,
+            warn_field_bytes: config.warn_field_bytes,
+            queue: None,
+            primary_adapter: None,
+            max_replica_lag: config.max_replica_lag_seconds.map(Duration::from_secs),
+            sync_retries: config.sync_retries,
+            sync_retry_delay: Duration::from_secs(config.sync_retry_delay_seconds),
+            operation_order: config.operation_order,
+            on_transform_error: config.on_transform_error,
+            dead_letters: config.dead_letter_path.as_deref().map(DeadLetterFile::new),
+            max_documents_per_sync: config.max_documents_per_sync,
+            max_delete_ratio: config.max_delete_ratio,
+            allow_mass_delete: false,
+            maintenance: None,
+            watermarks: Watermarks::default(),
+        }
+    }
+}
+
+// String form of a primary key value, as used for document ids
+fn primary_key_string(id: &Value) -> String {
+    id.to_string().trim_matches('"').to_string()
+}
+
+/// A primary key value converted to the table's `primary_key_type`, `None`
+/// if it can't be
+fn coerce_primary_key(id: &Value, primary_key_type: PrimaryKeyType) -> Option<Value> {
+    match (primary_key_type, id) {
+        (PrimaryKeyType::String, Value::Number(_)) => Some(Value::String(primary_key_string(id))),
+        (PrimaryKeyType::Integer, Value::String(s)) => s.parse::<i64>().ok().map(Value::from),
+        (PrimaryKeyType::Integer, Value::Number(n)) if !n.is_i64() && !n.is_u64() => {
+            n.as_f64().filter(|f| f.fract() == 0.0).map(|f| Value::from(f as i64))
+        }
+        _ => Some(id.clone()),
+    }
+}
+
+/// Why a row's primary key can't be used as a document id
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InvalidPrimaryKey {
+    Missing,
+    Null,
+    Empty,
+    /// Listed in `invalid_primary_keys`, or not convertible to `primary_key_type`
+    Invalid,
+}
+
+fn ensure_valid_primary_key(
+    doc: &Value,
+    table: &TableConfig,
+) -> std::result::Result<(String, Value), InvalidPrimaryKey> {
+    ensure_valid_key(doc, table, &table.primary_key)
+}
+
+/// `ensure_valid_primary_key` for the key held in `key`, which differs from
+/// the primary key column for indexed documents when `field_map` renames it
+fn ensure_valid_key(
+    doc: &Value,
+    table: &TableConfig,
+    key: &str,
+) -> std::result::Result<(String, Value), InvalidPrimaryKey> {
+    let Some(id) = doc.get(key) else {
+        return Err(InvalidPrimaryKey::Missing);
+    };
+    if id.is_null() {
+        return Err(InvalidPrimaryKey::Null);
+    }
+
+    // Convert ID to string for mapping
+    let id_str = primary_key_string(id);
+    if id_str.is_empty() {
+        return Err(InvalidPrimaryKey::Empty);
+    }
+    if table.invalid_primary_keys.contains(&id_str) {
+        debug!("Primary key value {} is listed in invalid_primary_keys", id_str);
+        return Err(InvalidPrimaryKey::Invalid);
+    }
+
+    let Some(coerced) = coerce_primary_key(id, table.primary_key_type) else {
+        debug!("Primary key value {} is not a valid {:?}", id, table.primary_key_type);
+        return Err(InvalidPrimaryKey::Invalid);
+    };
+    let mut doc = doc.clone();
+    if coerced != *id {
+        doc[key] = coerced;
+    }
+    Ok((id_str, doc))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_document_obj(
+    table: &TableConfig,
+    mut doc: serde_json::Map<String, Value>,
+    display_id: String,
+    max_text_length: usize,
+    max_fields: usize,
+    warn_field_bytes: Option<usize>,
+    synced_at: Option<&str>,
+    report: &mut SyncReport,
+) -> Result<Value> {
+    trace!("Processing document {}", display_id);
+    let mut processed_doc = serde_json::Map::new();
     
-    // Add the primary key
+    // Add the primary key, as a prefixed string when the table has an id_prefix
     if let Some(id_value) = doc.get(&table.primary_key) {
         debug!("Processing document with ID: {} ({:?})", display_id, id_value);
-        processed_doc.insert(table.primary_key.clone(), id_value.clone());
+        let id_value = match &table.id_prefix {
+            Some(_) => Value::String(table.document_id(&primary_key_string(id_value))),
+            None => id_value.clone(),
+        };
+        processed_doc.insert(table.primary_key.clone(), id_value);
     } else {
         return Err(ConnectorError::Config(format!("Document missing primary key: {}", table.primary_key)));
     }
     
+    // Epoch companions for date/time columns, so they can be sorted and filtered numerically
+    let mut timestamp_companions = Vec::new();
+    for field in &table.timestamp_fields {
+        match doc.get(field) {
+            None | Some(Value::Null) => {}
+            Some(value) => match parse_timestamp(value) {
+                Some(epoch) => timestamp_companions.push((format!("{}{}", field, table.timestamp_suffix), epoch)),
+                None => warn!("Could not parse timestamp field '{}' in document {} of table {}: {}",
+                              field, display_id, table.name, value),
+            },
+        }
+    }
+    
+    // Precomputed embedding, moved out of the regular fields into _vectors
+    let vector = match (&table.vector_field, &table.embedder_name) {
+        (Some(field), Some(embedder)) => match doc.remove(field) {
+            None | Some(Value::Null) => None,
+            Some(value) => {
+                let vector = parse_vector(&value).ok_or_else(|| {
+                    warn!("Skipping document {} of table {}: '{}' is not a vector of numbers",
+                          display_id, table.name, field);
+                    ConnectorError::Config(format!("Malformed vector in document {}", display_id))
+                })?;
+                if let Some(dimensions) = table.vector_dimensions.filter(|d| *d != vector.len()) {
+                    warn!("Skipping document {} of table {}: vector has {} dimensions, expected {}",
+                          display_id, table.name, vector.len(), dimensions);
+                    return Err(ConnectorError::Config(format!("Vector dimension mismatch in document {}", display_id)));
+                }
+                Some((embedder.clone(), vector))
+            }
+        },
+        _ => None,
+    };
+    
+    // References to binary columns, built before the row's columns are consumed
+    let mut blob_references: HashMap<&str, String> = HashMap::new();
+    for (field, template) in &table.blob_reference_template {
+        if doc.get(field).is_none_or(Value::is_null) {
+            continue;
+        }
+        match render_template(template, &doc) {
+            Some(reference) => {
+                blob_references.insert(field, reference);
+            }
+            None => warn!("Could not build the reference of field '{}' in document {} of table {}, a placeholder of '{}' is missing or null",
+                          field, display_id, table.name, template),
+        }
+    }
+    
     // Process other fields with size limits
     let mut field_count = 1; // Already counted primary key
     let mut problematic_fields = Vec::new();
@@ -323,17 +1517,76 @@ fn process_document_obj(
             continue; // Already added
         }
         
+        // Left out by fields_to_index or exclude_fields, e.g. a sensitive column
+        if !table.indexes_column(&key) {
+            continue;
+        }
+        
         // Check if we're hitting field count limits for very wide tables
         if field_count >= max_fields {
             warn!("Document {} has too many fields, limiting to {} fields", display_id, max_fields);
             break;
         }
         
+        // Binary columns only reach Meilisearch as a reference, null ones not at all
+        if table.blob_reference_template.contains_key(&key) {
+            if let Some(reference) = blob_references.remove(key.as_str()) {
+                processed_doc.insert(key.clone(), Value::String(reference));
+                field_count += 1;
+            }
+            continue;
+        }
+        
+        // Track field sizes before any truncation so large columns are visible early
+        let field_bytes = match value.as_str() {
+            Some(text) => text.len(),
+            None => serde_json::to_string(&value).map(|v| v.len()).unwrap_or_default(),
+        };
+        if field_bytes > report.max_field_bytes {
+            report.max_field_bytes = field_bytes;
+            report.max_field_name = Some(key.clone());
+        }
+        if warn_field_bytes.is_some_and(|limit| field_bytes > limit) {
+            warn!("Field '{}' in document {} of table {} is {} bytes, above warn_field_bytes",
+                  key, display_id, table.name, field_bytes);
+            report.oversized_fields += 1;
+        }
+        
+        // Sensitive columns only reach Meilisearch as a digest
+        if table.hash_fields.contains(&key) {
+            if let Some(digest) = hash_value(&value, table.hash_algorithm, table.hash_salt.as_deref()) {
+                processed_doc.insert(key.clone(), Value::String(digest));
+                field_count += 1;
+                continue;
+            }
+        }
+        
+        // Multi-valued columns become arrays so Meilisearch can facet on each value
+        if let Some(separator) = table.array_split.get(&key) {
+            match split_array(&value, separator) {
+                Some(items) => processed_doc.insert(key.clone(), Value::Array(items)),
+                None => {
+                    warn!("Field '{}' in document {} of table {} can't be split into an array: {}",
+                          key, display_id, table.name, value);
+                    processed_doc.insert(key.clone(), value)
+                }
+            };
+            field_count += 1;
+            continue;
+        }
+        
         // Check for null values or other problematic data
         if value.is_null() {
             debug!("Document {} has null value for field {}", display_id, key);
-            // Use an empty string instead of null
-            processed_doc.insert(key.clone(), Value::String(String::new()));
+            match table.null_handling_of(&key) {
+                NullHandling::Empty => {
+                    processed_doc.insert(key.clone(), Value::String(String::new()));
+                }
+                NullHandling::Keep => {
+                    processed_doc.insert(key.clone(), Value::Null);
+                }
+                NullHandling::Omit => {}
+            }
             problematic_fields.push(format!("{}=null", key));
             continue;
         }
@@ -355,6 +1608,40 @@ fn process_document_obj(
         field_count += 1;
     }
     
+    // Columns renamed once every option naming them was applied
+    if !table.field_map.is_empty() {
+        processed_doc = processed_doc.into_iter()
+            .map(|(key, value)| (table.attribute_name(&key).to_string(), value))
+            .collect();
+    }
+    
+    for (name, epoch) in timestamp_companions {
+        processed_doc.insert(name, Value::from(epoch));
+    }
+    
+    if let Some((embedder, vector)) = vector {
+        let mut vectors = serde_json::Map::new();
+        vectors.insert(embedder, Value::from(vector));
+        processed_doc.insert("_vectors".to_string(), Value::Object(vectors));
+    }
+    
+    // Constant metadata shared by every document of the table
+    for (name, value) in &table.constant_fields {
+        if processed_doc.contains_key(name) && !table.constant_fields_override {
+            debug!("Document {} already has a '{}' column, keeping it over the constant field", display_id, name);
+            continue;
+        }
+        processed_doc.insert(name.clone(), value.clone());
+    }
+    
+    if table.add_source_table {
+        processed_doc.insert(SOURCE_TABLE_FIELD.to_string(), Value::String(table.name.clone()));
+    }
+    
+    if let Some(synced_at) = synced_at {
+        processed_doc.insert(SYNCED_AT_FIELD.to_string(), Value::String(synced_at.to_string()));
+    }
+    
     // If we found problematic fields, log them
     if !problematic_fields.is_empty() {
         debug!("Document {} has problematic fields: {}", display_id, problematic_fields.join(", "));
@@ -371,176 +1658,1516 @@ fn process_document_obj(
         return Err(ConnectorError::Config(format!("Document too large: {} ({}MB)", display_id, serialized.len() / 1_000_000)));
     }
     
-    // Return the processed document
-    let result = Ok(processed_value);
-    println!("[process_document_obj] Returning for ID {}: {:?}", display_id, result);
-    result
+    Ok(processed_value)
+}
+
+/// Sleep until the table's active hours window opens, without touching the
+/// database. Returns false when shutdown is signalled while waiting.
+async fn wait_for_active_hours(active_hours: Option<&ActiveHours>, table_name: &str, shutdown_rx: &mut watch::Receiver<bool>) -> bool {
+    let Some(active_hours) = active_hours else {
+        return true;
+    };
+    let wait = active_hours.time_until_open(chrono::Utc::now());
+    if wait.is_zero() {
+        return true;
+    }
+    info!("Table {} is outside its active hours {}, waiting {:?} before syncing", table_name, active_hours, wait);
+    tokio::select! {
+        _ = sleep(wait) => true,
+        _ = shutdown_rx.changed() => {
+            info!("Shutdown signal received outside active hours, stopping sync for table: {}", table_name);
+            false
+        }
+    }
+}
+
+/// Run `sync_table_impl`, retrying a failed sync up to `sync_retries` times
+/// with `sync_retry_delay` in between before giving up until the next poll
+async fn sync_table_with_retries(
+    table: &TableConfig,
+    index_name: &str,
+    db_adapter: &Arc<Box<dyn DatabaseAdapter>>,
+    meilisearch_client: &Arc<dyn MeilisearchClientTrait>,
+    options: &SyncOptions,
+    shutdown_rx: &mut watch::Receiver<bool>,
+) -> Result<SyncReport> {
+    let mut attempt = 0;
+    loop {
+        match sync_table_impl(table, index_name, db_adapter, meilisearch_client, options).await {
+            Ok(report) => return Ok(report),
+            // Retrying can't help before the circuit's cooldown is over
+            Err(e @ ConnectorError::CircuitOpen(_)) => return Err(e),
+            Err(e) if attempt < options.sync_retries => {
+                attempt += 1;
+                warn!("Sync of table {} failed, retrying ({}/{}) in {:?}: {}",
+                      table.name, attempt, options.sync_retries, options.sync_retry_delay, e);
+                tokio::select! {
+                    _ = sleep(options.sync_retry_delay) => {}
+                    _ = shutdown_rx.changed() => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
 }
 
+#[instrument(name = "sync", skip_all, fields(table = %table.name, index = %index_name))]
 async fn sync_table_impl(
     table: &TableConfig,
     index_name: &str,
     db_adapter: &Arc<Box<dyn DatabaseAdapter>>,
     meilisearch_client: &Arc<dyn MeilisearchClientTrait>,
-    batch_size: usize,
-    max_concurrent_batches: usize,
-) -> Result<()> {
+    options: &SyncOptions,
+) -> Result<SyncReport> {
+    let batch_size = options.batch_size;
+    let max_concurrent_batches = options.max_concurrent_batches;
     info!("Syncing table {} to index {}", table.name, index_name);
+    let sync_start = Instant::now();
+    let mut report = SyncReport {
+        table: table.name.clone(),
+        index: index_name.to_string(),
+        ..Default::default()
+    };
     
-    // Fetch documents from Meilisearch and database
-    let (meili_docs, db_docs) = tokio::join!(
-        meilisearch_client.get_all_documents(index_name),
-        db_adapter.fetch_all_records(&table.name)
-    );
-    
-    let meili_docs = meili_docs?;
-    let db_docs = db_docs?;
-    println!("[sync_table_impl] Found {} docs in DB for table '{}': {:#?}", db_docs.len(), table.name, db_docs);
-    
-    info!("Found {} documents in Meilisearch and {} in database", 
-          meili_docs.len(), db_docs.len());
-
-    // Extract IDs for comparison
-    let mut meili_ids = std::collections::HashMap::new();
-    let mut db_map = std::collections::HashMap::new();
-
-    // Add debug information for document stats
-    let mut missing_pk_count = 0;
-    let mut invalid_pk_count = 0;
-    let mut valid_docs = 0;
+    // Reconcile on the key Meilisearch uses for the index
+    let resolved;
+    let (table, predicted_key) = if table.infer_primary_key {
+        let (config, predicted) = resolve_primary_key(table, index_name, db_adapter, meilisearch_client).await?;
+        resolved = config;
+        (&resolved, predicted)
+    } else {
+        (table, false)
+    };
 
-    // Build an efficient lookup map for Meilisearch documents
-    for doc in &meili_docs {
-        if let Some((id_str, doc_value)) = ensure_valid_primary_key(doc, table) {
-            meili_ids.insert(id_str, doc_value);
-        } else {
-            error!("Document in Meilisearch missing valid primary key: {}", table.primary_key);
+    // Send whatever a previous sync couldn't deliver before diffing against the index
+    if let Some(queue) = options.queue.as_ref().filter(|_| !options.in_maintenance()) {
+        let drained = drain_queue(queue, meilisearch_client, index_name, batch_size)
+            .instrument(phase_span("drain"))
+            .await?;
+        if drained > 0 {
+            info!("Sent {} queued operations to index {}", drained, index_name);
         }
     }
+    
+    if table.stream_records {
+        return sync_table_streamed(table, index_name, db_adapter, meilisearch_client, options, report, predicted_key, sync_start).await;
+    }
 
-    // Process database documents
-    for doc in &db_docs {
-        if let Some((id_str, doc_value)) = ensure_valid_primary_key(doc, table) {
-            db_map.insert(id_str.clone(), doc_value.clone());
-            valid_docs += 1;
-            debug!("[sync] Will sync doc with id: {} | doc: {:?}", id_str, doc_value);
-        } else {
-            // Try to identify whether it's a missing or invalid pk
-            if doc.get(&table.primary_key).is_some() {
-                invalid_pk_count += 1;
-                if invalid_pk_count <= 5 {
-                    debug!("[sync] Skipping doc with invalid primary key: {:?}", doc.get(&table.primary_key));
-                }
-            } else {
-                missing_pk_count += 1;
-                if missing_pk_count <= 5 {
-                    debug!("[sync] Skipping doc missing primary key field: {} | doc: {:?}", table.primary_key, doc);
-                }
+    // Fetch documents from Meilisearch and database, timing each side
+    // separately. Documents keeping their row's primary key are fetched
+    // ordered by it where both sides can, to diff them without an id set.
+    let columns = table.selected_columns();
+    let order_by = (table.id_prefix.is_none() && table.primary_key_type == PrimaryKeyType::Auto)
+        .then_some(table.primary_key.as_str());
+    let since = table.updated_at_column.as_ref()
+        .and_then(|_| options.watermarks.lock().unwrap().get(&(table.name.clone(), index_name.to_string())).cloned());
+    let ((meili_docs, meili_elapsed), (db_docs, db_elapsed)) = async {
+        tokio::join!(
+            async {
+                let start = Instant::now();
+                let docs = fetch_indexed_documents(meilisearch_client, table, index_name, order_by.map(|_| table.index_primary_key())).await;
+                (docs, start.elapsed())
+            },
+            async {
+                let start = Instant::now();
+                let docs = fetch_table_rows(db_adapter, table, columns.as_deref(), order_by, since.as_ref()).await;
+                (docs, start.elapsed())
             }
+        )
+    }
+    .instrument(phase_span("fetch"))
+    .await;
+    report.timings.meilisearch_fetch = meili_elapsed;
+    report.timings.db_fetch = db_elapsed;
+    
+    let (meili_docs, meili_ordered) = match meili_docs {
+        // Meilisearch is expected to be unreachable during maintenance
+        Err(e) if options.in_maintenance() => {
+            debug!("Skipping sync of table {} in maintenance mode: {}", table.name, e);
+            report.maintenance = true;
+            report.timings.total = sync_start.elapsed();
+            return Ok(report);
         }
+        result => result?,
+    };
+    let (mut db_docs, mut db_ordered, mut changed) = match db_docs? {
+        TableRows::Full { records, ordered } => (records, ordered, None),
+        TableRows::Changed { changed, keys } => (keys, false, Some(changed)),
+    };
+    let now = SystemTime::now();
+    report.outside_retention_window = retain_window(table, &mut db_docs, now);
+    if let Some(changed) = &mut changed {
+        retain_window(table, changed, now);
     }
-
-    // Log statistics about document primary keys
-    if missing_pk_count > 0 || invalid_pk_count > 0 {
-        warn!("Table {}: {} documents with valid primary keys, {} with invalid primary keys, {} missing primary key field", 
-             table.name, valid_docs, invalid_pk_count, missing_pk_count);
+    report.decode_errors = db_adapter.take_decode_errors(&table.name);
+    if !report.decode_errors.is_empty() {
+        warn!("Some values of table {} could not be decoded and are indexed as null: {}. \
+               Check the column types, or set strict_decoding to fail the sync instead.",
+              table.name, format_decode_errors(&report.decode_errors));
     }
+    let diff_start = Instant::now();
+    info!("Found {} documents in Meilisearch and {} in database", 
+          meili_docs.len(), db_docs.len());
 
-    println!("[sync_table_impl] DB Map Keys for '{}': {:?}", table.name, db_map.keys());
-    println!("[sync_table_impl] Meili IDs Keys for '{}': {:?}", table.name, meili_ids.keys());
-
-    // Find documents to delete (in Meilisearch but not in DB)
-    let ids_to_delete: Vec<String> = meili_ids.keys()
-        .filter(|id| !db_map.contains_key(*id))
-        .cloned()
-        .collect();
-
-    if !ids_to_delete.is_empty() {
-        info!("Deleting {} documents from index {}", ids_to_delete.len(), index_name);
-        meilisearch_client.delete_documents(index_name, &ids_to_delete, Some(batch_size)).await?;
+    // Rows already indexed are compared with their documents after the lookups
+    let lookups = if table.lookups.is_empty() {
+        Vec::new()
+    } else {
+        load_lookups(table, db_adapter).await?
+    };
+    // One timestamp for the whole sync, so its documents can be told apart from older ones
+    let synced_at = table.add_sync_timestamp
+        .then(|| chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    let transform = TransformOptions { lookups: &lookups, warn_field_bytes: options.warn_field_bytes, synced_at: synced_at.as_deref() };
+    let diff_span = phase_span("diff");
+    let incremental = changed.as_ref()
+        .and_then(|changed| diff_span.in_scope(|| diff_changed_documents(table, &meili_docs, &db_docs, changed, &transform, &mut report)));
+    if changed.is_some() && incremental.is_none() {
+        info!("Table {} has rows missing from index {} that didn't change since the last sync, reading the whole table",
+              table.name, index_name);
+        (db_docs, db_ordered) = fetch_table_records(db_adapter, &table.name, columns.as_deref(), order_by).await?;
+        report.outside_retention_window = retain_window(table, &mut db_docs, now);
+        changed = None;
     }
-
-    // Find documents to add or update (in DB but not in Meilisearch or modified)
-    let mut documents_to_add = Vec::new();
-    let max_text_length = 10000000; // Truncate text fields to this length
-    let max_fields = 65536; // Limit the number of fields per document if too many
-
-    for (id_str, doc) in db_map.iter() {
-        if !meili_ids.contains_key(id_str) {
-            // Document doesn't exist in Meilisearch, add it
-            debug!("Adding new document with ID: {}", id_str);
-            
-            if let Some(obj) = doc.as_object() {
-                let process_result = process_document_obj(table, obj.clone(), id_str.clone(), max_text_length, max_fields);
-                println!("[sync_table_impl] Result from process_document_obj for ID {}: {:?}", id_str, process_result);
-                if let Ok(processed_doc) = process_result {
-                    documents_to_add.push(processed_doc);
-                    println!("[sync_table_impl] Pushed doc ID {}. documents_to_add size: {}", id_str, documents_to_add.len());
-                } else {
-                    warn!("[sync_table_impl] Failed to process document ID {}", id_str);
+    report.incremental = incremental.is_some();
+    let watermark = table.updated_at_column.as_ref()
+        .and_then(|column| next_watermark(table, column, changed.as_ref().unwrap_or(&db_docs), since.filter(|_| report.incremental)));
+    let (mut ids_to_delete, mut pending, indexed) = incremental.unwrap_or_else(|| diff_span.in_scope(|| {
+        let merged = (meili_ordered && db_ordered)
+            .then(|| diff_sorted_documents(table, &meili_docs, &db_docs, &transform, &mut report))
+            .flatten();
+        report.sorted_diff = merged.is_some();
+        match merged {
+            Some(diff) => diff,
+            None => {
+                if meili_ordered && db_ordered {
+                    debug!("Documents of table {} can't be merged by primary key, diffing them by id", table.name);
                 }
-            } else {
-                warn!("Expected document to be an object, got: {:?}", doc);
+                diff_documents(table, &meili_docs, &db_docs, &transform, &mut report)
             }
         }
+    }));
+    guard_deletions(table, index_name, db_adapter, options, &mut ids_to_delete, indexed, db_docs.len(), &mut report).await?;
+    if let Some(max) = options.max_documents_per_sync {
+        let deferred = cap_operations(&mut ids_to_delete, &mut pending, max, options.operation_order);
+        if deferred > 0 {
+            warn!("Table {} has {} changes for index {}, more than max_documents_per_sync = {}; \
+                   sending {} now and deferring {} to the next sync. Check the table and its configuration \
+                   if this is unexpected.",
+                  table.name, max + deferred, index_name, max, max, deferred);
+            report.capped = true;
+            report.documents_deferred = deferred;
+        }
     }
-
-    println!("[sync_table_impl] Checking documents_to_add before final if. Size: {}", documents_to_add.len());
-    if !documents_to_add.is_empty() {
-        println!("[sync_table_impl] Adding {} documents to index {}", documents_to_add.len(), index_name);
-        debug!("[sync] Documents to add: {:#?}", documents_to_add);
-        
-        // Process documents in batches to improve performance
-        let total_batches = (documents_to_add.len() + batch_size - 1) / batch_size;
-        let mut batch_futures = Vec::new();
-        
-        for (batch_num, chunk) in documents_to_add.chunks(batch_size).enumerate() {
-            let batch_num = batch_num + 1; // 1-indexed for logging
-            let chunk_vec = chunk.to_vec();
-            let index_name = index_name.to_string();
-            let meili_client = meilisearch_client.clone();
-            
-            // Create a future for each batch
-            let future = tokio::spawn(async move {
-                info!("Processing batch {}/{} for index {}", batch_num, total_batches, index_name);
-                match meili_client.add_or_update_documents(&index_name, chunk_vec, Some(batch_size)).await {
-                    Ok(_) => {
-                        info!("Successfully added batch {}/{} to index {}", batch_num, total_batches, index_name);
-                        Ok(())
-                    },
-                    Err(e) => {
-                        error!("Failed to add batch {}/{} to index {}: {}", batch_num, total_batches, index_name, e);
-                        Err(e)
-                    }
-                }
-            });
-            
-            batch_futures.push(future);
-            
-            // Limit concurrent batches to avoid overwhelming the Meilisearch server
-            if batch_futures.len() >= max_concurrent_batches {
-                // Wait for one batch to complete before adding more
-                if let Some(future) = batch_futures.first_mut() {
-                    println!("[sync_table_impl] Waiting for batch future to complete...");
-                    let batch_result = future.await;
-                    println!("[sync_table_impl] Batch future completed: {:?}", batch_result);
-                    // Consider adding error handling here if needed
-                }
-                batch_futures.remove(0);
+    if options.in_maintenance() {
+        info!("Maintenance mode: withholding {} documents to add and {} to delete for index {}",
+              pending.len(), ids_to_delete.len(), index_name);
+        report.maintenance = true;
+        report.documents_withheld = pending.len() + ids_to_delete.len();
+        report.timings.diff = diff_start.elapsed();
+        report.timings.total = sync_start.elapsed();
+        return Ok(report);
+    }
+    if !lookups.is_empty() {
+        for document in &mut pending {
+            if let PendingDocument::Row(_, obj) = document {
+                apply_lookups(table, &lookups, obj);
             }
         }
-        
-        // Wait for all remaining batches to complete
-        for future in batch_futures {
-            println!("[sync_table_impl] Waiting for remaining batch future...");
-            if let Err(e) = future.await {
-                error!("Error joining batch task: {:?}", e);
-            }
-            println!("[sync_table_impl] Remaining batch future completed.");
+    }
+    // Read each sync, so the schema can be changed without a restart
+    let schema = table.document_schema.as_deref().map(load_document_schema).transpose()?.map(Arc::new);
+    let (documents, field_stats) = transform_pending(table, pending, options.warn_field_bytes, synced_at, schema)
+        .instrument(diff_span)
+        .await?;
+    // The field statistics of the documents transformed by the change check
+    // are already in the report
+    report.duplicate_documents = field_stats.duplicate_documents;
+    merge_transform_stats(&mut report, field_stats);
+    if report.documents_skipped > 0 {
+        if options.on_transform_error == TransformErrorPolicy::Fail {
+            return Err(ConnectorError::Config(format!(
+                "{} documents of table {} could not be transformed and on_transform_error is \"fail\"; \
+                 nothing was sent, see the warnings above for each document",
+                report.documents_skipped, table.name
+            )));
         }
-    } else {
-        println!("[sync_table_impl] No new documents to add to index {}", index_name);
+        warn!("Skipped {} documents of table {} that could not be transformed",
+              report.documents_skipped, table.name);
     }
 
+    report.timings.diff = diff_start.elapsed();
+
+    if let Some(queue) = &options.queue {
+        // Persist the operations first, then send them in order
+        report.documents_added = documents.len();
+        let mut operations: Vec<QueuedOperation> = documents.chunks(batch_size)
+            .map(|chunk| QueuedOperation::Add { documents: chunk.to_vec() })
+            .collect();
+        if !ids_to_delete.is_empty() {
+            let delete = QueuedOperation::Delete { ids: ids_to_delete.clone() };
+            match options.operation_order {
+                OperationOrder::DeleteThenAdd => operations.insert(0, delete),
+                OperationOrder::AddThenDelete => operations.push(delete),
+            }
+        }
+        report.documents_deleted = ids_to_delete.len();
+        queue.push(index_name, &operations).await?;
+
+        let add_start = Instant::now();
+        drain_queue(queue, meilisearch_client, index_name, batch_size)
+            .instrument(phase_span("add"))
+            .await?;
+        report.timings.add = add_start.elapsed();
+        if predicted_key && report.documents_added > 0 {
+            check_inferred_primary_key(table, index_name, meilisearch_client).await?;
+        }
+        store_watermark(options, table, index_name, watermark, &report);
+        report.timings.total = sync_start.elapsed();
+        return Ok(report);
+    }
+
+    if options.operation_order == OperationOrder::DeleteThenAdd {
+        delete_stale_documents(meilisearch_client, index_name, &ids_to_delete, batch_size, &mut report).await?;
+    }
+
+    let add_start = Instant::now();
+    if documents.is_empty() {
+        debug!("No new documents to add to index {}", index_name);
+    } else {
+        debug!("Adding {} documents to index {}", documents.len(), index_name);
+        report.documents_added = documents.len();
+        let dead_letters = options.dead_letters.as_ref().map(|file| (file, table.name.as_str()));
+        let (failed, rejected) = add_document_batches(
+            meilisearch_client, index_name, documents, batch_size, max_concurrent_batches, dead_letters,
+        )
+            .instrument(phase_span("add"))
+            .await;
+        report.batches_failed = failed;
+        report.documents_rejected = rejected;
+    }
+    report.timings.add = add_start.elapsed();
+
+    if options.operation_order == OperationOrder::AddThenDelete {
+        delete_stale_documents(meilisearch_client, index_name, &ids_to_delete, batch_size, &mut report).await?;
+    }
+    if predicted_key && report.documents_added > 0 {
+        check_inferred_primary_key(table, index_name, meilisearch_client).await?;
+    }
+    store_watermark(options, table, index_name, watermark, &report);
+
+    report.timings.total = sync_start.elapsed();
+    Ok(report)
+}
+
+/// `sync_table_impl` for tables with `stream_records`: the rows are read as
+/// a stream, and each chunk of `batch_size * max_concurrent_batches` rows is
+/// diffed, transformed and sent before the next one is read. Only the ids
+/// of the rows are kept for the whole sync, to find the deletions, which are
+/// sent last.
+#[allow(clippy::too_many_arguments)]
+async fn sync_table_streamed(
+    table: &TableConfig,
+    index_name: &str,
+    db_adapter: &Arc<Box<dyn DatabaseAdapter>>,
+    meilisearch_client: &Arc<dyn MeilisearchClientTrait>,
+    options: &SyncOptions,
+    mut report: SyncReport,
+    predicted_key: bool,
+    sync_start: Instant,
+) -> Result<SyncReport> {
+    let meili_start = Instant::now();
+    let meili_docs = fetch_indexed_documents(meilisearch_client, table, index_name, None).await;
+    // Rows are sent as they are read, so there is nothing to read in maintenance mode
+    if options.in_maintenance() {
+        match meili_docs {
+            Err(e) => debug!("Skipping sync of table {} in maintenance mode: {}", table.name, e),
+            Ok(_) => info!("Maintenance mode: skipping the streamed sync of table {}", table.name),
+        }
+        report.maintenance = true;
+        report.timings.total = sync_start.elapsed();
+        return Ok(report);
+    }
+    let (meili_docs, _) = meili_docs?;
+    report.timings.meilisearch_fetch = meili_start.elapsed();
+    report.streamed = true;
+    let indexed: HashMap<String, Value> = meili_docs.into_iter()
+        .filter_map(|doc| indexed_document_id(table, &doc).map(|id| (id, doc)))
+        .collect();
+    let lookups = if table.lookups.is_empty() {
+        Vec::new()
+    } else {
+        load_lookups(table, db_adapter).await?
+    };
+    let synced_at = table.add_sync_timestamp
+        .then(|| chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    let schema = table.document_schema.as_deref().map(load_document_schema).transpose()?.map(Arc::new);
+    let dead_letters = options.dead_letters.as_ref().map(|file| (file, table.name.as_str()));
+    let transform = TransformOptions { lookups: &lookups, warn_field_bytes: options.warn_field_bytes, synced_at: synced_at.as_deref() };
+
+    let columns = table.selected_columns();
+    let now = SystemTime::now();
+    let mut stats = KeyStats::default();
+    let mut seen = HashSet::new();
+    let mut rows = db_adapter.fetch_records_stream(&table.name, columns.as_deref())
+        .try_chunks(options.batch_size * options.max_concurrent_batches);
+    loop {
+        let fetch_start = Instant::now();
+        let Some(mut chunk) = rows.try_next().await.map_err(|e| e.1)? else { break };
+        report.timings.db_fetch += fetch_start.elapsed();
+
+        let diff_start = Instant::now();
+        report.outside_retention_window += retain_window(table, &mut chunk, now);
+        let mut pending = Vec::new();
+        for row in chunk {
+            let Some((id_str, doc)) = check_database_document(table, &row, &mut report, &mut stats) else { continue };
+            seen.insert(id_str.clone());
+            match indexed.get(&id_str) {
+                Some(indexed_doc) => {
+                    let Some(document) = changed_document(table, &transform, id_str, doc, indexed_doc, &mut report) else {
+                        continue;
+                    };
+                    report.documents_changed += 1;
+                    pending.push(document);
+                }
+                None => pending.extend(pending_document((id_str, doc))),
+            }
+        }
+        if !lookups.is_empty() {
+            for document in &mut pending {
+                if let PendingDocument::Row(_, obj) = document {
+                    apply_lookups(table, &lookups, obj);
+                }
+            }
+        }
+        let (documents, field_stats) =
+            transform_pending(table, pending, options.warn_field_bytes, synced_at.clone(), schema.clone()).await?;
+        report.duplicate_documents += field_stats.duplicate_documents;
+        let skipped = field_stats.documents_skipped;
+        merge_transform_stats(&mut report, field_stats);
+        report.timings.diff += diff_start.elapsed();
+        if skipped > 0 && options.on_transform_error == TransformErrorPolicy::Fail {
+            return Err(ConnectorError::Config(format!(
+                "{} documents of table {} could not be transformed and on_transform_error is \"fail\"; \
+                 the chunks before them were sent, see the warnings above for each document",
+                skipped, table.name
+            )));
+        }
+
+        let add_start = Instant::now();
+        if !documents.is_empty() {
+            report.documents_added += documents.len();
+            let (failed, rejected) = add_document_batches(
+                meilisearch_client, index_name, documents, options.batch_size, options.max_concurrent_batches, dead_letters,
+            )
+                .instrument(phase_span("add"))
+                .await;
+            report.batches_failed += failed;
+            report.documents_rejected += rejected;
+        }
+        report.timings.add += add_start.elapsed();
+    }
+    drop(rows);
+    log_key_stats(table, &mut report, &stats);
+    if report.documents_skipped > 0 {
+        warn!("Skipped {} documents of table {} that could not be transformed", report.documents_skipped, table.name);
+    }
+    report.decode_errors = db_adapter.take_decode_errors(&table.name);
+    if !report.decode_errors.is_empty() {
+        warn!("Some values of table {} could not be decoded and are indexed as null: {}. \
+               Check the column types, or set strict_decoding to fail the sync instead.",
+              table.name, format_decode_errors(&report.decode_errors));
+    }
+
+    let mut ids_to_delete: Vec<String> = indexed.keys().filter(|id| !seen.contains(*id)).cloned().collect();
+    guard_deletions(table, index_name, db_adapter, options, &mut ids_to_delete, indexed.len(), seen.len(), &mut report).await?;
+    delete_stale_documents(meilisearch_client, index_name, &ids_to_delete, options.batch_size, &mut report).await?;
+    if predicted_key && report.documents_added > 0 {
+        check_inferred_primary_key(table, index_name, meilisearch_client).await?;
+    }
+    report.timings.total = sync_start.elapsed();
+    Ok(report)
+}
+
+/// Hold back the deletions of a sync that may not be real: all of them
+/// while the replica the rows were read from lags, those whose rows the
+/// primary still has, and all of them when they exceed `max_delete_ratio`
+/// of the `indexed` documents
+#[allow(clippy::too_many_arguments)]
+async fn guard_deletions(
+    table: &TableConfig,
+    index_name: &str,
+    db_adapter: &Arc<Box<dyn DatabaseAdapter>>,
+    options: &SyncOptions,
+    ids_to_delete: &mut Vec<String>,
+    indexed: usize,
+    database_rows: usize,
+    report: &mut SyncReport,
+) -> Result<()> {
+    if let Some(max_lag) = options.max_replica_lag.filter(|_| !ids_to_delete.is_empty()) {
+        if replica_lagging(db_adapter, max_lag).await {
+            info!("Postponing the deletion of {} documents of table {} until the replica catches up",
+                  ids_to_delete.len(), table.name);
+            report.deletions_postponed = ids_to_delete.len();
+            ids_to_delete.clear();
+        }
+    }
+    if let Some(primary) = options.primary_adapter.as_ref().filter(|_| !ids_to_delete.is_empty()) {
+        report.deletions_unconfirmed = retain_deleted_on_primary(table, primary, ids_to_delete, SystemTime::now()).await?;
+        if report.deletions_unconfirmed > 0 {
+            info!("Keeping {} documents of table {} whose rows are missing from the replica but still on the primary",
+                  report.deletions_unconfirmed, table.name);
+        }
+    }
+    if let Some(ratio) = options.max_delete_ratio.filter(|_| !options.allow_mass_delete) {
+        if indexed > 0 && ids_to_delete.len() as f64 > ratio * indexed as f64 {
+            error!("REFUSING to delete {} of the {} documents of table {} in index {} ({} rows in the database), \
+                    more than max_delete_ratio = {}. If the database really lost these rows, rerun with \
+                    --allow-mass-delete; otherwise check the connection string and the table.",
+                   ids_to_delete.len(), indexed, table.name, index_name, database_rows, ratio);
+            report.deletions_blocked = ids_to_delete.len();
+            ids_to_delete.clear();
+        }
+    }
     Ok(())
 }
+
+/// After the first documents of an index with an inferred primary key were
+/// sent, check that Meilisearch inferred the predicted key. Failed additions
+/// are only logged, so this is where a failed inference surfaces.
+async fn check_inferred_primary_key(
+    table: &TableConfig,
+    index_name: &str,
+    meilisearch_client: &Arc<dyn MeilisearchClientTrait>,
+) -> Result<()> {
+    match meilisearch_client.index_primary_key(index_name).await? {
+        Some(key) if key == table.index_primary_key() => {
+            info!("Meilisearch inferred primary key {} for index {}", key, index_name);
+            Ok(())
+        }
+        inferred => Err(ConnectorError::Config(format!(
+            "Meilisearch did not infer primary key '{}' for index '{}' of table '{}' (index primary key: {}); \
+             set primary_key on the table",
+            table.index_primary_key(), index_name, table.name, inferred.as_deref().unwrap_or("none")
+        ))),
+    }
+}
+
+/// For a table with `infer_primary_key`, a copy of its configuration with
+/// the primary key Meilisearch uses for its index. Before the index has one,
+/// predict the key Meilisearch will infer from the documents, refusing to
+/// guess when it would fail. Also returns whether the key was predicted.
+async fn resolve_primary_key(
+    table: &TableConfig,
+    index_name: &str,
+    db_adapter: &Arc<Box<dyn DatabaseAdapter>>,
+    meilisearch_client: &Arc<dyn MeilisearchClientTrait>,
+) -> Result<(TableConfig, bool)> {
+    let columns: Vec<String> = db_adapter.get_table_columns(&table.name).await?
+        .into_iter()
+        .map(|(name, _, _)| name)
+        .collect();
+    let (primary_key, predicted) = match meilisearch_client.index_primary_key(index_name).await? {
+        Some(key) if columns.contains(&key) => (key, false),
+        Some(key) => {
+            return Err(ConnectorError::Config(format!(
+                "Index '{}' has primary key '{}', which is not a column of table '{}'; set primary_key on the table",
+                index_name, key, table.name
+            )));
+        }
+        None => {
+            let candidates = table.primary_key_candidates(columns);
+            match candidates.as_slice() {
+                [key] => (key.clone(), true),
+                [] => {
+                    return Err(ConnectorError::Config(format!(
+                        "Meilisearch can't infer a primary key for table '{}': none of its fields ends with 'id'. \
+                         Set primary_key on the table",
+                        table.name
+                    )));
+                }
+                _ => {
+                    return Err(ConnectorError::Config(format!(
+                        "Meilisearch can't infer a primary key for table '{}': {} could all be one. \
+                         Set primary_key on the table",
+                        table.name, candidates.join(", ")
+                    )));
+                }
+            }
+        }
+    };
+    debug!("Using primary key {} for table {} (predicted: {})", primary_key, table.name, predicted);
+    Ok((TableConfig { primary_key, infer_primary_key: false, ..table.clone() }, predicted))
+}
+
+/// Drop the records outside the table's retention window, i.e. whose
+/// `retention_column` is older than `retention_window_seconds` or not a
+/// date/time. Their documents are then deleted like those of removed rows.
+/// Adapters already leave out most of them in the query; this applies the
+/// window's exact start. Returns how many records were dropped.
+fn retain_window(table: &TableConfig, records: &mut Vec<Value>, now: SystemTime) -> usize {
+    let (Some(column), Some(window)) = (&table.retention_column, table.retention_window_seconds) else {
+        return 0;
+    };
+    let now = now.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+    let cutoff = now.saturating_sub(window as i64);
+    let before = records.len();
+    records.retain(|record| record.get(column).and_then(parse_timestamp).is_some_and(|ts| ts > cutoff));
+    let dropped = before - records.len();
+    if dropped > 0 {
+        debug!("{} rows of table {} are outside its {}s retention window", dropped, table.name, window);
+    }
+    dropped
+}
+
+/// Whether the database rows are read from lags its primary by more than
+/// `max_lag`, so rows missing from it may just not have arrived yet. A lag
+/// that can't be read counts as too much.
+async fn replica_lagging(db_adapter: &Arc<Box<dyn DatabaseAdapter>>, max_lag: Duration) -> bool {
+    match db_adapter.replication_lag().await {
+        Ok(Some(lag)) if lag > max_lag => {
+            warn!("The database is {}s behind its primary, more than max_replica_lag_seconds = {}",
+                  lag.as_secs(), max_lag.as_secs());
+            true
+        }
+        Ok(_) => false,
+        Err(e) => {
+            warn!("Could not read the replication lag of the database: {}", e);
+            true
+        }
+    }
+}
+
+/// Drop the ids of `ids_to_delete` whose rows the primary still has, rows
+/// the replica the sync read from hasn't caught up with. Rows outside the
+/// retention window count as deleted, as they do on the replica. Returns how
+/// many ids were dropped.
+async fn retain_deleted_on_primary(
+    table: &TableConfig,
+    primary: &Arc<Box<dyn DatabaseAdapter>>,
+    ids_to_delete: &mut Vec<String>,
+    now: SystemTime,
+) -> Result<usize> {
+    let columns: Vec<String> = std::iter::once(&table.primary_key).chain(&table.retention_column).cloned().collect();
+    let mut rows = primary.fetch_records(&table.name, Some(&columns)).await?;
+    retain_window(table, &mut rows, now);
+    let on_primary: HashSet<String> = rows.iter()
+        .filter_map(|row| ensure_valid_primary_key(row, table).ok())
+        .map(|(id_str, _)| table.document_id(&id_str))
+        .collect();
+    let before = ids_to_delete.len();
+    ids_to_delete.retain(|id| !on_primary.contains(id));
+    Ok(before - ids_to_delete.len())
+}
+
+/// Trim the deletions and additions of a sync to at most `max` in total,
+/// keeping the ones sent first under `order`. Both sets are sorted by ID so
+/// the same changes go out first on every sync. Returns how many were dropped;
+/// the next sync's diff finds them again.
+fn cap_operations(
+    ids_to_delete: &mut Vec<String>,
+    pending: &mut Vec<PendingDocument>,
+    max: usize,
+    order: OperationOrder,
+) -> usize {
+    let total = ids_to_delete.len() + pending.len();
+    if total <= max {
+        return 0;
+    }
+    ids_to_delete.sort_unstable();
+    pending.sort_unstable_by(|a, b| a.id().cmp(b.id()));
+    match order {
+        OperationOrder::DeleteThenAdd => {
+            ids_to_delete.truncate(max);
+            pending.truncate(max - ids_to_delete.len());
+        }
+        OperationOrder::AddThenDelete => {
+            pending.truncate(max);
+            ids_to_delete.truncate(max - pending.len());
+        }
+    }
+    total - max
+}
+
+async fn delete_stale_documents(
+    meilisearch_client: &Arc<dyn MeilisearchClientTrait>,
+    index_name: &str,
+    ids_to_delete: &[String],
+    batch_size: usize,
+    report: &mut SyncReport,
+) -> Result<()> {
+    if ids_to_delete.is_empty() {
+        return Ok(());
+    }
+    info!("Deleting {} documents from index {}", ids_to_delete.len(), index_name);
+    let delete_start = Instant::now();
+    meilisearch_client.delete_documents(index_name, ids_to_delete, Some(batch_size))
+        .instrument(phase_span("delete"))
+        .await?;
+    report.timings.delete = delete_start.elapsed();
+    report.documents_deleted = ids_to_delete.len();
+    Ok(())
+}
+
+/// Every document of an index, ordered by `order_by` if the client can
+/// fetch them that way, and whether they are ordered. The documents of a
+/// table with a `vector_field` are fetched with their vectors, unordered.
+async fn fetch_indexed_documents(
+    meilisearch_client: &Arc<dyn MeilisearchClientTrait>,
+    table: &TableConfig,
+    index_name: &str,
+    order_by: Option<&str>,
+) -> Result<(Vec<Value>, bool)> {
+    if compared_embedder(table).is_some() {
+        return Ok((meilisearch_client.get_all_documents_with_vectors(index_name).await?, false));
+    }
+    if let Some(primary_key) = order_by {
+        if let Some(docs) = meilisearch_client.get_all_documents_ordered(index_name, primary_key).await? {
+            return Ok((docs, true));
+        }
+    }
+    Ok((meilisearch_client.get_all_documents(index_name).await?, false))
+}
+
+/// The records of a table, ordered by `order_by` if the adapter can fetch
+/// them that way, and whether they are ordered
+async fn fetch_table_records(
+    db_adapter: &Arc<Box<dyn DatabaseAdapter>>,
+    table: &str,
+    columns: Option<&[String]>,
+    order_by: Option<&str>,
+) -> Result<(Vec<Value>, bool)> {
+    if let Some(primary_key) = order_by {
+        if let Some(records) = db_adapter.fetch_records_ordered(table, columns, primary_key).await? {
+            return Ok((records, true));
+        }
+    }
+    Ok((db_adapter.fetch_records(table, columns).await?, false))
+}
+
+/// Rows read for a sync: the whole table, or with `updated_at_column` the
+/// rows changed since the previous sync and the keys of every row
+enum TableRows {
+    Full { records: Vec<Value>, ordered: bool },
+    Changed { changed: Vec<Value>, keys: Vec<Value> },
+}
+
+/// The rows of a table changed since `since` if the table has an
+/// `updated_at_column` and the adapter can filter on it, otherwise all of
+/// them as `fetch_table_records` reads them
+async fn fetch_table_rows(
+    db_adapter: &Arc<Box<dyn DatabaseAdapter>>,
+    table: &TableConfig,
+    columns: Option<&[String]>,
+    order_by: Option<&str>,
+    since: Option<&Value>,
+) -> Result<TableRows> {
+    if let (Some(column), Some(since)) = (&table.updated_at_column, since) {
+        if let Some(changed) = db_adapter.fetch_records_since(&table.name, columns, column, since).await? {
+            // Deletions are still found from every row, by key
+            let key_columns: Vec<String> = std::iter::once(&table.primary_key)
+                .chain(&table.retention_column)
+                .cloned()
+                .collect();
+            let keys = db_adapter.fetch_records(&table.name, Some(&key_columns)).await?;
+            return Ok(TableRows::Changed { changed, keys });
+        }
+    }
+    let (records, ordered) = fetch_table_records(db_adapter, &table.name, columns, order_by).await?;
+    Ok(TableRows::Full { records, ordered })
+}
+
+/// The highest `column` value of `rows`, or `since` if it is higher
+fn next_watermark(table: &TableConfig, column: &str, rows: &[Value], since: Option<Value>) -> Option<Value> {
+    let highest = rows.iter()
+        .map(|row| &row[column])
+        .filter(|value| value.is_number() || value.is_string())
+        .max_by(|a, b| compare_primary_keys(a, b));
+    if highest.is_none() && since.is_none() && !rows.is_empty() {
+        warn!("Table {} has no values in updated_at_column {}, every sync reads the whole table", table.name, column);
+    }
+    highest.cloned().into_iter().chain(since).max_by(compare_primary_keys)
+}
+
+/// Let the next sync of the table and index resume from `watermark` if this
+/// one sent everything it found, otherwise have it read the whole table
+fn store_watermark(options: &SyncOptions, table: &TableConfig, index_name: &str, watermark: Option<Value>, report: &SyncReport) {
+    let key = (table.name.clone(), index_name.to_string());
+    let mut watermarks = options.watermarks.lock().unwrap();
+    match watermark {
+        Some(watermark) if !report.capped && report.batches_failed == 0 => {
+            watermarks.insert(key, watermark);
+        }
+        _ => {
+            watermarks.remove(&key);
+        }
+    }
+}
+
+/// A document still to be indexed, keyed by its primary key string: the row
+/// to transform, or the result of the transform the change check already ran
+enum PendingDocument {
+    Row(String, serde_json::Map<String, Value>),
+    Transformed(String, Result<Value>),
+}
+
+impl PendingDocument {
+    fn id(&self) -> &str {
+        match self {
+            Self::Row(id_str, _) | Self::Transformed(id_str, _) => id_str,
+        }
+    }
+}
+
+/// What the documents of a sync are transformed with besides their table
+struct TransformOptions<'a> {
+    lookups: &'a [LookupIndex],
+    warn_field_bytes: Option<usize>,
+    synced_at: Option<&'a str>,
+}
+
+/// Compare the database rows with the indexed documents, returning the IDs to
+/// delete from the index, the rows that still have to be added or changed
+/// since they were indexed, and the number of indexed documents belonging to
+/// the table
+fn diff_documents(
+    table: &TableConfig,
+    meili_docs: &[Value],
+    db_docs: &[Value],
+    transform: &TransformOptions,
+    report: &mut SyncReport,
+) -> (Vec<String>, Vec<PendingDocument>, usize) {
+    // Build an efficient lookup map for Meilisearch documents
+    let meili_ids: HashMap<String, &Value> = meili_docs.iter()
+        .filter_map(|doc| indexed_document_id(table, doc).map(|id| (id, doc)))
+        .collect();
+
+    // Process database documents
+    let mut stats = KeyStats::default();
+    let mut db_map = HashMap::new();
+    for doc in db_docs {
+        if let Some((id_str, doc_value)) = check_database_document(table, doc, report, &mut stats) {
+            db_map.insert(id_str, doc_value);
+        }
+    }
+    log_key_stats(table, report, &stats);
+
+    trace!("Table {}: database ids {:?}, index ids {:?}", table.name, db_map.keys(), meili_ids);
+
+    // Find documents to delete (in Meilisearch but not in DB). An ID that is in
+    // the database is never deleted, so a reused primary key can't lose its
+    // document whichever order the operations are sent in.
+    let ids_to_delete: Vec<String> = meili_ids.keys()
+        .filter(|id| !db_map.contains_key(*id))
+        .cloned()
+        .collect();
+
+    // Find documents to add or update (in DB but not in Meilisearch or modified)
+    let pending: Vec<PendingDocument> = db_map.into_iter()
+        .filter_map(|(id_str, doc)| match meili_ids.get(&id_str) {
+            Some(indexed) => {
+                let document = changed_document(table, transform, id_str, doc, indexed, report);
+                report.documents_changed += document.is_some() as usize;
+                document
+            }
+            None => pending_document((id_str, doc)),
+        })
+        .collect();
+
+    (ids_to_delete, pending, meili_ids.len())
+}
+
+/// `diff_documents` for an incremental sync: the IDs to delete are found
+/// from the keys of every row, and the changed rows are pending unless their
+/// document is indexed as it is, like the rows at the watermark, which every
+/// sync reads again. `None` if rows that didn't change are missing from the
+/// index, e.g. after a failed sync, since only a full diff finds them.
+fn diff_changed_documents(
+    table: &TableConfig,
+    meili_docs: &[Value],
+    keys: &[Value],
+    changed: &[Value],
+    transform: &TransformOptions,
+    report: &mut SyncReport,
+) -> Option<(Vec<String>, Vec<PendingDocument>, usize)> {
+    let meili_ids: HashMap<String, &Value> = meili_docs.iter()
+        .filter_map(|doc| indexed_document_id(table, doc).map(|id| (id, doc)))
+        .collect();
+    let pending: Vec<(String, Value)> = changed.iter()
+        .filter_map(|doc| ensure_valid_primary_key(doc, table).ok())
+        .map(|(id_str, doc)| (table.document_id(&id_str), doc))
+        .collect();
+    let changed_ids: HashSet<&String> = pending.iter().map(|(id_str, _)| id_str).collect();
+    let unindexed = keys.iter()
+        .filter_map(|doc| ensure_valid_primary_key(doc, table).ok())
+        .any(|(id_str, _)| {
+            let id_str = table.document_id(&id_str);
+            !meili_ids.contains_key(&id_str) && !changed_ids.contains(&id_str)
+        });
+    if unindexed {
+        return None;
+    }
+
+    let mut stats = KeyStats::default();
+    let db_ids: HashSet<String> = keys.iter()
+        .filter_map(|doc| check_database_document(table, doc, report, &mut stats))
+        .map(|(id_str, _)| id_str)
+        .collect();
+    log_key_stats(table, report, &stats);
+
+    let ids_to_delete = meili_ids.keys()
+        .filter(|id| !db_ids.contains(*id))
+        .cloned()
+        .collect();
+    let pending = pending.into_iter()
+        .filter_map(|(id_str, doc)| match meili_ids.get(&id_str) {
+            Some(indexed) => {
+                let document = changed_document(table, transform, id_str, doc, indexed, report);
+                report.documents_changed += document.is_some() as usize;
+                document
+            }
+            None => pending_document((id_str, doc)),
+        })
+        .collect();
+    Some((ids_to_delete, pending, meili_ids.len()))
+}
+
+/// `diff_documents` for indexed documents and database rows that are both
+/// ordered by primary key: a merge join over the two sequences. Both are in
+/// memory already, so it only saves the map of every indexed id and row.
+/// `None` if they can't be merged because their keys aren't all integers or
+/// all strings, or aren't in `compare_primary_keys` order, e.g. because the
+/// database collates strings differently.
+fn diff_sorted_documents(
+    table: &TableConfig,
+    meili_docs: &[Value],
+    db_docs: &[Value],
+    transform: &TransformOptions,
+    report: &mut SyncReport,
+) -> Option<(Vec<String>, Vec<PendingDocument>, usize)> {
+    // The maps match keys by their text, the merge by their value, so both
+    // agree only when every key is of the same kind
+    let primary_key = table.primary_key.as_str();
+    let index_key = table.index_primary_key();
+    let first_key = meili_docs.iter().filter_map(|doc| doc.get(index_key))
+        .chain(db_docs.iter().filter_map(|doc| doc.get(primary_key)))
+        .find(|id| !id.is_null());
+    let kind = first_key.map_or(Some(KeyKind::Integer), KeyKind::of)?;
+    if !keys_ordered(meili_docs, index_key, kind) || !keys_ordered(db_docs, primary_key, kind) {
+        return None;
+    }
+
+    let mut stats = KeyStats::default();
+    let mut ids_to_delete = Vec::new();
+    let mut pending = Vec::new();
+    let mut indexed_count = 0;
+    let mut changed = 0;
+    // The rows borrow the report for their key statistics
+    let mut field_stats = SyncReport::default();
+    {
+        let mut indexed = meili_docs.iter()
+            .filter_map(|doc| indexed_document_id(table, doc).map(|id| (id, doc)))
+            .peekable();
+        let mut rows = db_docs.iter()
+            .filter_map(|doc| check_database_document(table, doc, report, &mut stats))
+            .peekable();
+        loop {
+            let order = match (indexed.peek(), rows.peek()) {
+                (None, None) => break,
+                (Some(_), None) => cmp::Ordering::Less,
+                (None, Some(_)) => cmp::Ordering::Greater,
+                (Some((_, doc)), Some((_, row))) => compare_primary_keys(&doc[index_key], &row[primary_key]),
+            };
+            match order {
+                // Indexed but no longer in the database
+                cmp::Ordering::Less => {
+                    ids_to_delete.extend(indexed.next().map(|(id, _)| id));
+                    indexed_count += 1;
+                }
+                // Indexed, and re-sent if the row changed since
+                cmp::Ordering::Equal => {
+                    let indexed_doc = indexed.next().map(|(_, doc)| doc);
+                    indexed_count += 1;
+                    let document = next_distinct_row(&mut rows, primary_key).zip(indexed_doc)
+                        .and_then(|((id_str, row), doc)| changed_document(table, transform, id_str, row, doc, &mut field_stats));
+                    changed += document.is_some() as usize;
+                    pending.extend(document);
+                }
+                // In the database but not indexed yet
+                cmp::Ordering::Greater => {
+                    pending.extend(next_distinct_row(&mut rows, primary_key).and_then(pending_document));
+                }
+            }
+        }
+    }
+    report.documents_changed = changed;
+    merge_transform_stats(report, field_stats);
+    log_key_stats(table, report, &stats);
+    Some((ids_to_delete, pending, indexed_count))
+}
+
+/// The kinds of primary key values `diff_sorted_documents` can merge on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyKind {
+    Integer,
+    String,
+}
+
+impl KeyKind {
+    fn of(id: &Value) -> Option<Self> {
+        match id {
+            Value::Number(n) if n.is_i64() || n.is_u64() => Some(Self::Integer),
+            Value::String(_) => Some(Self::String),
+            _ => None,
+        }
+    }
+}
+
+// Whether the primary keys of `docs`, held in `key`, are all of `kind` and
+// ordered. Rows without a key are left out of the diff anyway.
+fn keys_ordered(docs: &[Value], key: &str, kind: KeyKind) -> bool {
+    let keys = docs.iter()
+        .filter_map(|doc| doc.get(key))
+        .filter(|id| !id.is_null());
+    keys.clone().all(|id| KeyKind::of(id) == Some(kind))
+        && keys.is_sorted_by(|a, b| compare_primary_keys(a, b).is_le())
+}
+
+// The next row and the ones after it with the same key, of which the last
+// wins like in `diff_documents`'s map
+fn next_distinct_row(
+    rows: &mut std::iter::Peekable<impl Iterator<Item = (String, Value)>>,
+    primary_key: &str,
+) -> Option<(String, Value)> {
+    let mut row = rows.next()?;
+    while let Some(next) = rows.next_if(|next| next.1[primary_key] == row.1[primary_key]) {
+        row = next;
+    }
+    Some(row)
+}
+
+/// Primary key kinds of the rows of a table, for `log_key_stats`
+#[derive(Debug, Default)]
+struct KeyStats {
+    valid_docs: usize,
+    string_keys: usize,
+    number_keys: usize,
+}
+
+/// ID of an indexed document of the table, `None` for documents with an
+/// invalid primary key or belonging to another table sharing the index
+fn indexed_document_id(table: &TableConfig, doc: &Value) -> Option<String> {
+    let Ok((id_str, _)) = ensure_valid_key(doc, table, table.index_primary_key()) else {
+        error!("Document in Meilisearch missing valid primary key: {}", table.index_primary_key());
+        return None;
+    };
+    // Documents without this table's prefix belong to another table sharing the index
+    if table.id_prefix.as_ref().is_some_and(|prefix| !id_str.starts_with(prefix.as_str())) {
+        return None;
+    }
+    Some(id_str)
+}
+
+/// Document ID and row, with its primary key converted, of a database row
+/// with a valid primary key. Rows without one are counted in `report`.
+fn check_database_document(
+    table: &TableConfig,
+    doc: &Value,
+    report: &mut SyncReport,
+    stats: &mut KeyStats,
+) -> Option<(String, Value)> {
+    match ensure_valid_primary_key(doc, table) {
+        Ok((id_str, doc_value)) => {
+            match &doc_value[&table.primary_key] {
+                Value::String(_) => stats.string_keys += 1,
+                _ => stats.number_keys += 1,
+            }
+            if doc_value[&table.primary_key] != doc[&table.primary_key] {
+                report.primary_keys_coerced += 1;
+            }
+            let id_str = table.document_id(&id_str);
+            stats.valid_docs += 1;
+            trace!("Syncing document {}", id_str);
+            Some((id_str, doc_value))
+        }
+        Err(reason) => {
+            let count = match reason {
+                InvalidPrimaryKey::Missing => &mut report.primary_keys_missing,
+                InvalidPrimaryKey::Null => &mut report.primary_keys_null,
+                InvalidPrimaryKey::Empty => &mut report.primary_keys_empty,
+                InvalidPrimaryKey::Invalid => &mut report.primary_keys_invalid,
+            };
+            *count += 1;
+            if *count <= 5 {
+                debug!("Skipping a row of table {} with a {:?} primary key", table.name, reason);
+            }
+            None
+        }
+    }
+}
+
+/// Log statistics about the primary keys of a table's rows
+fn log_key_stats(table: &TableConfig, report: &mut SyncReport, stats: &KeyStats) {
+    if report.primary_keys_skipped() > 0 {
+        warn!("Table {}: {} documents with valid primary keys, skipped {} with a missing, {} with a null, \
+               {} with an empty and {} with an invalid primary key",
+              table.name, stats.valid_docs, report.primary_keys_missing, report.primary_keys_null,
+              report.primary_keys_empty, report.primary_keys_invalid);
+    }
+    report.database_documents = stats.valid_docs;
+    if report.primary_keys_coerced > 0 {
+        info!("Table {}: converted {} primary key values to {:?}",
+              table.name, report.primary_keys_coerced, table.primary_key_type);
+    }
+    if stats.string_keys > 0 && stats.number_keys > 0 && table.id_prefix.is_none() {
+        warn!("Table {} mixes string ({}) and numeric ({}) primary key values; set primary_key_type \
+               to \"string\" or \"integer\" to index them consistently",
+              table.name, stats.string_keys, stats.number_keys);
+    }
+}
+
+// A row to add to the index, `None` if it isn't an object
+fn pending_document((id_str, doc): (String, Value)) -> Option<PendingDocument> {
+    match doc {
+        Value::Object(obj) => {
+            debug!("Adding new document with ID: {}", id_str);
+            Some(PendingDocument::Row(id_str, obj))
+        }
+        other => {
+            warn!("Expected document to be an object, got: {:?}", other);
+            None
+        }
+    }
+}
+
+/// The pending document of an indexed row, `None` if the document the row
+/// transforms into has the same contents as its indexed version, comparing a
+/// hash of them. Fields Meilisearch doesn't return or that change on every
+/// sync are left out. The transformed document is kept so it isn't
+/// transformed again, with its field statistics added to `report`; rows
+/// failing the transform count as changed, so the sync reports them.
+fn changed_document(
+    table: &TableConfig,
+    transform: &TransformOptions,
+    id_str: String,
+    row: Value,
+    indexed: &Value,
+    report: &mut SyncReport,
+) -> Option<PendingDocument> {
+    let Value::Object(mut obj) = row else {
+        return pending_document((id_str, row));
+    };
+    apply_lookups(table, transform.lookups, &mut obj);
+    let mut stats = SyncReport::default();
+    let document = process_document_obj(
+        table, obj, id_str.clone(), MAX_TEXT_LENGTH, MAX_FIELDS, transform.warn_field_bytes, transform.synced_at, &mut stats,
+    );
+    // Indexed documents fetched without their vectors can't tell a changed embedding
+    let embedder = compared_embedder(table).filter(|_| indexed.get("_vectors").is_some());
+    if document.as_ref().is_ok_and(|document| content_hash(document, embedder) == content_hash(indexed, embedder)) {
+        return None;
+    }
+    merge_transform_stats(report, stats);
+    Some(PendingDocument::Transformed(id_str, document))
+}
+
+/// Hash of a document's fields other than `_vectors`, the sync time and the
+/// content hash, which `compare_documents` leaves out for the same reasons,
+/// and the source table, the same for every row of the table. The vector of
+/// `embedder` is hashed too, as Meilisearch stores it.
+fn content_hash(document: &Value, embedder: Option<&str>) -> Vec<u8> {
+    let empty = serde_json::Map::new();
+    // Map keys are sorted, so equal contents serialize the same way
+    let content: BTreeMap<&String, &Value> = document.as_object().unwrap_or(&empty).iter()
+        .filter(|(field, _)| !["_vectors", SYNCED_AT_FIELD, SOURCE_TABLE_FIELD, CONTENT_HASH_FIELD].contains(&field.as_str()))
+        .collect();
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&content).unwrap_or_default());
+    if let Some(embedder) = embedder {
+        hasher.update(serde_json::to_vec(&stored_embedding(&document["_vectors"][embedder])).unwrap_or_default());
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Embedder whose vectors the change check compares, for tables sending
+/// their own with `vector_field`
+fn compared_embedder(table: &TableConfig) -> Option<&str> {
+    table.vector_field.as_ref().and(table.embedder_name.as_deref())
+}
+
+// The vector of a `_vectors` entry, sent as an array of numbers and returned
+// by Meilisearch as `{"embeddings": [[...]], "regenerate": false}`, rounded
+// to the 32 bit floats Meilisearch stores. Empty when there is none.
+fn stored_embedding(entry: &Value) -> Vec<f32> {
+    let vector = match entry.get("embeddings") {
+        Some(Value::Array(embeddings)) if embeddings.first().is_some_and(Value::is_array) => &embeddings[0],
+        Some(embeddings) => embeddings,
+        None => entry,
+    };
+    vector.as_array().into_iter().flatten()
+        .filter_map(Value::as_f64)
+        .map(|value| value as f32)
+        .collect()
+}
+
+/// Without a configured `vector_dimensions`, take the most common vector
+/// length of the batch as the expected one and drop documents that differ,
+/// since Meilisearch rejects the whole batch on a dimension mismatch.
+/// Returns how many were dropped.
+fn drop_inconsistent_vectors(table: &TableConfig, embedder: &str, documents: &mut Vec<Value>) -> usize {
+    let dimensions = |doc: &Value| doc["_vectors"][embedder].as_array().map(Vec::len);
+    let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+    for len in documents.iter().filter_map(dimensions) {
+        *counts.entry(len).or_default() += 1;
+    }
+    // Ties go to the smaller length so the choice doesn't depend on row order
+    let Some((&expected, _)) = counts.iter()
+        .max_by_key(|(len, count)| (**count, std::cmp::Reverse(**len)))
+    else {
+        return 0;
+    };
+    let before = documents.len();
+    documents.retain(|doc| match dimensions(doc) {
+        Some(len) if len != expected => {
+            warn!("Skipping document {} of table {}: vector has {} dimensions, expected {}",
+                  doc[table.index_primary_key()], table.name, len, expected);
+            false
+        }
+        _ => true,
+    });
+    before - documents.len()
+}
+
+/// Rows of a lookup's reference table by the string form of their foreign key
+type LookupIndex = HashMap<String, serde_json::Map<String, Value>>;
+
+/// Read the reference tables of a table's lookups, once per sync
+async fn load_lookups(table: &TableConfig, db_adapter: &Arc<Box<dyn DatabaseAdapter>>) -> Result<Vec<LookupIndex>> {
+    let mut lookups = Vec::with_capacity(table.lookups.len());
+    for lookup in &table.lookups {
+        let rows = db_adapter.fetch_all_records(&lookup.table).await?;
+        let index: LookupIndex = rows.into_iter()
+            .filter_map(|row| match row {
+                Value::Object(obj) => match obj.get(&lookup.foreign_key) {
+                    None | Some(Value::Null) => None,
+                    Some(key) => Some((primary_key_string(key), obj)),
+                },
+                _ => None,
+            })
+            .collect();
+        debug!("Loaded {} rows of lookup table {} for table {}", index.len(), lookup.table, table.name);
+        lookups.push(index);
+    }
+    Ok(lookups)
+}
+
+/// Merge the fields of each lookup's matching row into a document. A missing
+/// reference or row omits the fields or sets them to null, per `on_missing`.
+fn apply_lookups(table: &TableConfig, lookups: &[LookupIndex], doc: &mut serde_json::Map<String, Value>) {
+    for (lookup, index) in table.lookups.iter().zip(lookups) {
+        let row = doc.get(&lookup.local_key)
+            .filter(|key| !key.is_null())
+            .and_then(|key| index.get(&primary_key_string(key)));
+        for (field, column) in &lookup.fields {
+            match row {
+                Some(row) => {
+                    doc.insert(field.clone(), row.get(column).cloned().unwrap_or(Value::Null));
+                }
+                None if lookup.on_missing == LookupMissing::Null => {
+                    doc.insert(field.clone(), Value::Null);
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Run the document transform, moving large change sets to a blocking thread
+async fn transform_pending(
+    table: &TableConfig,
+    pending: Vec<PendingDocument>,
+    warn_field_bytes: Option<usize>,
+    synced_at: Option<String>,
+    schema: Option<Arc<jsonschema::Validator>>,
+) -> Result<(Vec<Value>, SyncReport)> {
+    if pending.len() >= PARALLEL_TRANSFORM_THRESHOLD {
+        // The transform is CPU bound, so keep it off the async worker threads
+        let table = table.clone();
+        let span = Span::current();
+        tokio::task::spawn_blocking(move || span.in_scope(|| {
+            transform_documents(&table, pending, warn_field_bytes, synced_at.as_deref(), schema.as_deref())
+        }))
+            .await
+            .map_err(|e| ConnectorError::Config(format!("Document transform task failed: {}", e)))
+    } else {
+        Ok(transform_documents(table, pending, warn_field_bytes, synced_at.as_deref(), schema.as_deref()))
+    }
+}
+
+fn phase_span(phase: &'static str) -> Span {
+    info_span!("phase", phase)
+}
+
+// Truncate text fields to this length
+const MAX_TEXT_LENGTH: usize = 10_000_000;
+// Limit the number of fields per document if too many
+const MAX_FIELDS: usize = 65_536;
+
+// Below this many pending documents the transform runs on the async worker,
+// the thread hop costs more than it saves
+const PARALLEL_TRANSFORM_THRESHOLD: usize = 1_000;
+
+/// Run `process_document_obj` over the pending rows not transformed yet and
+/// check every document against the document schema, in parallel on the
+/// rayon pool; the field size statistics of each worker are merged into the
+/// returned report.
+fn transform_documents(
+    table: &TableConfig,
+    pending: Vec<PendingDocument>,
+    warn_field_bytes: Option<usize>,
+    synced_at: Option<&str>,
+    schema: Option<&jsonschema::Validator>,
+) -> (Vec<Value>, SyncReport) {
+    let transform = |mut acc: (Vec<Value>, SyncReport), pending: PendingDocument| {
+        let (id_str, document) = match pending {
+            PendingDocument::Row(id_str, obj) => {
+                let document = process_document_obj(table, obj, id_str.clone(), MAX_TEXT_LENGTH, MAX_FIELDS, warn_field_bytes, synced_at, &mut acc.1);
+                (id_str, document)
+            }
+            PendingDocument::Transformed(id_str, document) => (id_str, document),
+        };
+        match document {
+            Ok(processed_doc) => {
+                let violations = schema.map(|schema| schema_violations(schema, &processed_doc)).unwrap_or_default();
+                if violations.is_empty() {
+                    acc.0.push(processed_doc);
+                } else {
+                    let reasons: Vec<String> = violations.iter().map(|(field, reason)| format!("{}: {}", field, reason)).collect();
+                    warn!("Skipping document {} of table {}, it doesn't match the document schema: {}",
+                          id_str, table.name, reasons.join("; "));
+                    acc.1.documents_skipped += 1;
+                    let fields: BTreeSet<String> = violations.into_iter().map(|(field, _)| field).collect();
+                    for field in fields {
+                        *acc.1.schema_violations.entry(field).or_default() += 1;
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Skipping document {} of table {}: {}", id_str, table.name, e);
+                acc.1.documents_skipped += 1;
+            }
+        }
+        acc
+    };
+
+    let (mut documents, mut stats) = pending.into_par_iter()
+        .fold(|| (Vec::new(), SyncReport::default()), transform)
+        .reduce(|| (Vec::new(), SyncReport::default()), |mut a, b| {
+            a.0.extend(b.0);
+            merge_transform_stats(&mut a.1, b.1);
+            a
+        });
+
+    if let (Some(embedder), None) = (&table.embedder_name, table.vector_dimensions) {
+        stats.documents_skipped += drop_inconsistent_vectors(table, embedder, &mut documents);
+    }
+    if table.detect_duplicates || table.suppress_duplicates {
+        stats.duplicate_documents = hash_document_contents(table, &mut documents);
+    }
+    (documents, stats)
+}
+
+/// Count the documents with the same content as an earlier one, leaving out
+/// the primary key and the sync time. With `suppress_duplicates` the hash
+/// is added to every document as `CONTENT_HASH_FIELD`.
+fn hash_document_contents(table: &TableConfig, documents: &mut [Value]) -> usize {
+    let mut seen = HashSet::new();
+    let mut duplicates = 0;
+    for document in documents.iter_mut() {
+        let Some(obj) = document.as_object_mut() else { continue };
+        // Map keys are sorted, so equal contents serialize the same way
+        let content: BTreeMap<&String, &Value> = obj.iter()
+            .filter(|(field, _)| ![table.index_primary_key(), SYNCED_AT_FIELD].contains(&field.as_str()))
+            .collect();
+        let hash = format!("{:x}", Sha256::digest(serde_json::to_vec(&content).unwrap_or_default()));
+        if !seen.insert(hash.clone()) {
+            duplicates += 1;
+        }
+        if table.suppress_duplicates {
+            obj.insert(CONTENT_HASH_FIELD.to_string(), Value::String(hash));
+        }
+    }
+    if duplicates > 0 {
+        warn!("{} documents of table {} have the same content as another document of the sync under a different id, \
+               check for joins producing redundant rows", duplicates, table.name);
+    }
+    duplicates
+}
+
+/// Send the queued operations of an index in order, removing each once
+/// Meilisearch accepted it. Stops at the first failure, leaving the rest queued.
+async fn drain_queue(
+    queue: &DurableQueue,
+    meilisearch_client: &Arc<dyn MeilisearchClientTrait>,
+    index_name: &str,
+    batch_size: usize,
+) -> Result<usize> {
+    let pending = queue.pending(index_name).await?;
+    for (position, (id, operation)) in pending.iter().enumerate() {
+        let result = match operation {
+            QueuedOperation::Add { documents } => {
+                meilisearch_client.add_or_update_documents(index_name, documents.clone(), Some(batch_size)).await
+            }
+            QueuedOperation::Delete { ids } => {
+                meilisearch_client.delete_documents(index_name, ids, Some(batch_size)).await
+            }
+        };
+        if let Err(e) = result {
+            warn!("Keeping {} operations queued for index {}: {}", pending.len() - position, index_name, e);
+            return Err(e);
+        }
+        queue.remove(*id).await?;
+    }
+    Ok(pending.len())
+}
+
+fn merge_transform_stats(into: &mut SyncReport, from: SyncReport) {
+    if from.max_field_bytes > into.max_field_bytes {
+        into.max_field_bytes = from.max_field_bytes;
+        into.max_field_name = from.max_field_name;
+    }
+    into.oversized_fields += from.oversized_fields;
+    into.documents_skipped += from.documents_skipped;
+    for (field, count) in from.schema_violations {
+        *into.schema_violations.entry(field).or_default() += count;
+    }
+}
+
+/// Send documents to one index in batches, keeping at most
+/// `max_concurrent_batches` requests in flight. Returns how many batches
+/// failed, the failures are only logged, and how many documents went to
+/// `dead_letters` instead.
+async fn add_document_batches(
+    meilisearch_client: &Arc<dyn MeilisearchClientTrait>,
+    index_name: &str,
+    documents_to_add: Vec<Value>,
+    batch_size: usize,
+    max_concurrent_batches: usize,
+    dead_letters: Option<(&DeadLetterFile, &str)>,
+) -> (usize, usize) {
+    // Process documents in batches to improve performance
+    let total_batches = documents_to_add.len().div_ceil(batch_size);
+    let mut batch_futures = Vec::new();
+    let mut failed = 0;
+    let mut rejected = 0;
+    let mut record = |batch_result: std::result::Result<Result<usize>, tokio::task::JoinError>| match batch_result {
+        Ok(Ok(count)) => rejected += count,
+        Ok(Err(_)) => failed += 1,
+        Err(e) => {
+            error!("Error joining batch task: {:?}", e);
+            failed += 1;
+        }
+    };
+    
+    for (batch_num, chunk) in documents_to_add.chunks(batch_size).enumerate() {
+        let batch_num = batch_num + 1; // 1-indexed for logging
+        let chunk_vec = chunk.to_vec();
+        let index_name = index_name.to_string();
+        let meili_client = meilisearch_client.clone();
+        let dead_letters = dead_letters.map(|(file, table)| (file.clone(), table.to_string()));
+        
+        // Create a future for each batch
+        let future = tokio::spawn(async move {
+            info!("Processing batch {}/{} for index {}", batch_num, total_batches, index_name);
+            let result = match &dead_letters {
+                Some((file, table)) => add_isolating_rejected(&meili_client, &index_name, chunk_vec, file, table).await,
+                None => meili_client.add_or_update_documents(&index_name, chunk_vec, Some(batch_size)).await.map(|_| 0),
+            };
+            match result {
+                Ok(rejected) => {
+                    info!("Successfully added batch {}/{} to index {}", batch_num, total_batches, index_name);
+                    Ok(rejected)
+                },
+                Err(e) => {
+                    error!("Failed to add batch {}/{} to index {}: {}", batch_num, total_batches, index_name, e);
+                    Err(e)
+                }
+            }
+        }.instrument(Span::current()));
+        
+        batch_futures.push(future);
+        
+        // Limit concurrent batches to avoid overwhelming the Meilisearch server
+        if batch_futures.len() >= max_concurrent_batches {
+            // Wait for one batch to complete before adding more
+            if let Some(future) = batch_futures.first_mut() {
+                record(future.await);
+            }
+            batch_futures.remove(0);
+        }
+    }
+    
+    // Wait for all remaining batches to complete
+    for future in batch_futures {
+        record(future.await);
+    }
+    (failed, rejected)
+}
+
+/// Add a batch with `add_documents_checked`, halving any part Meilisearch
+/// refuses and retrying the halves, down to the single documents it refuses.
+/// Those are written to `dead_letters` with Meilisearch's reason; a failed
+/// write is logged, as the other documents are already indexed. Returns how
+/// many documents were refused; other errors, such as Meilisearch being
+/// unreachable, fail the batch as usual.
+async fn add_isolating_rejected(
+    meilisearch_client: &Arc<dyn MeilisearchClientTrait>,
+    index_name: &str,
+    documents: Vec<Value>,
+    dead_letters: &DeadLetterFile,
+    table: &str,
+) -> Result<usize> {
+    let mut rejected = Vec::new();
+    // Work through the parts in order; a split pushes its halves back in front
+    let mut pending = vec![documents.as_slice()];
+    while let Some(part) = pending.pop() {
+        match meilisearch_client.add_documents_checked(index_name, part.to_vec()).await {
+            Ok(()) => {}
+            Err(ConnectorError::DocumentsRejected(reason)) if part.len() > 1 => {
+                let (first, second) = part.split_at(part.len() / 2);
+                debug!("Meilisearch refused {} documents for index {}, retrying as {} and {}: {}",
+                       part.len(), index_name, first.len(), second.len(), reason);
+                pending.push(second);
+                pending.push(first);
+            }
+            Err(ConnectorError::DocumentsRejected(reason)) => {
+                warn!("Meilisearch refused a document of table {} for index {}: {}", table, index_name, reason);
+                rejected.extend(part.iter().map(|document| (document.clone(), reason.clone())));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    if !rejected.is_empty() {
+        if let Err(e) = dead_letters.append(table, index_name, &rejected).await {
+            error!("Failed to write {} refused documents of table {} to the dead letter file: {}", rejected.len(), table, e);
+        }
+    }
+    Ok(rejected.len())
+}
+
+fn record_status(status: &Mutex<BTreeMap<String, TableStatus>>, table: &str, result: &Result<SyncReport>) {
+    let mut status = status.lock().unwrap();
+    status.entry(table.to_string()).or_default().record(result.as_ref().map_err(|e| e.to_string()));
+}
+
+fn log_sync_profile(report: &SyncReport, profile: bool) {
+    if profile {
+        info!("Sync profile for table {} (index {}): {}", report.table, report.index, report.timings.summary());
+    } else {
+        debug!("Sync profile for table {} (index {}): {}", report.table, report.index, report.timings.summary());
+    }
+    if let Some(field) = &report.max_field_name {
+        debug!("Largest field synced for table {}: '{}' ({} bytes, {} above warn_field_bytes)",
+               report.table, field, report.max_field_bytes, report.oversized_fields);
+    }
+}
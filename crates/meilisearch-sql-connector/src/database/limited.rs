@@ -0,0 +1,139 @@
+use crate::database::{ColumnInfo, DatabaseAdapter};
+use crate::error::{ConnectorError, Result};
+use futures_util::stream::{BoxStream, TryStreamExt};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Wraps an adapter so that queries on some tables hold at most a given
+/// number of pool connections at once, leaving the rest of a shared pool to
+/// the other tables, and optionally so that at most a given number of table
+/// queries run at once overall. Queries without a limit pass straight through.
+pub struct ConnectionLimitedAdapter {
+    inner: Arc<Box<dyn DatabaseAdapter>>,
+    limits: HashMap<String, Semaphore>,
+    queries: Option<Semaphore>,
+}
+
+// The permits a query holds while it runs
+type Permits<'a> = (Option<SemaphorePermit<'a>>, Option<SemaphorePermit<'a>>);
+
+impl ConnectionLimitedAdapter {
+    pub fn new(
+        inner: Arc<Box<dyn DatabaseAdapter>>,
+        limits: impl IntoIterator<Item = (String, u32)>,
+        max_queries: Option<u32>,
+    ) -> Self {
+        Self {
+            inner,
+            limits: limits.into_iter()
+                .map(|(table, max)| (table, Semaphore::new(max as usize)))
+                .collect(),
+            queries: max_queries.map(|max| Semaphore::new(max as usize)),
+        }
+    }
+
+    // Wait for one of the table's connections, if it has a limit, then for
+    // one of the connector's queries. Taking the table's first keeps a table
+    // at its limit from holding query permits other tables could use.
+    async fn acquire(&self, table: &str) -> Result<Permits<'_>> {
+        let table_permit = match self.limits.get(table) {
+            Some(semaphore) => Some(semaphore.acquire().await
+                .map_err(|e| ConnectorError::Database(format!("Connection limit of table {} closed: {}", table, e)))?),
+            None => None,
+        };
+        let query_permit = match &self.queries {
+            Some(semaphore) => Some(semaphore.acquire().await
+                .map_err(|e| ConnectorError::Database(format!("Query limit closed: {}", e)))?),
+            None => None,
+        };
+        Ok((table_permit, query_permit))
+    }
+}
+
+#[async_trait::async_trait]
+impl DatabaseAdapter for ConnectionLimitedAdapter {
+    async fn fetch_all_records(&self, table: &str) -> Result<Vec<Value>> {
+        let _permits = self.acquire(table).await?;
+        self.inner.fetch_all_records(table).await
+    }
+
+    async fn fetch_records(&self, table: &str, columns: Option<&[String]>) -> Result<Vec<Value>> {
+        let _permits = self.acquire(table).await?;
+        self.inner.fetch_records(table, columns).await
+    }
+
+    fn fetch_records_stream<'a>(&'a self, table: &'a str, columns: Option<&'a [String]>) -> BoxStream<'a, Result<Value>> {
+        // The permits are held until the stream is read to the end or dropped
+        Box::pin(async_stream::try_stream! {
+            let _permits = self.acquire(table).await?;
+            let mut records = self.inner.fetch_records_stream(table, columns);
+            while let Some(record) = records.try_next().await? {
+                yield record;
+            }
+        })
+    }
+
+    async fn fetch_records_ordered(&self, table: &str, columns: Option<&[String]>, order_by: &str) -> Result<Option<Vec<Value>>> {
+        let _permits = self.acquire(table).await?;
+        self.inner.fetch_records_ordered(table, columns, order_by).await
+    }
+
+    async fn fetch_records_since(&self, table: &str, columns: Option<&[String]>, column: &str, since: &Value) -> Result<Option<Vec<Value>>> {
+        let _permits = self.acquire(table).await?;
+        self.inner.fetch_records_since(table, columns, column, since).await
+    }
+
+    async fn get_all_tables(&self) -> Result<Vec<String>> {
+        self.inner.get_all_tables().await
+    }
+
+    async fn get_table_columns(&self, table: &str) -> Result<Vec<(String, String, bool)>> {
+        let _permits = self.acquire(table).await?;
+        self.inner.get_table_columns(table).await
+    }
+
+    async fn get_primary_key(&self, table: &str) -> Result<String> {
+        let _permits = self.acquire(table).await?;
+        self.inner.get_primary_key(table).await
+    }
+
+    async fn get_all_views(&self) -> Result<Vec<String>> {
+        self.inner.get_all_views().await
+    }
+
+    async fn get_table_schema(&self, table: &str) -> Result<Vec<(String, String)>> {
+        let _permits = self.acquire(table).await?;
+        self.inner.get_table_schema(table).await
+    }
+
+    async fn get_column_details(&self, table: &str) -> Result<Vec<ColumnInfo>> {
+        let _permits = self.acquire(table).await?;
+        self.inner.get_column_details(table).await
+    }
+
+    async fn count_records(&self, table: &str) -> Result<usize> {
+        let _permits = self.acquire(table).await?;
+        self.inner.count_records(table).await
+    }
+
+    async fn fetch_sample_records(&self, table: &str, limit: usize) -> Result<Vec<Value>> {
+        let _permits = self.acquire(table).await?;
+        self.inner.fetch_sample_records(table, limit).await
+    }
+
+    async fn fetch_random_records(&self, table: &str, limit: usize) -> Result<Vec<Value>> {
+        let _permits = self.acquire(table).await?;
+        self.inner.fetch_random_records(table, limit).await
+    }
+
+    async fn replication_lag(&self) -> Result<Option<Duration>> {
+        self.inner.replication_lag().await
+    }
+
+    fn take_decode_errors(&self, table: &str) -> BTreeMap<String, usize> {
+        self.inner.take_decode_errors(table)
+    }
+}
@@ -1,8 +1,16 @@
+use futures_util::stream::{self, BoxStream, StreamExt, TryStreamExt};
 use serde_json::Value;
-use std::sync::Arc;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
 
+use crate::config::{DatabaseConfig, TableConfig};
 use crate::error::Result;
 
+pub mod limited;
+
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
 
@@ -18,6 +26,42 @@ pub trait DatabaseAdapter: Send + Sync {
     /// Fetch all records from a table
     async fn fetch_all_records(&self, table: &str) -> Result<Vec<Value>>;
     
+    /// Fetch all records from a table with only `columns`, or every column
+    /// when `None`. Adapters that can't select columns fetch whole records
+    /// and drop the others.
+    async fn fetch_records(&self, table: &str, columns: Option<&[String]>) -> Result<Vec<Value>> {
+        let records = self.fetch_all_records(table).await?;
+        Ok(match columns {
+            Some(columns) => records.into_iter().map(|record| project_record(record, columns)).collect(),
+            None => records,
+        })
+    }
+
+    /// Like `fetch_records`, as a stream yielding the records while they are
+    /// read, so a table larger than memory can be processed a part at a
+    /// time. Adapters that can't stream read every record before yielding
+    /// the first.
+    fn fetch_records_stream<'a>(&'a self, table: &'a str, columns: Option<&'a [String]>) -> BoxStream<'a, Result<Value>> {
+        stream::once(self.fetch_records(table, columns))
+            .map_ok(|records| stream::iter(records.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed()
+    }
+
+    /// Like `fetch_records`, ordered by the column `order_by` as
+    /// `compare_primary_keys` orders its values. `None` for adapters that
+    /// can't order records.
+    async fn fetch_records_ordered(&self, _table: &str, _columns: Option<&[String]>, _order_by: &str) -> Result<Option<Vec<Value>>> {
+        Ok(None)
+    }
+
+    /// Like `fetch_records`, only the records whose `column` is at least
+    /// `since`, a number or a string as the column's values are returned.
+    /// `None` for adapters that can't filter records.
+    async fn fetch_records_since(&self, _table: &str, _columns: Option<&[String]>, _column: &str, _since: &Value) -> Result<Option<Vec<Value>>> {
+        Ok(None)
+    }
+    
     /// Get all tables in the database
     async fn get_all_tables(&self) -> Result<Vec<String>>;
     
@@ -26,10 +70,312 @@ pub trait DatabaseAdapter: Send + Sync {
     
     /// Get the primary key of a table
     async fn get_primary_key(&self, table: &str) -> Result<String>;
+    
+    /// Get all views (including materialized views) in the database. Adapters
+    /// that can't list views return none.
+    async fn get_all_views(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+    
+    /// Get the schema of a table as (column name, SQL type) pairs, in column order
+    async fn get_table_schema(&self, table: &str) -> Result<Vec<(String, String)>> {
+        let columns = self.get_table_columns(table).await?;
+        Ok(columns.into_iter().map(|(name, type_, _)| (name, type_)).collect())
+    }
+    
+    /// Get all columns of a table with their nullability. Adapters that can't
+    /// tell leave `nullable` unset.
+    async fn get_column_details(&self, table: &str) -> Result<Vec<ColumnInfo>> {
+        let columns = self.get_table_columns(table).await?;
+        Ok(columns.into_iter()
+            .map(|(name, type_, primary_key)| ColumnInfo { name, type_, primary_key, nullable: None })
+            .collect())
+    }
+    
+    /// Count the records of a table
+    async fn count_records(&self, table: &str) -> Result<usize> {
+        Ok(self.fetch_all_records(table).await?.len())
+    }
+    
+    /// Fetch up to `limit` records of a table, converted like `fetch_all_records`
+    async fn fetch_sample_records(&self, table: &str, limit: usize) -> Result<Vec<Value>> {
+        let mut records = self.fetch_all_records(table).await?;
+        records.truncate(limit);
+        Ok(records)
+    }
+    
+    /// Fetch up to `limit` records picked at random. Adapters that can't
+    /// sample fall back to the first `limit` records.
+    async fn fetch_random_records(&self, table: &str, limit: usize) -> Result<Vec<Value>> {
+        self.fetch_sample_records(table, limit).await
+    }
+    
+    /// How far the database is behind its primary when it is a replica.
+    /// `None` for databases that aren't replicas and adapters that can't tell.
+    async fn replication_lag(&self) -> Result<Option<Duration>> {
+        Ok(None)
+    }
+    
+    /// Number of values per column of `table` that couldn't be decoded and
+    /// were returned as null since the last call. Adapters that don't track
+    /// decoding report none.
+    fn take_decode_errors(&self, _table: &str) -> BTreeMap<String, usize> {
+        BTreeMap::new()
+    }
+}
+
+/// Keep only `columns` of a record
+pub fn project_record(record: Value, columns: &[String]) -> Value {
+    match record {
+        Value::Object(mut obj) => {
+            obj.retain(|name, _| columns.contains(name));
+            Value::Object(obj)
+        }
+        other => other,
+    }
 }
 
+/// The order of primary key values in `fetch_records_ordered`, SQLite's
+/// `ORDER BY`: nulls, then numbers by value, then strings by their bytes
+pub fn compare_primary_keys(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Number(_) => 1,
+            Value::String(_) => 2,
+            _ => 3,
+        }
+    }
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => match (a.as_i64(), b.as_i64()) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => a.as_f64().unwrap_or(f64::NAN).total_cmp(&b.as_f64().unwrap_or(f64::NAN)),
+        },
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// `column (count), ...` listing of decode errors
+pub fn format_decode_errors(errors: &BTreeMap<String, usize>) -> String {
+    errors.iter()
+        .map(|(column, count)| format!("{} ({})", column, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A table column as reported by `DatabaseAdapter::get_column_details`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub type_: String,
+    pub primary_key: bool,
+    pub nullable: Option<bool>,
+}
+
+/// Connection and row conversion settings passed to an adapter
+#[derive(Debug, Clone, Default)]
+pub struct AdapterOptions {
+    /// Maximum number of pooled connections
+    pub pool_size: Option<u32>,
+    /// Integers with a magnitude above this are emitted as JSON strings
+    pub large_int_threshold: Option<u64>,
+    /// Extra connection attempts when the database can't be reached
+    pub connect_retries: u32,
+    /// Delay before the first retry, doubled after every failed attempt
+    pub connect_retry_delay: Duration,
+    /// Fail fetches with values that can't be decoded instead of returning null
+    pub strict_decoding: bool,
+    /// Create an empty SQLite database when the file doesn't exist
+    pub create_if_missing: bool,
+    /// `WHERE` conditions limiting which rows of each table are read
+    pub row_filters: RowFilters,
+}
+
+impl From<&DatabaseConfig> for AdapterOptions {
+    fn from(config: &DatabaseConfig) -> Self {
+        Self {
+            pool_size: Some(config.connection_pool_size),
+            large_int_threshold: config.large_int_as_string.then_some(config.large_int_threshold),
+            connect_retries: config.db_connect_retries,
+            connect_retry_delay: Duration::from_secs(config.db_connect_retry_delay_seconds),
+            strict_decoding: config.strict_decoding,
+            create_if_missing: config.create_if_missing,
+            row_filters: RowFilters::new(&config.tables),
+        }
+    }
+}
+
+/// The `filter` of every configured table, applied by adapters to each
+/// query reading the table's rows, along with the table's retention window.
+/// Clones share the tables, so a configuration reload reaches every adapter.
+#[derive(Debug, Clone, Default)]
+pub struct RowFilters {
+    tables: Arc<RwLock<Vec<TableConfig>>>,
+    dialect: Dialect,
+}
+
+/// SQL flavor of the conditions `RowFilters` writes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+// Variants of the adapters not built are unused
+#[allow(dead_code)]
+pub enum Dialect {
+    #[default]
+    Sqlite,
+    Postgres,
+    MySql,
+}
+
+// Margin the query's retention condition leaves before the window starts.
+// The condition compares dates, ignoring time zones, so it keeps rows up to a
+// day older than the window; the sync then drops them by their exact time.
+const RETENTION_QUERY_MARGIN_SECS: u64 = 86_400;
+
+// Values from this magnitude are epoch milliseconds, as in `parse_timestamp`
+const EPOCH_MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+impl RowFilters {
+    pub fn new(tables: &[TableConfig]) -> Self {
+        let filters = Self::default();
+        filters.set_tables(tables);
+        filters
+    }
+
+    /// Replace the tables, for this value and every clone of it
+    pub fn set_tables(&self, tables: &[TableConfig]) {
+        *self.tables.write().unwrap() = tables.iter().filter(|t| t.is_enabled()).cloned().collect();
+    }
+
+    /// The same filters, with conditions written for `dialect`
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    // The configuration of `table`. A table's own block wins over the pattern
+    // blocks matching it, which apply to discovered tables.
+    fn table(&self, table: &str) -> Option<TableConfig> {
+        let tables = self.tables.read().unwrap();
+        let exact = tables.iter().find(|t| t.name_match.is_exact() && t.name == table);
+        exact.or_else(|| tables.iter().find(|t| !t.name_match.is_exact() && t.matches(table))).cloned()
+    }
+
+    /// Condition the rows of `table` must meet
+    pub fn get(&self, table: &str) -> Option<String> {
+        self.table(table).and_then(|t| t.filter)
+    }
+
+    /// Condition keeping the rows of `table` whose `retention_column` may be
+    /// within `retention_window_seconds` of `now`. It errs on keeping rows,
+    /// see `RETENTION_QUERY_MARGIN_SECS`, and like `parse_timestamp` reads
+    /// numbers as epoch seconds or milliseconds and text as ISO 8601 dates.
+    pub fn retention_condition(&self, table: &str, now: SystemTime) -> Option<String> {
+        let table = self.table(table)?;
+        let (column, window) = (table.retention_column.as_deref()?, table.retention_window_seconds?);
+        let now = now.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
+        let cutoff = now.saturating_sub(window + RETENTION_QUERY_MARGIN_SECS) as i64;
+        let date = chrono::DateTime::from_timestamp(cutoff, 0).unwrap_or_default().format("%Y-%m-%d");
+        let ms = EPOCH_MILLIS_THRESHOLD;
+        Some(match self.dialect {
+            Dialect::Sqlite => {
+                let text = format!("trim(CAST({} AS TEXT))", quote_identifier(column, self.dialect.identifier_quote()));
+                format!(
+                    "(CASE WHEN {text} <> '' AND {text} NOT GLOB '*[^0-9.]*' \
+                     THEN (CASE WHEN CAST({text} AS REAL) >= {ms} THEN CAST({text} AS REAL) / 1000 ELSE CAST({text} AS REAL) END) > {cutoff} \
+                     ELSE substr({text}, 1, 10) >= '{date}' END)"
+                )
+            }
+            Dialect::Postgres => {
+                let text = format!("btrim({}::text)", quote_identifier(column, self.dialect.identifier_quote()));
+                format!(
+                    "(CASE WHEN {text} ~ '^[0-9]+([.][0-9]+)?(e[+]?[0-9]+)?$' \
+                     THEN (CASE WHEN {text}::numeric >= {ms} THEN {text}::numeric / 1000 ELSE {text}::numeric END) > {cutoff} \
+                     ELSE left({text}, 10) COLLATE \"C\" >= '{date}' END)"
+                )
+            }
+            Dialect::MySql => {
+                let text = format!("TRIM(CAST({} AS CHAR))", quote_identifier(column, self.dialect.identifier_quote()));
+                let number = format!("CAST({text} AS DECIMAL(30, 6))");
+                format!(
+                    "(CASE WHEN {text} REGEXP '^[0-9]+([.][0-9]+)?$' \
+                     THEN (CASE WHEN {number} >= {ms} THEN {number} / 1000 ELSE {number} END) > {cutoff} \
+                     ELSE LEFT({text}, 10) >= '{date}' END)"
+                )
+            }
+        })
+    }
+
+    /// `table`, quoted, followed by a `WHERE` clause combining its filter,
+    /// its retention window and `condition`, for use after `FROM`
+    pub fn filtered_table(&self, table: &str, condition: Option<&str>) -> String {
+        let retention = self.retention_condition(table, SystemTime::now());
+        let filter = self.get(table).map(|filter| format!("({})", filter));
+        let conditions: Vec<&str> = [filter.as_deref(), retention.as_deref(), condition].into_iter().flatten().collect();
+        // Unquoted, a table named after a keyword such as `user` reads
+        // something else entirely on Postgres
+        let quoted = quote_identifier(table, self.dialect.identifier_quote());
+        if conditions.is_empty() {
+            quoted
+        } else {
+            format!("{} WHERE {}", quoted, conditions.join(" AND "))
+        }
+    }
+}
+
+impl Dialect {
+    fn identifier_quote(self) -> char {
+        match self {
+            Dialect::Sqlite | Dialect::Postgres => '"',
+            Dialect::MySql => '`',
+        }
+    }
+}
+
+// `column` between `quote`s, the way each dialect quotes identifiers
+fn quote_identifier(column: &str, quote: char) -> String {
+    format!("{quote}{}{quote}", column.replace(quote, &format!("{quote}{quote}")))
+}
+
+/// Scheme assumed for connection strings that are bare filesystem paths
+pub const DEFAULT_SCHEME: &str = "sqlite";
+
+/// Turn a connection string into a database URL. Bare filesystem paths such
+/// as `./data.db`, `/var/lib/app.db` or `data.db` (and Windows drive paths,
+/// which parse with a one letter scheme) get `default_scheme` prepended.
+pub fn normalize_database_url(url: &str, default_scheme: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(parsed) if parsed.scheme().len() > 1 => url.to_string(),
+        Ok(_) | Err(url::ParseError::RelativeUrlWithoutBase) => format!("{}:{}", default_scheme, url),
+        Err(_) => url.to_string(),
+    }
+}
+
+// Upper bound for the doubling delay between connection attempts
+const MAX_CONNECT_RETRY_DELAY: Duration = Duration::from_secs(60);
+
 // Database URL parser and connection factory
-pub async fn create_db_adapter(url: &str, pool_size: Option<u32>) -> Result<Arc<Box<dyn DatabaseAdapter>>> {
+pub async fn create_db_adapter(url: &str, options: &AdapterOptions) -> Result<Arc<Box<dyn DatabaseAdapter>>> {
+    let url = &normalize_database_url(url, DEFAULT_SCHEME);
+    let mut delay = options.connect_retry_delay;
+    let mut attempt = 0;
+    loop {
+        // Connection errors reach users and error reporters, so they must not
+        // carry the password of the URL
+        match connect_adapter(url, options).await.map_err(crate::error::ConnectorError::redacted) {
+            // Only connection failures are worth waiting out
+            Err(crate::error::ConnectorError::Database(e)) if attempt < options.connect_retries => {
+                attempt += 1;
+                warn!("Database connection attempt {}/{} failed: {}. Retrying in {:?}",
+                      attempt, options.connect_retries + 1, e, delay);
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_CONNECT_RETRY_DELAY);
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn connect_adapter(url: &str, options: &AdapterOptions) -> Result<Arc<Box<dyn DatabaseAdapter>>> {
     let parsed_url = url::Url::parse(url).map_err(|e| {
         crate::error::ConnectorError::Config(format!("Invalid database URL: {}", e))
     })?;
@@ -38,19 +384,36 @@ pub async fn create_db_adapter(url: &str, pool_size: Option<u32>) -> Result<Arc<
         #[cfg(feature = "sqlite")]
         "sqlite" => {
             let path = parsed_url.path();
-            if let Some(size) = pool_size {
-                Box::new(sqlite::SqliteAdapter::new_with_pool_size(path, size).await?)
-            } else {
-                Box::new(sqlite::SqliteAdapter::new(path).await?)
-            }
+            let adapter = match (options.pool_size, options.create_if_missing) {
+                (size, true) => sqlite::SqliteAdapter::new_with_options(path, size.unwrap_or(5), true).await?,
+                (Some(size), false) => sqlite::SqliteAdapter::new_with_pool_size(path, size).await?,
+                (None, false) => sqlite::SqliteAdapter::new(path).await?,
+            };
+            Box::new(adapter
+                .with_large_int_threshold(options.large_int_threshold)
+                .with_strict_decoding(options.strict_decoding)
+                .with_row_filters(options.row_filters.clone()))
         },
         #[cfg(feature = "postgres")]
         "postgres" | "postgresql" => {
-            Box::new(postgres::PostgresAdapter::new(url).await?)
+            let adapter = match options.pool_size {
+                Some(size) => postgres::PostgresAdapter::new_with_pool_size(url, size).await?,
+                None => postgres::PostgresAdapter::new(url).await?,
+            };
+            Box::new(adapter
+                .with_large_int_threshold(options.large_int_threshold)
+                .with_row_filters(options.row_filters.clone()))
         },
         #[cfg(feature = "mysql")]
         "mysql" => {
-            Box::new(mysql::MySqlAdapter::new(url).await?)
+            let adapter = match options.pool_size {
+                Some(size) => mysql::MySqlAdapter::new_with_pool_size(url, size).await?,
+                None => mysql::MySqlAdapter::new(url).await?,
+            };
+            Box::new(adapter
+                .with_large_int_threshold(options.large_int_threshold)
+                .with_strict_decoding(options.strict_decoding)
+                .with_row_filters(options.row_filters.clone()))
         },
         scheme => return Err(crate::error::ConnectorError::UnsupportedDatabaseType(scheme.to_string())),
     };
@@ -58,10 +421,28 @@ pub async fn create_db_adapter(url: &str, pool_size: Option<u32>) -> Result<Arc<
     Ok(Arc::new(adapter))
 }
 
+/// Apply the `max_connections` of `tables` and a limit on the table queries
+/// running at once to an adapter, returning it unchanged when there are none
+pub fn limit_table_connections(
+    adapter: Arc<Box<dyn DatabaseAdapter>>,
+    tables: &[TableConfig],
+    max_queries: Option<u32>,
+) -> Arc<Box<dyn DatabaseAdapter>> {
+    let limits: Vec<(String, u32)> = tables.iter()
+        .filter_map(|t| t.max_connections.map(|max| (t.name.clone(), max)))
+        .collect();
+    if limits.is_empty() && max_queries.is_none() {
+        return adapter;
+    }
+    Arc::new(Box::new(limited::ConnectionLimitedAdapter::new(adapter, limits, max_queries)))
+}
+
 // Conditional exports based on enabled features
 
 #[cfg(feature = "postgres")]
+#[allow(unused_imports)]
 pub use postgres::PostgresAdapter;
 
 #[cfg(feature = "mysql")]
+#[allow(unused_imports)]
 pub use mysql::MySqlAdapter;
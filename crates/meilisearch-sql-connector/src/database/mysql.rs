@@ -1,51 +1,355 @@
-use crate::database::DatabaseAdapter;
-use crate::error::{ConnectorError, Result};
-use serde_json::Value;
+use crate::database::{format_decode_errors, DatabaseAdapter, Dialect, RowFilters};
+use crate::error::{redact_credentials, ConnectorError, Result};
+use sqlx::{Column, Executor, MySqlPool, Row, TypeInfo, mysql::{MySqlPoolOptions, MySqlRow}};
+use sqlx::types::time::{Date, PrimitiveDateTime, Time};
+use futures_util::stream::{BoxStream, TryStreamExt};
+use serde_json::{Value, Map};
 use async_trait::async_trait;
-use tracing::{info, debug};
+use tracing::{info, debug, instrument};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
 
 pub struct MySqlAdapter {
-    connection_string: String,
+    pool: MySqlPool,
+    large_int_threshold: Option<u64>,
+    strict_decoding: bool,
+    row_filters: RowFilters,
+    // Values that couldn't be decoded since the last `take_decode_errors`, by table and column
+    decode_errors: Mutex<HashMap<String, BTreeMap<String, usize>>>,
 }
 
 impl MySqlAdapter {
     pub async fn new(connection_string: &str) -> Result<Self> {
-        info!("MySQL adapter is currently a stub implementation");
+        // Default pool size, overridden when used by the connector
+        Self::new_with_pool_size(connection_string, 5).await
+    }
+
+    pub async fn new_with_pool_size(connection_string: &str, pool_size: u32) -> Result<Self> {
+        debug!("MySQL adapter initializing with pool size: {}", pool_size);
+
+        let pool = MySqlPoolOptions::new()
+            .max_connections(pool_size)
+            .connect(connection_string)
+            .await
+            .map_err(|e| ConnectorError::Database(redact_credentials(&format!(
+                "Failed to connect to MySQL database at {}: {}", connection_string, e
+            ))))?;
+
+        info!("Connected to MySQL database with connection pool size {}", pool_size);
+
         Ok(Self {
-            connection_string: connection_string.to_string(),
+            pool,
+            large_int_threshold: None,
+            strict_decoding: false,
+            row_filters: RowFilters::default(),
+            decode_errors: Mutex::new(HashMap::new()),
         })
     }
+
+    /// Emit integers whose magnitude exceeds `threshold` as JSON strings so
+    /// consumers parsing numbers as doubles don't silently lose precision
+    pub fn with_large_int_threshold(mut self, threshold: Option<u64>) -> Self {
+        self.large_int_threshold = threshold;
+        self
+    }
+
+    /// Fail fetches with a row whose values can't all be decoded, instead of
+    /// indexing them as null
+    pub fn with_strict_decoding(mut self, strict: bool) -> Self {
+        self.strict_decoding = strict;
+        self
+    }
+
+    /// Only read the rows of each table meeting its filter
+    pub fn with_row_filters(mut self, row_filters: RowFilters) -> Self {
+        self.row_filters = row_filters.with_dialect(Dialect::MySql);
+        self
+    }
+
+    fn integer_to_json(&self, val: i64) -> Value {
+        match self.large_int_threshold {
+            Some(threshold) if val.unsigned_abs() > threshold => Value::String(val.to_string()),
+            _ => Value::Number(val.into()),
+        }
+    }
+
+    fn unsigned_to_json(&self, val: u64) -> Value {
+        match self.large_int_threshold {
+            Some(threshold) if val > threshold => Value::String(val.to_string()),
+            _ => Value::Number(val.into()),
+        }
+    }
+
+    #[instrument(name = "database_query", skip_all, fields(table = table))]
+    async fn fetch(&self, query: &str, table: &str) -> Result<Vec<Value>> {
+        debug!("Executing query: {}", query);
+
+        let rows = sqlx::query(query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to fetch records: {}", e)))?;
+
+        self.rows_to_json(table, rows)
+    }
+
+    /// Convert the rows of `table`, counting the values that couldn't be
+    /// decoded (or failing in strict mode)
+    fn rows_to_json(&self, table: &str, rows: Vec<MySqlRow>) -> Result<Vec<Value>> {
+        let mut failures = BTreeMap::new();
+        let records = rows.iter().map(|row| self.row_to_json(row, &mut failures)).collect();
+        self.record_decode_errors(table, failures)?;
+        Ok(records)
+    }
+
+    /// Count the values of `table` that couldn't be decoded for
+    /// `take_decode_errors`, or fail in strict mode
+    fn record_decode_errors(&self, table: &str, failures: BTreeMap<String, usize>) -> Result<()> {
+        if failures.is_empty() {
+            return Ok(());
+        }
+        if self.strict_decoding {
+            return Err(ConnectorError::Database(format!(
+                "Could not decode values of table {}: {}", table, format_decode_errors(&failures)
+            )));
+        }
+        let mut decode_errors = self.decode_errors.lock().unwrap();
+        let counts = decode_errors.entry(table.to_string()).or_default();
+        for (column, count) in failures {
+            *counts.entry(column).or_default() += count;
+        }
+        Ok(())
+    }
+
+    fn row_to_json(&self, row: &MySqlRow, failures: &mut BTreeMap<String, usize>) -> Value {
+        let mut map = Map::new();
+
+        for (i, column) in row.columns().iter().enumerate() {
+            let column_name = column.name();
+            let value = self.column_to_json(row, i, column.type_info().name()).unwrap_or_else(|| {
+                debug!("Could not decode column {} of type {}", column_name, column.type_info().name());
+                *failures.entry(column_name.to_string()).or_default() += 1;
+                Value::Null
+            });
+            map.insert(column_name.to_string(), value);
+        }
+
+        Value::Object(map)
+    }
+
+    // The value of column `i` by its MySQL type, `None` when it can't be decoded
+    fn column_to_json(&self, row: &MySqlRow, i: usize, type_name: &str) -> Option<Value> {
+        let value = match type_name {
+            // TINYINT(1), MySQL's BOOLEAN
+            "BOOLEAN" => row.try_get::<Option<bool>, _>(i).ok()?.map(Value::Bool),
+            "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+                row.try_get::<Option<i64>, _>(i).ok()?.map(|val| self.integer_to_json(val))
+            }
+            "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED"
+            | "BIGINT UNSIGNED" | "YEAR" | "BIT" => {
+                row.try_get::<Option<u64>, _>(i).ok()?.map(|val| self.unsigned_to_json(val))
+            }
+            // JSON has no infinity or NaN
+            "FLOAT" => match row.try_get::<Option<f32>, _>(i).ok()? {
+                Some(val) => Some(Value::Number(serde_json::Number::from_f64(val.into())?)),
+                None => None,
+            },
+            "DOUBLE" => match row.try_get::<Option<f64>, _>(i).ok()? {
+                Some(val) => Some(Value::Number(serde_json::Number::from_f64(val)?)),
+                None => None,
+            },
+            // Decimals are sent as their digits, which sqlx only decodes into
+            // the decimal types of features the connector doesn't enable
+            "DECIMAL" => match row.try_get_unchecked::<Option<String>, _>(i).ok()? {
+                Some(val) => Some(Value::Number(serde_json::Number::from_f64(val.parse().ok()?)?)),
+                None => None,
+            },
+            // Dates and times as ISO 8601 strings
+            "DATETIME" | "TIMESTAMP" => row.try_get::<Option<PrimitiveDateTime>, _>(i).ok()?
+                .map(|val| Value::String(format!("{}T{}", val.date(), format_time(val.time())))),
+            "DATE" => row.try_get::<Option<Date>, _>(i).ok()?.map(|val| Value::String(val.to_string())),
+            "TIME" => row.try_get::<Option<Time>, _>(i).ok()?.map(|val| Value::String(format_time(val))),
+            "JSON" => row.try_get::<Option<Value>, _>(i).ok()?,
+            "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "GEOMETRY" => {
+                row.try_get_unchecked::<Option<Vec<u8>>, _>(i).ok()?.map(|val| Value::String(format!("BLOB({})", val.len())))
+            }
+            // CHAR, VARCHAR, TEXT, ENUM and SET
+            _ => row.try_get_unchecked::<Option<String>, _>(i).ok()?.map(Value::String),
+        };
+        Some(value.unwrap_or(Value::Null))
+    }
 }
 
 #[async_trait]
 impl DatabaseAdapter for MySqlAdapter {
     async fn fetch_all_records(&self, table: &str) -> Result<Vec<Value>> {
-        debug!("MySQL stub: fetch_all_records called for table {}", table);
-        Err(ConnectorError::NotImplemented("MySQL adapter fetch_all_records".to_string()))
+        self.fetch(&format!("SELECT * FROM {}", self.row_filters.filtered_table(table, None)), table).await
+    }
+
+    async fn fetch_records(&self, table: &str, columns: Option<&[String]>) -> Result<Vec<Value>> {
+        let Some(columns) = columns else {
+            return self.fetch_all_records(table).await;
+        };
+        let select_list = columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", ");
+        self.fetch(&format!("SELECT {} FROM {}", select_list, self.row_filters.filtered_table(table, None)), table).await
     }
-    
-    async fn fetch_record(&self, table: &str, id: &str) -> Result<Value> {
-        debug!("MySQL stub: fetch_record called for table {}, id {}", table, id);
-        Err(ConnectorError::NotImplemented("MySQL adapter fetch_record".to_string()))
+
+    async fn fetch_records_ordered(&self, table: &str, columns: Option<&[String]>, order_by: &str) -> Result<Option<Vec<Value>>> {
+        let select_list = match columns {
+            Some(columns) => columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", "),
+            None => "*".to_string(),
+        };
+        let query = format!("SELECT {} FROM {} ORDER BY {}", select_list, self.row_filters.filtered_table(table, None), quote_identifier(order_by));
+        self.fetch(&query, table).await.map(Some)
+    }
+
+    fn fetch_records_stream<'a>(&'a self, table: &'a str, columns: Option<&'a [String]>) -> BoxStream<'a, Result<Value>> {
+        Box::pin(async_stream::try_stream! {
+            let select_list = match columns {
+                Some(columns) => columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", "),
+                None => "*".to_string(),
+            };
+            let query = format!("SELECT {} FROM {}", select_list, self.row_filters.filtered_table(table, None));
+            debug!("Streaming query: {}", query);
+
+            let mut rows = sqlx::query(&query).fetch(&self.pool);
+            let mut failures = BTreeMap::new();
+            while let Some(row) = rows.try_next().await
+                .map_err(|e| ConnectorError::Database(format!("Failed to fetch records: {}", e)))?
+            {
+                let record = self.row_to_json(&row, &mut failures);
+                // Strict mode fails on the first row that can't be decoded
+                if self.strict_decoding {
+                    self.record_decode_errors(table, std::mem::take(&mut failures))?;
+                }
+                yield record;
+            }
+            self.record_decode_errors(table, failures)?;
+        })
     }
-    
-    async fn get_table_schema(&self, table: &str) -> Result<Vec<(String, String)>> {
-        debug!("MySQL stub: get_table_schema called for table {}", table);
-        Err(ConnectorError::NotImplemented("MySQL adapter get_table_schema".to_string()))
+
+    async fn fetch_records_since(&self, table: &str, columns: Option<&[String]>, column: &str, since: &Value) -> Result<Option<Vec<Value>>> {
+        let select_list = match columns {
+            Some(columns) => columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", "),
+            None => "*".to_string(),
+        };
+        let condition = format!("{} >= ?", quote_identifier(column));
+        let query = format!("SELECT {} FROM {}", select_list, self.row_filters.filtered_table(table, Some(&condition)));
+        debug!("Executing query: {} with {}", query, since);
+
+        let query = sqlx::query(&query);
+        let query = match since {
+            Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(n), _) => query.bind(n),
+                (None, Some(n)) => query.bind(n),
+                _ => query.bind(n.as_f64()),
+            },
+            Value::String(s) => query.bind(s.as_str()),
+            _ => return Ok(None),
+        };
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to fetch records: {}", e)))?;
+
+        self.rows_to_json(table, rows).map(Some)
     }
-    
+
     async fn get_all_tables(&self) -> Result<Vec<String>> {
-        debug!("MySQL stub: get_all_tables called");
-        Err(ConnectorError::NotImplemented("MySQL adapter get_all_tables".to_string()))
+        let query = "SELECT CAST(table_name AS CHAR) AS name FROM information_schema.tables \
+                     WHERE table_schema = DATABASE() AND table_type = 'BASE TABLE' ORDER BY table_name";
+        debug!("Executing query: {}", query);
+
+        let rows = sqlx::query(query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to get tables: {}", e)))?;
+
+        rows.into_iter()
+            .map(|row| row.try_get("name"))
+            .collect::<std::result::Result<Vec<String>, _>>()
+            .map_err(|e| ConnectorError::Database(format!("Failed to extract table names: {}", e)))
     }
-    
+
     async fn get_table_columns(&self, table: &str) -> Result<Vec<(String, String, bool)>> {
-        debug!("MySQL stub: get_table_columns called for table {}", table);
-        Err(ConnectorError::NotImplemented("MySQL adapter get_table_columns".to_string()))
+        let query = "SELECT CAST(column_name AS CHAR) AS name, CAST(column_type AS CHAR) AS type, \
+                     column_key = 'PRI' AS pk FROM information_schema.columns \
+                     WHERE table_schema = DATABASE() AND table_name = ? ORDER BY ordinal_position";
+        debug!("Executing query: {}", query);
+
+        let rows = sqlx::query(query)
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to get table columns: {}", e)))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let name: String = row.try_get("name")
+                .map_err(|e| ConnectorError::Database(format!("Failed to get column name: {}", e)))?;
+
+            let type_: String = row.try_get("type")
+                .map_err(|e| ConnectorError::Database(format!("Failed to get column type: {}", e)))?;
+
+            let pk: i64 = row.try_get("pk")
+                .map_err(|e| ConnectorError::Database(format!("Failed to get primary key flag: {}", e)))?;
+
+            results.push((name, type_, pk == 1));
+        }
+
+        Ok(results)
     }
-    
+
     async fn get_primary_key(&self, table: &str) -> Result<String> {
-        debug!("MySQL stub: get_primary_key called for table {}", table);
-        Err(ConnectorError::NotImplemented("MySQL adapter get_primary_key".to_string()))
+        let query = "SELECT CAST(column_name AS CHAR) AS name FROM information_schema.key_column_usage \
+                     WHERE table_schema = DATABASE() AND table_name = ? AND constraint_name = 'PRIMARY' \
+                     ORDER BY ordinal_position LIMIT 1";
+        debug!("Executing query: {}", query);
+
+        let row = sqlx::query(query)
+            .bind(table)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to get table info: {}", e)))?
+            .ok_or_else(|| ConnectorError::NoPrimaryKey(table.to_string()))?;
+
+        row.try_get("name")
+            .map_err(|e| ConnectorError::Database(format!("Failed to get column name: {}", e)))
+    }
+
+    // SHOW REPLICA STATUS needs MySQL 8.0.22 or MariaDB 10.5, older servers
+    // only know SHOW SLAVE STATUS. A server without replication status isn't
+    // a replica; one whose replication isn't running is infinitely far behind.
+    async fn replication_lag(&self) -> Result<Option<Duration>> {
+        let row = match self.pool.fetch_optional("SHOW REPLICA STATUS").await {
+            Ok(row) => row,
+            Err(_) => self.pool.fetch_optional("SHOW SLAVE STATUS").await
+                .map_err(|e| ConnectorError::Database(format!("Failed to get replication lag: {}", e)))?,
+        };
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let seconds = row.try_get_unchecked::<Option<String>, _>("Seconds_Behind_Source")
+            .or_else(|_| row.try_get_unchecked::<Option<String>, _>("Seconds_Behind_Master"))
+            .map_err(|e| ConnectorError::Database(format!("Failed to get replication lag: {}", e)))?;
+        Ok(Some(seconds.and_then(|s| s.parse().ok()).map_or(Duration::MAX, Duration::from_secs)))
     }
-} 
\ No newline at end of file
+
+    fn take_decode_errors(&self, table: &str) -> BTreeMap<String, usize> {
+        self.decode_errors.lock().unwrap().remove(table).unwrap_or_default()
+    }
+}
+
+// A time of day as HH:MM:SS, with microseconds when there are any
+fn format_time(time: Time) -> String {
+    let mut formatted = format!("{:02}:{:02}:{:02}", time.hour(), time.minute(), time.second());
+    if time.microsecond() != 0 {
+        formatted.push_str(&format!(".{:06}", time.microsecond()));
+    }
+    formatted
+}
+
+// A column name as a quoted MySQL identifier
+fn quote_identifier(column: &str) -> String {
+    format!("`{}`", column.replace('`', "``"))
+}
@@ -1,51 +1,264 @@
-use crate::database::DatabaseAdapter;
-use crate::error::{ConnectorError, Result};
+use crate::database::{DatabaseAdapter, Dialect, RowFilters};
+use crate::error::{redact_credentials, ConnectorError, Result};
+use sqlx::{PgPool, Row, postgres::PgPoolOptions};
+use futures_util::stream::{BoxStream, TryStreamExt};
 use serde_json::Value;
 use async_trait::async_trait;
-use tracing::{info, debug};
+use tracing::{info, debug, instrument};
+use std::time::Duration;
+
+// Schema whose tables are synced
+const SCHEMA: &str = "public";
 
 pub struct PostgresAdapter {
-    connection_string: String,
+    pool: PgPool,
+    large_int_threshold: Option<u64>,
+    row_filters: RowFilters,
 }
 
 impl PostgresAdapter {
     pub async fn new(connection_string: &str) -> Result<Self> {
-        info!("PostgreSQL adapter is currently a stub implementation");
+        // Default pool size, overridden when used by the connector
+        Self::new_with_pool_size(connection_string, 5).await
+    }
+
+    pub async fn new_with_pool_size(connection_string: &str, pool_size: u32) -> Result<Self> {
+        debug!("PostgreSQL adapter initializing with pool size: {}", pool_size);
+
+        let pool = PgPoolOptions::new()
+            .max_connections(pool_size)
+            .connect(connection_string)
+            .await
+            .map_err(|e| ConnectorError::Database(redact_credentials(&format!(
+                "Failed to connect to PostgreSQL database at {}: {}", connection_string, e
+            ))))?;
+
+        info!("Connected to PostgreSQL database with connection pool size {}", pool_size);
+
         Ok(Self {
-            connection_string: connection_string.to_string(),
+            pool,
+            large_int_threshold: None,
+            row_filters: RowFilters::default(),
+        })
+    }
+
+    /// Emit integers whose magnitude exceeds `threshold` as JSON strings so
+    /// consumers parsing numbers as doubles don't silently lose precision
+    pub fn with_large_int_threshold(mut self, threshold: Option<u64>) -> Self {
+        self.large_int_threshold = threshold;
+        self
+    }
+
+    /// Only read the rows of each table meeting its filter
+    pub fn with_row_filters(mut self, row_filters: RowFilters) -> Self {
+        self.row_filters = row_filters.with_dialect(Dialect::Postgres);
+        self
+    }
+
+    // Rows are converted to JSON by Postgres itself with `row_to_json`, which
+    // handles every column type: numerics become numbers, dates and times
+    // ISO 8601 strings, arrays arrays and bytea hex strings
+    #[instrument(name = "database_query", skip_all, fields(table = table))]
+    async fn fetch_json(&self, select: &str, table: &str) -> Result<Vec<Value>> {
+        let query = format!("SELECT row_to_json(t) AS record FROM ({}) t", select);
+        debug!("Executing query: {}", query);
+
+        let rows = sqlx::query(&query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to fetch records of {}: {}", table, e)))?;
+
+        rows.into_iter()
+            .map(|row| row.try_get::<Value, _>("record").map(|record| self.convert_large_ints(record)))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| ConnectorError::Database(format!("Failed to decode records of {}: {}", table, e)))
+    }
+
+    // `fetch_json` yielding the records while Postgres sends them
+    fn stream_json<'a>(&'a self, select: String, table: &'a str) -> BoxStream<'a, Result<Value>> {
+        Box::pin(async_stream::try_stream! {
+            let query = format!("SELECT row_to_json(t) AS record FROM ({}) t", select);
+            debug!("Streaming query: {}", query);
+
+            let mut rows = sqlx::query(&query).fetch(&self.pool);
+            while let Some(row) = rows.try_next().await
+                .map_err(|e| ConnectorError::Database(format!("Failed to fetch records of {}: {}", table, e)))?
+            {
+                let record = row.try_get::<Value, _>("record")
+                    .map_err(|e| ConnectorError::Database(format!("Failed to decode records of {}: {}", table, e)))?;
+                yield self.convert_large_ints(record);
+            }
         })
     }
+
+    fn convert_large_ints(&self, mut record: Value) -> Value {
+        let (Some(threshold), Value::Object(obj)) = (self.large_int_threshold, &mut record) else {
+            return record;
+        };
+        for value in obj.values_mut() {
+            let large = match value {
+                Value::Number(n) => n.as_i64().is_some_and(|n| n.unsigned_abs() > threshold)
+                    || n.as_u64().is_some_and(|n| n > threshold),
+                _ => false,
+            };
+            if large {
+                *value = Value::String(value.to_string());
+            }
+        }
+        record
+    }
+
+    // Columns of the table's primary key constraint, in key order
+    async fn primary_key_columns(&self, table: &str) -> Result<Vec<String>> {
+        let query = "SELECT kcu.column_name::text AS name \
+                     FROM information_schema.table_constraints tc \
+                     JOIN information_schema.key_column_usage kcu \
+                       ON kcu.constraint_schema = tc.constraint_schema \
+                      AND kcu.constraint_name = tc.constraint_name \
+                      AND kcu.table_name = tc.table_name \
+                     WHERE tc.constraint_type = 'PRIMARY KEY' AND tc.table_schema = $1 AND tc.table_name = $2 \
+                     ORDER BY kcu.ordinal_position";
+        debug!("Executing query: {}", query);
+
+        let rows = sqlx::query(query)
+            .bind(SCHEMA)
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to get table info: {}", e)))?;
+
+        rows.into_iter()
+            .map(|row| row.try_get("name"))
+            .collect::<std::result::Result<Vec<String>, _>>()
+            .map_err(|e| ConnectorError::Database(format!("Failed to get column name: {}", e)))
+    }
 }
 
 #[async_trait]
 impl DatabaseAdapter for PostgresAdapter {
     async fn fetch_all_records(&self, table: &str) -> Result<Vec<Value>> {
-        debug!("PostgreSQL stub: fetch_all_records called for table {}", table);
-        Err(ConnectorError::NotImplemented("PostgreSQL adapter fetch_all_records".to_string()))
+        self.fetch_json(&format!("SELECT * FROM {}", self.row_filters.filtered_table(table, None)), table).await
     }
-    
-    async fn fetch_record(&self, table: &str, id: &str) -> Result<Value> {
-        debug!("PostgreSQL stub: fetch_record called for table {}, id {}", table, id);
-        Err(ConnectorError::NotImplemented("PostgreSQL adapter fetch_record".to_string()))
+
+    async fn fetch_records(&self, table: &str, columns: Option<&[String]>) -> Result<Vec<Value>> {
+        let Some(columns) = columns else {
+            return self.fetch_all_records(table).await;
+        };
+        let select_list = columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", ");
+        self.fetch_json(&format!("SELECT {} FROM {}", select_list, self.row_filters.filtered_table(table, None)), table).await
+    }
+
+    async fn fetch_records_ordered(&self, table: &str, columns: Option<&[String]>, order_by: &str) -> Result<Option<Vec<Value>>> {
+        let select_list = match columns {
+            Some(columns) => columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", "),
+            None => "*".to_string(),
+        };
+        // The scan over the subquery in `fetch_json` keeps its order. Text
+        // keys sorted by a collation other than "C" are found out of order
+        // by the connector, which then diffs them by id.
+        let select = format!("SELECT {} FROM {} ORDER BY {}", select_list, self.row_filters.filtered_table(table, None), quote_identifier(order_by));
+        self.fetch_json(&select, table).await.map(Some)
     }
-    
-    async fn get_table_schema(&self, table: &str) -> Result<Vec<(String, String)>> {
-        debug!("PostgreSQL stub: get_table_schema called for table {}", table);
-        Err(ConnectorError::NotImplemented("PostgreSQL adapter get_table_schema".to_string()))
+
+    fn fetch_records_stream<'a>(&'a self, table: &'a str, columns: Option<&'a [String]>) -> BoxStream<'a, Result<Value>> {
+        let select_list = match columns {
+            Some(columns) => columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", "),
+            None => "*".to_string(),
+        };
+        self.stream_json(format!("SELECT {} FROM {}", select_list, self.row_filters.filtered_table(table, None)), table)
+    }
+
+    async fn fetch_records_since(&self, table: &str, columns: Option<&[String]>, column: &str, since: &Value) -> Result<Option<Vec<Value>>> {
+        // A bound string would be text, which doesn't compare with dates and
+        // times; a literal takes the type of the column
+        let since = match since {
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => format!("'{}'", s.replace('\'', "''")),
+            _ => return Ok(None),
+        };
+        let select_list = match columns {
+            Some(columns) => columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", "),
+            None => "*".to_string(),
+        };
+        let condition = format!("{} >= {}", quote_identifier(column), since);
+        let select = format!("SELECT {} FROM {}", select_list, self.row_filters.filtered_table(table, Some(&condition)));
+        self.fetch_json(&select, table).await.map(Some)
     }
-    
+
     async fn get_all_tables(&self) -> Result<Vec<String>> {
-        debug!("PostgreSQL stub: get_all_tables called");
-        Err(ConnectorError::NotImplemented("PostgreSQL adapter get_all_tables".to_string()))
+        let query = "SELECT table_name::text AS name FROM information_schema.tables \
+                     WHERE table_schema = $1 AND table_type = 'BASE TABLE' ORDER BY table_name";
+        debug!("Executing query: {}", query);
+
+        let rows = sqlx::query(query)
+            .bind(SCHEMA)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to get tables: {}", e)))?;
+
+        rows.into_iter()
+            .map(|row| row.try_get("name"))
+            .collect::<std::result::Result<Vec<String>, _>>()
+            .map_err(|e| ConnectorError::Database(format!("Failed to extract table names: {}", e)))
     }
-    
+
     async fn get_table_columns(&self, table: &str) -> Result<Vec<(String, String, bool)>> {
-        debug!("PostgreSQL stub: get_table_columns called for table {}", table);
-        Err(ConnectorError::NotImplemented("PostgreSQL adapter get_table_columns".to_string()))
+        let primary_key = self.primary_key_columns(table).await?;
+        let query = "SELECT column_name::text AS name, data_type::text AS type FROM information_schema.columns \
+                     WHERE table_schema = $1 AND table_name = $2 ORDER BY ordinal_position";
+        debug!("Executing query: {}", query);
+
+        let rows = sqlx::query(query)
+            .bind(SCHEMA)
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to get table columns: {}", e)))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let name: String = row.try_get("name")
+                .map_err(|e| ConnectorError::Database(format!("Failed to get column name: {}", e)))?;
+
+            let type_: String = row.try_get("type")
+                .map_err(|e| ConnectorError::Database(format!("Failed to get column type: {}", e)))?;
+
+            let pk = primary_key.contains(&name);
+            results.push((name, type_, pk));
+        }
+
+        Ok(results)
     }
-    
+
     async fn get_primary_key(&self, table: &str) -> Result<String> {
-        debug!("PostgreSQL stub: get_primary_key called for table {}", table);
-        Err(ConnectorError::NotImplemented("PostgreSQL adapter get_primary_key".to_string()))
+        self.primary_key_columns(table).await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ConnectorError::NoPrimaryKey(table.to_string()))
     }
-} 
\ No newline at end of file
+
+    // A standby that replayed everything it received is caught up, however
+    // long ago the last transaction was. One that hasn't replayed any
+    // transaction yet is infinitely far behind.
+    async fn replication_lag(&self) -> Result<Option<Duration>> {
+        let query = "SELECT CASE \
+                       WHEN NOT pg_is_in_recovery() THEN NULL \
+                       WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0 \
+                       ELSE COALESCE(EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp()), 'Infinity') \
+                     END::float8 AS lag";
+        debug!("Executing query: {}", query);
+
+        let lag: Option<f64> = sqlx::query(query)
+            .fetch_one(&self.pool)
+            .await
+            .and_then(|row| row.try_get("lag"))
+            .map_err(|e| ConnectorError::Database(format!("Failed to get replication lag: {}", e)))?;
+
+        Ok(lag.map(|secs| Duration::try_from_secs_f64(secs.max(0.0)).unwrap_or(Duration::MAX)))
+    }
+}
+
+// A column name as a quoted SQL identifier
+fn quote_identifier(column: &str) -> String {
+    format!("\"{}\"", column.replace('"', "\"\""))
+}
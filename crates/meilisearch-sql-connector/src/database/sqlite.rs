@@ -1,92 +1,42 @@
-use crate::database::DatabaseAdapter;
-use crate::error::{ConnectorError, Result};
-use sqlx::{Column, Row, SqlitePool, pool::PoolOptions, sqlite::SqliteRow};
+use crate::database::{format_decode_errors, ColumnInfo, DatabaseAdapter, Dialect, RowFilters};
+use crate::error::{redact_credentials, ConnectorError, Result};
+use sqlx::{Column, Row, SqlitePool, pool::PoolOptions, sqlite::{SqliteConnectOptions, SqliteRow}};
+use futures_util::stream::{BoxStream, TryStreamExt};
 use serde_json::{Value, Map};
-use tracing::{info, debug, warn};
+use tracing::{info, debug, instrument};
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
 
 pub struct SqliteAdapter {
     pool: SqlitePool,
     #[allow(dead_code)]
     path: String,
+    large_int_threshold: Option<u64>,
+    strict_decoding: bool,
+    row_filters: RowFilters,
+    // Values that couldn't be decoded since the last `take_decode_errors`, by table and column
+    decode_errors: Mutex<HashMap<String, BTreeMap<String, usize>>>,
 }
 
 impl SqliteAdapter {
     pub async fn new(path: &str) -> Result<Self> {
-        // For debug purposes
-        debug!("SQLite adapter initializing with path: {}", path);
-        
-        // Normalize path - handle double slashes at the beginning
-        let normalized_path = if path.starts_with("//") {
-            // Convert //Users/... to /Users/... (absolute path)
-            format!("/{}", path.trim_start_matches("//"))
-        } else {
-            path.to_string()
-        };
-        
-        debug!("Normalized path: {}", normalized_path);
-        
-        // Verify path exists for file-based databases
-        if normalized_path != ":memory:" {
-            let file_path = Path::new(&normalized_path);
-            if !file_path.exists() && file_path.parent().map_or(false, |p| p.exists()) {
-                debug!("SQLite database file does not exist but will be created: {}", normalized_path);
-            } else if !file_path.exists() {
-                debug!("SQLite database path does not exist: {}", normalized_path);
-                // Check if the path without sqlite: prefix exists
-                if normalized_path.starts_with("sqlite:") {
-                    let stripped = normalized_path.trim_start_matches("sqlite:");
-                    if Path::new(stripped).exists() {
-                        debug!("Path exists without sqlite: prefix: {}", stripped);
-                    }
-                }
-            }
-        }
-        
-        // Create connection string - handle sqlite:// correctly
-        let connection_string = if normalized_path == ":memory:" {
-            // For in-memory database
-            "sqlite::memory:".to_string()
-        } else if normalized_path.starts_with("sqlite://") {
-            // Already in correct format for SQLx
-            normalized_path.clone()
-        } else if normalized_path.starts_with("sqlite:") {
-            // Already has the prefix
-            normalized_path.clone()
-        } else if normalized_path.starts_with('/') {
-            // Absolute path
-            format!("sqlite://{}", normalized_path)
-        } else if normalized_path.contains(':') {
-            // Path already has a protocol or drive letter (Windows)
-            format!("sqlite:{}", normalized_path)
-        } else {
-            // Relative path - ensure it has proper format
-            format!("sqlite:./{}", normalized_path)
-        };
-        
-        debug!("SQLite connection string: {}", connection_string);
-        eprintln!("[SqliteAdapter] Connecting with string: {}", connection_string);
-        if normalized_path != ":memory:" {
-            eprintln!("[SqliteAdapter] File exists at {}: {}", normalized_path, std::path::Path::new(&normalized_path).exists());
-        }
-        
-        // Set up connection pool with default pool size (will be overridden when used by connector)
-        let pool = PoolOptions::new()
-            .max_connections(5)
-            .connect(&connection_string)
-            .await
-            .map_err(|e| ConnectorError::Database(format!("Failed to connect to SQLite database at {}: {}", normalized_path, e)))?;
-        
-        info!("Connected to SQLite database at {}", normalized_path);
-        
-        Ok(Self {
-            pool,
-            path: normalized_path,
-        })
+        // Default pool size, overridden when used by the connector
+        Self::connect(path, 5, false).await
     }
 
-    // Add method to create with specific pool size
     pub async fn new_with_pool_size(path: &str, pool_size: u32) -> Result<Self> {
+        Self::connect(path, pool_size, false).await
+    }
+
+    /// Like `new_with_pool_size`, creating an empty database file when
+    /// `create_if_missing` is set and the file doesn't exist
+    pub async fn new_with_options(path: &str, pool_size: u32, create_if_missing: bool) -> Result<Self> {
+        Self::connect(path, pool_size, create_if_missing).await
+    }
+
+    async fn connect(path: &str, pool_size: u32, create_if_missing: bool) -> Result<Self> {
         // For debug purposes
         debug!("SQLite adapter initializing with path: {} and pool size: {}", path, pool_size);
         
@@ -103,8 +53,8 @@ impl SqliteAdapter {
         // Verify path exists for file-based databases
         if normalized_path != ":memory:" {
             let file_path = Path::new(&normalized_path);
-            if !file_path.exists() && file_path.parent().map_or(false, |p| p.exists()) {
-                debug!("SQLite database file does not exist but will be created: {}", normalized_path);
+            if !file_path.exists() && create_if_missing {
+                info!("SQLite database file does not exist and will be created: {}", normalized_path);
             } else if !file_path.exists() {
                 debug!("SQLite database path does not exist: {}", normalized_path);
                 // Check if the path without sqlite: prefix exists
@@ -138,28 +88,102 @@ impl SqliteAdapter {
             format!("sqlite:./{}", normalized_path)
         };
         
-        debug!("SQLite connection string: {}", connection_string);
-        eprintln!("[SqliteAdapter] Connecting with string: {}", connection_string);
+        debug!("SQLite connection string: {}", redact_credentials(&connection_string));
         if normalized_path != ":memory:" {
-            eprintln!("[SqliteAdapter] File exists at {}: {}", normalized_path, std::path::Path::new(&normalized_path).exists());
+            debug!("SQLite file {} exists: {}", normalized_path, std::path::Path::new(&normalized_path).exists());
         }
         
+        let connect_options = SqliteConnectOptions::from_str(&connection_string)
+            .map_err(|e| ConnectorError::Database(redact_credentials(&format!(
+                "Invalid SQLite connection string {}: {}", connection_string, e
+            ))))?
+            .create_if_missing(create_if_missing);
+        
         // Set up connection pool with specified pool size
         let pool = PoolOptions::new()
             .max_connections(pool_size)
-            .connect(&connection_string)
+            .connect_with(connect_options)
             .await
-            .map_err(|e| ConnectorError::Database(format!("Failed to connect to SQLite database at {}: {}", normalized_path, e)))?;
+            .map_err(|e| {
+                if normalized_path != ":memory:" && !create_if_missing && !Path::new(&normalized_path).exists() {
+                    ConnectorError::Database(redact_credentials(&format!(
+                        "SQLite database {} does not exist, set create_if_missing = true to create it: {}", normalized_path, e
+                    )))
+                } else {
+                    ConnectorError::Database(redact_credentials(&format!(
+                        "Failed to connect to SQLite database at {}: {}", normalized_path, e
+                    )))
+                }
+            })?;
         
         info!("Connected to SQLite database at {} with connection pool size {}", normalized_path, pool_size);
         
         Ok(Self {
             pool,
             path: normalized_path,
+            large_int_threshold: None,
+            strict_decoding: false,
+            row_filters: RowFilters::default(),
+            decode_errors: Mutex::new(HashMap::new()),
         })
     }
     
-    fn row_to_json(&self, row: SqliteRow) -> Value {
+    /// Emit integers whose magnitude exceeds `threshold` as JSON strings so
+    /// consumers parsing numbers as doubles don't silently lose precision
+    pub fn with_large_int_threshold(mut self, threshold: Option<u64>) -> Self {
+        self.large_int_threshold = threshold;
+        self
+    }
+
+    /// Fail fetches with a row whose values can't all be decoded, instead of
+    /// indexing them as null
+    pub fn with_strict_decoding(mut self, strict: bool) -> Self {
+        self.strict_decoding = strict;
+        self
+    }
+
+    /// Only read the rows of each table meeting its filter
+    pub fn with_row_filters(mut self, row_filters: RowFilters) -> Self {
+        self.row_filters = row_filters.with_dialect(Dialect::Sqlite);
+        self
+    }
+
+    fn integer_to_json(&self, val: i64) -> Value {
+        match self.large_int_threshold {
+            Some(threshold) if val.unsigned_abs() > threshold => Value::String(val.to_string()),
+            _ => Value::Number(val.into()),
+        }
+    }
+
+    /// Convert the rows of `table`, counting the values that couldn't be
+    /// decoded (or failing in strict mode)
+    fn rows_to_json(&self, table: &str, rows: Vec<SqliteRow>) -> Result<Vec<Value>> {
+        let mut failures = BTreeMap::new();
+        let records = rows.into_iter().map(|row| self.row_to_json(row, &mut failures)).collect();
+        self.record_decode_errors(table, failures)?;
+        Ok(records)
+    }
+
+    /// Count the values of `table` that couldn't be decoded for
+    /// `take_decode_errors`, or fail in strict mode
+    fn record_decode_errors(&self, table: &str, failures: BTreeMap<String, usize>) -> Result<()> {
+        if failures.is_empty() {
+            return Ok(());
+        }
+        if self.strict_decoding {
+            return Err(ConnectorError::Database(format!(
+                "Could not decode values of table {}: {}", table, format_decode_errors(&failures)
+            )));
+        }
+        let mut decode_errors = self.decode_errors.lock().unwrap();
+        let counts = decode_errors.entry(table.to_string()).or_default();
+        for (column, count) in failures {
+            *counts.entry(column).or_default() += count;
+        }
+        Ok(())
+    }
+
+    fn row_to_json(&self, row: SqliteRow, failures: &mut BTreeMap<String, usize>) -> Value {
         let mut map = Map::new();
         
         // Get column names
@@ -168,21 +192,14 @@ impl SqliteAdapter {
             
             // First try to get the value as different types
             let value = if let Ok(val) = row.try_get::<i64, _>(i) {
-                // Special handling for primary key values - ensure they're never null
-                if column_name == "id" {
-                    if val == 0 {
-                        debug!("Found id with value 0, converting to proper number");
-                    }
-                    // Always ensure the ID is a proper number
-                    Value::Number(val.into())
-                } else {
-                    Value::Number(val.into())
-                }
+                self.integer_to_json(val)
             } else if let Ok(val) = row.try_get::<f64, _>(i) {
                 // Convert f64 to serde_json::Number
                 if let Some(num) = serde_json::Number::from_f64(val) {
                     Value::Number(num)
                 } else {
+                    // JSON has no infinity or NaN
+                    *failures.entry(column_name.to_string()).or_default() += 1;
                     Value::Null
                 }
             } else if let Ok(val) = row.try_get::<String, _>(i) {
@@ -192,17 +209,13 @@ impl SqliteAdapter {
             } else if let Ok(val) = row.try_get::<Vec<u8>, _>(i) {
                 Value::String(format!("BLOB({})", val.len()))
             } else if row.try_get::<Option<String>, _>(i).is_ok() {
-                // Column is null
-                if column_name == "id" {
-                    // For ID columns, replace null with 0 to avoid issues
-                    debug!("Found null id, using 0 instead");
-                    Value::Number(0.into())
-                } else {
-                    Value::Null
-                }
+                // Column is null. A null primary key stays null so the sync
+                // skips the row rather than indexing it as id 0.
+                Value::Null
             } else {
                 // Default to null if we can't determine the type
-                warn!("Could not determine type of column {}", column_name);
+                debug!("Could not determine type of column {}", column_name);
+                *failures.entry(column_name.to_string()).or_default() += 1;
                 if column_name == "id" {
                     // For ID columns, use 0 as a fallback
                     debug!("Using fallback 0 for id with undetermined type");
@@ -221,8 +234,9 @@ impl SqliteAdapter {
 
 #[async_trait::async_trait]
 impl DatabaseAdapter for SqliteAdapter {
+    #[instrument(name = "database_query", skip_all, fields(table = table))]
     async fn fetch_all_records(&self, table: &str) -> Result<Vec<Value>> {
-        let query = format!("SELECT * FROM {}", table);
+        let query = format!("SELECT * FROM {}", self.row_filters.filtered_table(table, None));
         debug!("Executing query: {}", query);
         
         let rows = sqlx::query(&query)
@@ -230,11 +244,80 @@ impl DatabaseAdapter for SqliteAdapter {
             .await
             .map_err(|e| ConnectorError::Database(format!("Failed to fetch records: {}", e)))?;
         
-        let results = rows.into_iter()
-            .map(|row| self.row_to_json(row))
-            .collect();
-        
-        Ok(results)
+        self.rows_to_json(table, rows)
+    }
+
+    #[instrument(name = "database_query", skip_all, fields(table = table))]
+    async fn fetch_records(&self, table: &str, columns: Option<&[String]>) -> Result<Vec<Value>> {
+        let Some(columns) = columns else {
+            return self.fetch_all_records(table).await;
+        };
+        let query = format!("SELECT {} FROM {}", select_list(Some(columns)), self.row_filters.filtered_table(table, None));
+        debug!("Executing query: {}", query);
+
+        let rows = sqlx::query(&query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to fetch records: {}", e)))?;
+
+        self.rows_to_json(table, rows)
+    }
+
+    fn fetch_records_stream<'a>(&'a self, table: &'a str, columns: Option<&'a [String]>) -> BoxStream<'a, Result<Value>> {
+        Box::pin(async_stream::try_stream! {
+            let query = format!("SELECT {} FROM {}", select_list(columns), self.row_filters.filtered_table(table, None));
+            debug!("Streaming query: {}", query);
+
+            let mut rows = sqlx::query(&query).fetch(&self.pool);
+            let mut failures = BTreeMap::new();
+            while let Some(row) = rows.try_next().await
+                .map_err(|e| ConnectorError::Database(format!("Failed to fetch records: {}", e)))?
+            {
+                let record = self.row_to_json(row, &mut failures);
+                // Strict mode fails on the first row that can't be decoded
+                if self.strict_decoding {
+                    self.record_decode_errors(table, std::mem::take(&mut failures))?;
+                }
+                yield record;
+            }
+            self.record_decode_errors(table, failures)?;
+        })
+    }
+
+    #[instrument(name = "database_query", skip_all, fields(table = table))]
+    async fn fetch_records_ordered(&self, table: &str, columns: Option<&[String]>, order_by: &str) -> Result<Option<Vec<Value>>> {
+        let query = format!("SELECT {} FROM {} ORDER BY {}", select_list(columns), self.row_filters.filtered_table(table, None), quote_identifier(order_by));
+        debug!("Executing query: {}", query);
+
+        let rows = sqlx::query(&query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to fetch records: {}", e)))?;
+
+        self.rows_to_json(table, rows).map(Some)
+    }
+
+    #[instrument(name = "database_query", skip_all, fields(table = table))]
+    async fn fetch_records_since(&self, table: &str, columns: Option<&[String]>, column: &str, since: &Value) -> Result<Option<Vec<Value>>> {
+        let condition = format!("{} >= ?", quote_identifier(column));
+        let query = format!("SELECT {} FROM {}", select_list(columns), self.row_filters.filtered_table(table, Some(&condition)));
+        debug!("Executing query: {} with {}", query, since);
+
+        let query = sqlx::query(&query);
+        let query = match since {
+            Value::Number(n) => match n.as_i64() {
+                Some(n) => query.bind(n),
+                None => query.bind(n.as_f64()),
+            },
+            Value::String(s) => query.bind(s.as_str()),
+            _ => return Ok(None),
+        };
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to fetch records: {}", e)))?;
+
+        self.rows_to_json(table, rows).map(Some)
     }
 
     async fn get_all_tables(&self) -> Result<Vec<String>> {
@@ -254,8 +337,23 @@ impl DatabaseAdapter for SqliteAdapter {
         Ok(results)
     }
 
+    async fn get_all_views(&self) -> Result<Vec<String>> {
+        let query = "SELECT name FROM sqlite_master WHERE type='view'";
+        debug!("Executing query: {}", query);
+        
+        let rows = sqlx::query(query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to get views: {}", e)))?;
+        
+        rows.into_iter()
+            .map(|row| row.try_get("name"))
+            .collect::<std::result::Result<Vec<String>, _>>()
+            .map_err(|e| ConnectorError::Database(format!("Failed to extract view names: {}", e)))
+    }
+
     async fn get_table_columns(&self, table: &str) -> Result<Vec<(String, String, bool)>> {
-        let query = format!("PRAGMA table_info({})", table);
+        let query = format!("PRAGMA table_info({})", quote_identifier(table));
         debug!("Executing query: {}", query);
         
         let rows = sqlx::query(&query)
@@ -280,8 +378,69 @@ impl DatabaseAdapter for SqliteAdapter {
         Ok(results)
     }
 
+    async fn get_column_details(&self, table: &str) -> Result<Vec<ColumnInfo>> {
+        let query = format!("PRAGMA table_info({})", quote_identifier(table));
+        debug!("Executing query: {}", query);
+        
+        let rows = sqlx::query(&query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to get table columns: {}", e)))?;
+        
+        rows.into_iter()
+            .map(|row| {
+                let notnull: i64 = row.try_get("notnull")?;
+                let pk: i64 = row.try_get("pk")?;
+                Ok(ColumnInfo {
+                    name: row.try_get("name")?,
+                    type_: row.try_get("type")?,
+                    primary_key: pk == 1,
+                    nullable: Some(notnull == 0),
+                })
+            })
+            .collect::<std::result::Result<Vec<_>, sqlx::Error>>()
+            .map_err(|e| ConnectorError::Database(format!("Failed to read column details: {}", e)))
+    }
+
+    async fn count_records(&self, table: &str) -> Result<usize> {
+        let query = format!("SELECT COUNT(*) AS count FROM {}", self.row_filters.filtered_table(table, None));
+        debug!("Executing query: {}", query);
+        
+        let row = sqlx::query(&query)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to count records: {}", e)))?;
+        let count: i64 = row.try_get("count")
+            .map_err(|e| ConnectorError::Database(format!("Failed to read record count: {}", e)))?;
+        Ok(count as usize)
+    }
+
+    async fn fetch_sample_records(&self, table: &str, limit: usize) -> Result<Vec<Value>> {
+        let query = format!("SELECT * FROM {} LIMIT {}", self.row_filters.filtered_table(table, None), limit);
+        debug!("Executing query: {}", query);
+        
+        let rows = sqlx::query(&query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to fetch records: {}", e)))?;
+        
+        self.rows_to_json(table, rows)
+    }
+
+    async fn fetch_random_records(&self, table: &str, limit: usize) -> Result<Vec<Value>> {
+        let query = format!("SELECT * FROM {} ORDER BY RANDOM() LIMIT {}", self.row_filters.filtered_table(table, None), limit);
+        debug!("Executing query: {}", query);
+        
+        let rows = sqlx::query(&query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ConnectorError::Database(format!("Failed to fetch records: {}", e)))?;
+        
+        self.rows_to_json(table, rows)
+    }
+
     async fn get_primary_key(&self, table: &str) -> Result<String> {
-        let query = format!("PRAGMA table_info({})", table);
+        let query = format!("PRAGMA table_info({})", quote_identifier(table));
         debug!("Executing query: {}", query);
         
         let rows = sqlx::query(&query)
@@ -303,4 +462,21 @@ impl DatabaseAdapter for SqliteAdapter {
         
         Err(ConnectorError::NoPrimaryKey(table.to_string()))
     }
+
+    fn take_decode_errors(&self, table: &str) -> BTreeMap<String, usize> {
+        self.decode_errors.lock().unwrap().remove(table).unwrap_or_default()
+    }
+}
+
+// A column name as a quoted SQL identifier
+fn quote_identifier(column: &str) -> String {
+    format!("\"{}\"", column.replace('"', "\"\""))
+}
+
+// Only read the listed columns, so large unindexed ones never leave the disk
+fn select_list(columns: Option<&[String]>) -> String {
+    match columns {
+        Some(columns) => columns.iter().map(|column| quote_identifier(column)).collect::<Vec<_>>().join(", "),
+        None => "*".to_string(),
+    }
 }
@@ -0,0 +1,48 @@
+use crate::error::{ConnectorError, Result};
+use serde_json::{json, Value};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Newline-delimited JSON file collecting the documents Meilisearch refused,
+/// each with the table and index it was meant for and Meilisearch's reason.
+///
+/// Every `append` is a single write to a file opened for appending, so syncs
+/// of different tables can share the file without interleaving their lines.
+/// The write runs on the blocking pool, off the async worker threads.
+#[derive(Debug, Clone)]
+pub struct DeadLetterFile {
+    path: PathBuf,
+}
+
+impl DeadLetterFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Append one line per rejected document, creating the file if needed
+    pub async fn append(&self, table: &str, index_name: &str, rejected: &[(Value, String)]) -> Result<()> {
+        let rejected_at = chrono::Utc::now().to_rfc3339();
+        let mut buffer = Vec::new();
+        for (document, error) in rejected {
+            let line = json!({
+                "table": table,
+                "index": index_name,
+                "rejected_at": rejected_at,
+                "error": error,
+                "document": document,
+            });
+            // Writing a `Value` to a `Vec` can't fail
+            serde_json::to_writer(&mut buffer, &line).unwrap_or_default();
+            buffer.push(b'\n');
+        }
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            file.write_all(&buffer)?;
+            Ok(())
+        })
+            .await
+            .map_err(|e| ConnectorError::Io(format!("Dead letter write task failed: {}", e)))?
+    }
+}
@@ -10,20 +10,27 @@
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
-//!     let config = Config::load("config.toml")?;
-//!     let connector = Connector::new(config)?;
+//!     let config = Config::from_file("config.toml")?;
+//!     let connector = Connector::new(config).await?;
 //!     connector.start().await?;
 //!     Ok(())
 //! }
 //! ```
 
+pub mod breaker;
 pub mod cli;
 pub mod config;
 pub mod connector;
 pub mod database;
+pub mod dead_letter;
 pub mod error;
 pub mod logging;
 pub mod meilisearch;
+pub mod plan;
+pub mod queue;
+pub mod report;
+pub mod transform;
+pub mod watch;
 
 #[cfg(feature = "test")]
 pub mod common;
@@ -31,3 +38,4 @@ pub mod common;
 pub use config::Config;
 pub use connector::Connector;
 pub use error::ConnectorError;
+pub use report::SyncReport;
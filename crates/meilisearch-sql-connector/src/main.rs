@@ -1,32 +1,53 @@
+mod breaker;
 mod cli;
 mod config;
 mod connector;
 mod database;
+mod dead_letter;
 mod error;
 mod meilisearch;
+mod plan;
+mod queue;
+mod report;
+#[cfg(feature = "otel")]
+mod telemetry;
+mod transform;
+mod watch;
 
 use anyhow::Result;
 use clap::Parser;
 use cli::{Cli, Commands};
 use colored::Colorize;
 use std::fs;
+use std::path::Path;
 use std::sync::Arc;
 use tokio::signal;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{EnvFilter, fmt};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Initialize logging
-    // Use RUST_LOG environment variable or default to info
+    // Use RUST_LOG environment variable or default to info, or to warn
+    // under `run --watch` so the logs don't bury the status lines
+    let watching = matches!(cli.command, Some(Commands::Run { watch: true, .. }));
     let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("info"));
-
-    fmt()
-        .with_env_filter(env_filter)
-        .with_target(false)
-        .init();
+        .unwrap_or_else(|_| EnvFilter::new(if watching { "warn" } else { "info" }));
 
-    let cli = Cli::parse();
+    let subscriber = tracing_subscriber::registry()
+        .with(env_filter)
+        .with(fmt::layer().with_target(false));
+    // Spans also go to an OpenTelemetry collector when one is configured,
+    // until the guard flushes them at exit
+    #[cfg(feature = "otel")]
+    let (subscriber, _telemetry) = {
+        let (layer, guard) = telemetry::layer()?.unzip();
+        (subscriber.with(layer), guard)
+    };
+    subscriber.init();
 
     if cli.command.is_none() {
         cli::print_banner();
@@ -35,55 +56,388 @@ async fn main() -> Result<()> {
         // Cli::command().print_help()?;
     } else if let Some(command) = cli.command {
         match command {
-            Commands::Run { config } => {
-                let config = config::Config::from_file(&config)?;
-                let connector = Arc::new(connector::Connector::new(config).await?);
-                
-                // Clone for signal handler
-                let connector_for_signal = connector.clone();
-                
-                // Setup Ctrl+C handler
-                tokio::spawn(async move {
-                    match signal::ctrl_c().await {
-                        Ok(()) => {
-                            println!("Ctrl+C received, shutting down...");
-                            let _ = connector_for_signal.stop().await;
-                        },
-                        Err(err) => {
-                            eprintln!("Error setting up Ctrl+C handler: {}", err);
-                        },
-                    }
-                });
-                
-                connector.start().await?;
+            Commands::Run { config, profile, watch, allow_mass_delete, overrides } => {
+                // Command line flags win over the file and the environment, on reloads too
+                let load_config = move || -> Result<config::Config> {
+                    let mut config = config::Config::from_file(&config)?;
+                    overrides.apply(&mut config);
+                    Ok(config)
+                };
+                let connector = connector::Connector::new(load_config()?).await?
+                    .with_profile(profile)
+                    .with_watch(watch)
+                    .with_allow_mass_delete(allow_mass_delete);
+                run_connector(connector, load_config).await?;
             }
-            Commands::Generate { database_url, meilisearch_host, meilisearch_key, output, poll_interval } => {
+            Commands::Generate { database_url, meilisearch_host, meilisearch_key, output, poll_interval, include_views, rules } => {
                 println!("{}", "Generating configuration...".green());
-                let mut config = config::Config::generate_from_database_url(
-                    &database_url,
-                    &meilisearch_host,
-                    poll_interval,
-                ).await?;
-                
-                // Set the API key if provided
-                if let Some(key) = meilisearch_key {
-                    config.meilisearch.api_key = Some(key);
-                }
-                
-                fs::write(&output, config.to_toml()?)?;
+                generate_config(&database_url, &meilisearch_host, meilisearch_key, &output, poll_interval, include_views, rules.as_deref()).await?;
                 println!(
                     "{} Configuration generated successfully at {}",
                     "✓".green(),
                     output.display()
                 );
             }
+            Commands::Init { database_url, meilisearch_host, meilisearch_key, output, poll_interval, include_views, rules, run } => {
+                println!("{}", "Generating configuration...".green());
+                generate_config(&database_url, &meilisearch_host, meilisearch_key, &output, poll_interval, include_views, rules.as_deref()).await?;
+                println!("{} Configuration generated at {}", "✓".green(), output.display());
+
+                // Re-read the file we just wrote so we validate exactly what the user will run
+                println!("{}", "Validating configuration against the database...".green());
+                let mut config = config::Config::from_file(&output)?;
+                let db_adapter = database::create_db_adapter(
+                    &config.database.adapter_url()?,
+                    &database::AdapterOptions::from(&config.database),
+                ).await?;
+                config.database.verify_tables(db_adapter.as_ref().as_ref()).await?;
+                println!("{} Configuration is valid", "✓".green());
+
+                println!("\n{}", "Indices to be created:".bold());
+                for table in &config.database.tables {
+                    println!(
+                        "  {} -> {} (primary key: {}, {} fields)",
+                        table.name,
+                        table.index_name.as_deref().unwrap_or(&table.name).cyan(),
+                        table.primary_key,
+                        table.fields_to_index.len()
+                    );
+                }
+                println!();
+
+                if run {
+                    let connector = connector::Connector::new(config).await?;
+                    run_connector(connector, move || Ok(config::Config::from_file(&output)?)).await?;
+                } else {
+                    println!("Start syncing with: meilisearch-sql-connector run --config {}", output.display());
+                }
+            }
+            Commands::SyncOnce { config, dry_run: true, output, summary_only, overrides, .. } => {
+                let mut config = config::Config::from_file(&config)?;
+                overrides.apply(&mut config);
+                let connector = connector::Connector::new(config).await?;
+                let (plan, run) = connector.plan(summary_only).await?;
+                for report in &run.reports {
+                    println!(
+                        "{} {} -> {}: +{} -{} ({} changed), {} documents",
+                        "~".yellow(), report.table, report.index.cyan(), report.documents_added, report.documents_deleted,
+                        report.documents_changed, report.database_documents,
+                    );
+                }
+                print_sync_errors(&run);
+                if let Some(output) = output {
+                    fs::write(&output, serde_json::to_string_pretty(&plan)?)?;
+                    println!("{} Changes written to {}", "✓".green(), output.display());
+                }
+                if plan.is_empty() {
+                    println!("No changes to make");
+                }
+                println!("Dry run of {} tables, nothing was written to Meilisearch", run.reports.len());
+                if !run.is_success() {
+                    anyhow::bail!("{} table(s) failed to sync", run.errors.len());
+                }
+            }
+            Commands::SyncOnce { config, sort_by, overrides, .. } => {
+                let mut config = config::Config::from_file(&config)?;
+                overrides.apply(&mut config);
+                let connector = connector::Connector::new(config).await?;
+                connector.setup().await?;
+                let mut run = connector.sync_all_once().await;
+                run.sort_reports(sort_by);
+                // Failed tables lead when looking for errors
+                if sort_by == report::ReportOrder::Errors {
+                    print_sync_errors(&run);
+                }
+                for report in &run.reports {
+                    println!(
+                        "{} {} -> {}: +{} -{}, {} documents, {} errors, {}ms",
+                        "✓".green(), report.table, report.index.cyan(), report.documents_added, report.documents_deleted,
+                        report.database_documents, report.error_count(), report.timings.total.as_millis(),
+                    );
+                }
+                if sort_by != report::ReportOrder::Errors {
+                    print_sync_errors(&run);
+                }
+                let (added, deleted) = run.document_totals();
+                println!(
+                    "Synced {} tables in {:.1}s: {} documents added, {} deleted",
+                    run.reports.len(), run.duration.as_secs_f64(), added, deleted,
+                );
+                if !run.is_success() {
+                    anyhow::bail!("{} table(s) failed to sync", run.errors.len());
+                }
+            }
+            Commands::Plan { config, output } => {
+                let config = config::Config::from_file(&config)?;
+                let connector = connector::Connector::new(config).await?;
+                let (plan, run) = connector.plan(false).await?;
+                print_sync_errors(&run);
+                if !run.is_success() {
+                    anyhow::bail!("{} table(s) failed to sync, no plan was written", run.errors.len());
+                }
+                for index in &plan.indexes {
+                    println!(
+                        "{} {}: +{} ~{} -{}",
+                        "~".yellow(), index.index.cyan(), index.added.len(), index.updated.len(), index.deleted.len(),
+                    );
+                }
+                if plan.is_empty() {
+                    println!("No changes to make");
+                }
+                fs::write(&output, serde_json::to_string_pretty(&plan)?)?;
+                println!("{} Plan written to {}", "✓".green(), output.display());
+            }
+            Commands::Apply { config, plan } => {
+                let config = config::Config::from_file(&config)?;
+                let plan: plan::SyncPlan = serde_json::from_str(&fs::read_to_string(&plan)?)?;
+                let connector = connector::Connector::new(config).await?;
+                let report = connector.apply(&plan).await?;
+                for table in &report.changed_tables {
+                    eprintln!("{} Rows of table {} changed since the plan was computed", "!".yellow(), table);
+                }
+                for index in &report.indexes {
+                    println!(
+                        "{} {}: +{} ~{} -{}",
+                        "✓".green(), index.index.cyan(), index.documents_added, index.documents_updated, index.documents_deleted,
+                    );
+                }
+                println!("Applied the plan computed at {}", plan.created_at);
+            }
             Commands::Validate { config } => {
                 println!("{}", "Validating configuration...".green());
-                let _config = config::Config::from_file(&config)?;
+                let config = config::Config::from_file(&config)?;
+                let issues = connector::Connector::validate_config(&config);
+                if !issues.is_empty() {
+                    for issue in &issues {
+                        eprintln!("{} {}", "✗".red(), issue);
+                    }
+                    anyhow::bail!("Configuration has {} issue(s)", issues.len());
+                }
                 println!("{} Configuration is valid", "✓".green());
             }
+            Commands::ApplySettings { config, table } => {
+                println!("{}", "Applying index settings...".green());
+                let config = config::Config::from_file(&config)?;
+                let connector = connector::Connector::new(config).await?;
+                let indices = connector.apply_settings(table.as_deref()).await?;
+                if let Some(version) = connector.meilisearch_version() {
+                    println!("Meilisearch version: {}", version.cyan());
+                }
+                for index in indices {
+                    println!("{} Settings applied to index {}", "✓".green(), index.cyan());
+                }
+            }
+            Commands::Cutover { config, table, keep_previous } => {
+                println!("{}", "Rebuilding versioned indexes...".green());
+                let config = config::Config::from_file(&config)?;
+                let connector = connector::Connector::new(config).await?;
+                let reports = connector.cutover(table.as_deref(), keep_previous).await?;
+                if reports.is_empty() {
+                    println!("No table sets use_versioned_indexes = true");
+                }
+                for report in reports {
+                    let previous = if report.kept_previous {
+                        format!(", previous documents kept in {}", report.versioned_index)
+                    } else {
+                        String::new()
+                    };
+                    println!(
+                        "{} {} -> {}: rebuilt {} documents in {} and swapped them in{}",
+                        "✓".green(), report.table, report.index.cyan(), report.sync.documents_added,
+                        report.versioned_index, previous,
+                    );
+                }
+            }
+            Commands::Verify { config, table, sample } => {
+                println!("{}", "Verifying indexed documents...".green());
+                let config = config::Config::from_file(&config)?;
+                let connector = connector::Connector::new(config).await?;
+                let mut mismatches = 0;
+                for report in connector.verify(table.as_deref(), sample).await? {
+                    for mismatch in &report.mismatches {
+                        match &mismatch.field {
+                            Some(field) => eprintln!(
+                                "{} {}/{} field {}: database {} vs index {}",
+                                "✗".red(), report.index, mismatch.id, field.cyan(),
+                                display_value(mismatch.database.as_ref()), display_value(mismatch.index.as_ref()),
+                            ),
+                            None => eprintln!("{} {}/{} is missing from the index", "✗".red(), report.index, mismatch.id),
+                        }
+                    }
+                    if report.mismatches.is_empty() {
+                        println!("{} Table {}: {} documents match index {}", "✓".green(), report.table, report.checked, report.index.cyan());
+                    }
+                    mismatches += report.mismatches.len();
+                }
+                if mismatches > 0 {
+                    anyhow::bail!("Found {} mismatch(es) between the database and Meilisearch", mismatches);
+                }
+            }
+            Commands::Inspect { database_url, table, limit } => {
+                inspect_table(&database_url, &table, limit).await?;
+            }
+            Commands::Template { output } => {
+                let template = config::Config::template();
+                match output {
+                    Some(output) => {
+                        fs::write(&output, template)?;
+                        println!("{} Template written to {}", "✓".green(), output.display());
+                    }
+                    None => print!("{}", template),
+                }
+            }
+            Commands::Schema { output } => {
+                let schema = serde_json::to_string_pretty(&config::Config::json_schema())?;
+                match output {
+                    Some(output) => {
+                        fs::write(&output, schema)?;
+                        println!("{} Schema written to {}", "✓".green(), output.display());
+                    }
+                    None => println!("{}", schema),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// A field value as printed by `verify`, "<missing>" for absent fields
+fn display_value(value: Option<&serde_json::Value>) -> String {
+    value.map_or_else(|| "<missing>".to_string(), |v| v.to_string())
+}
+
+async fn generate_config(
+    database_url: &str,
+    meilisearch_host: &str,
+    meilisearch_key: Option<String>,
+    output: &Path,
+    poll_interval: u64,
+    include_views: bool,
+    rules: Option<&Path>,
+) -> Result<()> {
+    let mut config = config::Config::generate_from_database_url(
+        database_url,
+        meilisearch_host,
+        poll_interval,
+        include_views,
+    ).await?;
+
+    if let Some(rules) = rules {
+        let unused = config::GenerateRules::from_file(rules)?.apply(&mut config.database.tables);
+        for pattern in unused {
+            eprintln!("{} Rule for columns '{}' matches no column", "Warning:".yellow().bold(), pattern);
         }
     }
+    
+    // Set the API key if provided
+    if let Some(key) = meilisearch_key {
+        config.meilisearch.api_key = Some(key);
+    }
+    
+    fs::write(output, config.to_toml()?)?;
+    Ok(())
+}
+
+async fn inspect_table(database_url: &str, table: &str, limit: usize) -> Result<()> {
+    let adapter = database::create_db_adapter(database_url, &database::AdapterOptions::default()).await?;
+
+    println!("\n{} {}", "Table".bold(), table.cyan());
+    let columns = adapter.get_column_details(table).await?;
+    if columns.is_empty() {
+        anyhow::bail!("Table '{}' not found or has no columns", table);
+    }
+    for column in &columns {
+        let nullable = match column.nullable {
+            Some(true) => "NULL",
+            Some(false) => "NOT NULL",
+            None => "",
+        };
+        let primary_key = if column.primary_key { "PRIMARY KEY" } else { "" };
+        println!("  {:<24} {:<16} {:<9} {}", column.name, column.type_, nullable, primary_key);
+    }
+
+    match adapter.get_primary_key(table).await {
+        Ok(primary_key) => println!("\n{} {}", "Primary key:".bold(), primary_key),
+        Err(e) => println!("\n{} none detected ({})", "Primary key:".bold(), e),
+    }
+    println!("{} {}", "Rows:".bold(), adapter.count_records(table).await?);
+
+    let samples = adapter.fetch_sample_records(table, limit).await?;
+    println!("\n{}", format!("Sample rows ({}):", samples.len()).bold());
+    for sample in samples {
+        println!("{}", serde_json::to_string_pretty(&sample)?);
+    }
+    Ok(())
+}
+
+fn print_sync_errors(run: &report::RunReport) {
+    for (table, error) in &run.errors {
+        eprintln!("{} {}: {}", "✗".red(), table, error);
+    }
+}
+
+/// Run the connector until Ctrl+C. On Unix, SIGHUP reloads its
+/// configuration from `load_config`.
+async fn run_connector(
+    connector: connector::Connector,
+    load_config: impl Fn() -> Result<config::Config> + Send + 'static,
+) -> Result<()> {
+    let connector = Arc::new(connector);
+    
+    // Clone for signal handler
+    let connector_for_signal = connector.clone();
+    
+    // Setup Ctrl+C handler
+    tokio::spawn(async move {
+        match signal::ctrl_c().await {
+            Ok(()) => {
+                println!("Ctrl+C received, shutting down...");
+                let _ = connector_for_signal.stop().await;
+            },
+            Err(err) => {
+                eprintln!("Error setting up Ctrl+C handler: {}", err);
+            },
+        }
+    });
+    
+    #[cfg(unix)]
+    {
+        let connector_for_reload = connector.clone();
+        tokio::spawn(async move {
+            let mut hangups = match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+                Ok(hangups) => hangups,
+                Err(err) => {
+                    eprintln!("Error setting up SIGHUP handler: {}", err);
+                    return;
+                }
+            };
+            while hangups.recv().await.is_some() {
+                println!("SIGHUP received, reloading configuration...");
+                let reloaded = match load_config() {
+                    Ok(config) => connector_for_reload.reload(config).await.map_err(anyhow::Error::from),
+                    Err(err) => Err(err),
+                };
+                match reloaded {
+                    Ok(report) => {
+                        println!(
+                            "{} Configuration reloaded: {} added, {} removed, {} changed",
+                            "✓".green(), report.added.len(), report.removed.len(), report.changed.len(),
+                        );
+                        for option in &report.restart_required {
+                            eprintln!("{} {} changed, restart to apply it", "!".yellow(), option);
+                        }
+                        for (table, error) in &report.errors {
+                            eprintln!("{} {}: {}", "✗".red(), table, error);
+                        }
+                    }
+                    // The connector keeps running with its current configuration
+                    Err(err) => eprintln!("{} Configuration not reloaded: {}", "✗".red(), err),
+                }
+            }
+        });
+    }
 
+    connector.start().await?;
     Ok(())
 }
@@ -1,10 +1,20 @@
-use meilisearch_sdk::client::Client;
+use meilisearch_sdk::client::{Client, SwapIndexes};
+use meilisearch_sdk::documents::DocumentsQuery;
+use meilisearch_sdk::indexes::IndexesQuery;
+use meilisearch_sdk::request::{HttpClient, Method};
 use meilisearch_sdk::settings::Settings;
+use meilisearch_sdk::task_info::TaskInfo;
+use meilisearch_sdk::tasks::{Task, TasksSearchQuery};
+use crate::config::UploadFormat;
+use super::HttpTransport;
 use crate::error::{ConnectorError, Result};
 use std::sync::Arc;
 use async_trait::async_trait;
-use tracing::{info, warn};
+use futures_util::io::Cursor;
+use tracing::{debug, info, info_span, warn, Instrument};
 use tokio::time::{sleep, Duration};
+use meilisearch_sdk::errors::{Error as MeilisearchError, ErrorCode};
+use std::future::Future;
 
 #[async_trait]
 pub trait MeilisearchClientTrait: Send + Sync {
@@ -12,31 +22,389 @@ pub trait MeilisearchClientTrait: Send + Sync {
     async fn get_all_documents(&self, index_name: &str) -> Result<Vec<serde_json::Value>>;
     async fn add_or_update_documents(&self, index_name: &str, documents: Vec<serde_json::Value>, batch_size: Option<usize>) -> Result<()>;
     async fn delete_documents(&self, index_name: &str, ids: &[String], batch_size: Option<usize>) -> Result<()>;
+
+    /// Like `get_all_documents`, with the `_vectors` Meilisearch stores for
+    /// each document. Clients keeping documents as they were sent return
+    /// their vectors already.
+    async fn get_all_documents_with_vectors(&self, index_name: &str) -> Result<Vec<serde_json::Value>> {
+        self.get_all_documents(index_name).await
+    }
+
+    /// Every document of an index ordered by `primary_key` as
+    /// `compare_primary_keys` orders its values, `None` for clients that
+    /// can't fetch them in that order. Meilisearch's documents API can't
+    /// sort, so the real client leaves the documents unordered.
+    async fn get_all_documents_ordered(&self, _index_name: &str, _primary_key: &str) -> Result<Option<Vec<serde_json::Value>>> {
+        Ok(None)
+    }
+
+    /// Primary key of an existing index, `None` if the index doesn't exist or
+    /// has no primary key yet
+    async fn index_primary_key(&self, _index_name: &str) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Delete an index with all its documents, waiting for the deletion to finish
+    async fn delete_index(&self, index_name: &str) -> Result<()> {
+        Err(ConnectorError::Meilisearch(format!("Deleting index {} is not supported by this client", index_name)))
+    }
+
+    /// Names of every index on the server
+    async fn list_indexes(&self) -> Result<Vec<String>> {
+        Err(ConnectorError::Meilisearch("Listing indexes is not supported by this client".to_string()))
+    }
+
+    /// Swap the documents and settings of two existing indexes in one
+    /// atomic operation, waiting for the swap to finish
+    async fn swap_indexes(&self, first: &str, second: &str) -> Result<()> {
+        Err(ConnectorError::Meilisearch(format!("Swapping indexes {} and {} is not supported by this client", first, second)))
+    }
+
+    /// Wait until Meilisearch has processed every write sent to an index,
+    /// failing if any of them failed. Clients that apply writes as they
+    /// receive them have nothing to wait for.
+    async fn wait_for_tasks(&self, _index_name: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Add or update documents in a single request and wait for Meilisearch
+    /// to index them, failing with `ConnectorError::DocumentsRejected` if it
+    /// refused them. Clients that apply writes as they receive them just add
+    /// the documents.
+    async fn add_documents_checked(&self, index_name: &str, documents: Vec<serde_json::Value>) -> Result<()> {
+        let batch_size = documents.len().max(1);
+        self.add_or_update_documents(index_name, documents, Some(batch_size)).await
+    }
+
+    /// Update index settings from a raw JSON object, for settings `Settings`
+    /// doesn't model. Waits for Meilisearch to accept or reject them.
+    async fn set_raw_settings(&self, index_name: &str, _settings: &serde_json::Value) -> Result<()> {
+        Err(ConnectorError::Meilisearch(format!("Raw settings for index {} are not supported by this client", index_name)))
+    }
+
+    /// Fetch a single document by primary key, `None` if the index doesn't
+    /// contain it
+    async fn get_document(&self, index_name: &str, _id: &str) -> Result<Option<serde_json::Value>> {
+        Err(ConnectorError::Meilisearch(format!("Fetching single documents from index {} is not supported by this client", index_name)))
+    }
+
+    /// Check that Meilisearch is up and available. Clients that can't check
+    /// report healthy.
+    async fn health(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Version of the Meilisearch server, `None` for clients that can't tell
+    async fn version(&self) -> Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+/// Whether Meilisearch rejected a request because its body exceeded the
+/// configured payload size limit
+pub fn is_payload_too_large(error: &MeilisearchError) -> bool {
+    match error {
+        MeilisearchError::Meilisearch(e) => e.error_code == ErrorCode::PayloadTooLarge,
+        MeilisearchError::MeilisearchCommunication(e) => e.status_code == 413,
+        _ => false,
+    }
+}
+
+/// Whether Meilisearch rejected a settings update because it doesn't know
+/// one of the settings, as servers released before that setting do
+pub fn is_unknown_setting(error: &MeilisearchError) -> bool {
+    match error {
+        MeilisearchError::Meilisearch(e) => e.error_code == ErrorCode::BadRequest && e.error_message.starts_with("Unknown field"),
+        _ => false,
+    }
+}
+
+/// Send `documents` with `send`, halving any batch Meilisearch rejects as too
+/// large and retrying the halves, down to single documents. A single document
+/// that is still too large fails the whole call.
+pub async fn add_splitting_oversized<F, Fut>(
+    documents: &[serde_json::Value],
+    mut send: F,
+) -> std::result::Result<(), MeilisearchError>
+where
+    F: FnMut(&[serde_json::Value]) -> Fut,
+    Fut: Future<Output = std::result::Result<(), MeilisearchError>>,
+{
+    // Work through the batches in order; a split pushes its halves back in front
+    let mut pending = vec![documents];
+    while let Some(batch) = pending.pop() {
+        match send(batch).await {
+            Ok(()) => {}
+            Err(e) if batch.len() > 1 && is_payload_too_large(&e) => {
+                let (first, second) = batch.split_at(batch.len() / 2);
+                warn!("Batch of {} documents is too large for Meilisearch, retrying as {} and {}",
+                      batch.len(), first.len(), second.len());
+                pending.push(second);
+                pending.push(first);
+            }
+            Err(e) => {
+                if batch.len() == 1 && is_payload_too_large(&e) {
+                    warn!("Document is larger than the Meilisearch payload limit on its own: {}",
+                          serde_json::to_string(&batch[0]).unwrap_or_default().chars().take(200).collect::<String>());
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fetch every page of a paginated listing with `fetch(offset, limit)`,
+/// which returns a page and the listing's total. Stops at the first short
+/// page, or once the total is reached.
+pub async fn fetch_all_pages<T, F, Fut>(page_size: usize, mut fetch: F) -> std::result::Result<Vec<T>, MeilisearchError>
+where
+    F: FnMut(usize, usize) -> Fut,
+    Fut: Future<Output = std::result::Result<(Vec<T>, usize), MeilisearchError>>,
+{
+    let mut items = Vec::new();
+    loop {
+        let (page, total) = fetch(items.len(), page_size).await?;
+        let count = page.len();
+        items.extend(page);
+        if count < page_size || items.len() >= total {
+            return Ok(items);
+        }
+    }
+}
+
+/// Serialize documents as newline-delimited JSON, one document per line
+pub fn to_ndjson(documents: &[serde_json::Value]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    for document in documents {
+        // Writing a `Value` to a `Vec` can't fail
+        serde_json::to_writer(&mut buffer, document).unwrap_or_default();
+        buffer.push(b'\n');
+    }
+    buffer
+}
+
+/// Serialize documents as CSV with Meilisearch's typed headers, e.g.
+/// `price:number`. Returns `None` when the documents wouldn't be indexed
+/// exactly as their JSON: nested objects or arrays, nulls and empty strings
+/// (both read back as null), a column mixing types, or documents with
+/// different fields.
+pub fn to_csv(documents: &[serde_json::Value]) -> Option<Vec<u8>> {
+    let first = documents.first()?.as_object()?;
+    let mut columns = Vec::with_capacity(first.len());
+    for (name, value) in first {
+        let type_ = match value {
+            serde_json::Value::Number(_) => "number",
+            serde_json::Value::Bool(_) => "boolean",
+            serde_json::Value::String(s) if !s.is_empty() => "string",
+            _ => return None,
+        };
+        // A colon would be read as the start of the type annotation
+        if name.contains(':') {
+            return None;
+        }
+        columns.push((name, type_));
+    }
+
+    let mut csv = String::new();
+    let header: Vec<String> = columns.iter().map(|(name, type_)| csv_field(&format!("{}:{}", name, type_))).collect();
+    csv.push_str(&header.join(","));
+    csv.push('\n');
+    for document in documents {
+        let object = document.as_object()?;
+        if object.len() != columns.len() {
+            return None;
+        }
+        let mut row = Vec::with_capacity(columns.len());
+        for (name, type_) in &columns {
+            row.push(match (object.get(*name)?, *type_) {
+                (serde_json::Value::Number(n), "number") => n.to_string(),
+                (serde_json::Value::Bool(b), "boolean") => b.to_string(),
+                (serde_json::Value::String(s), "string") if !s.is_empty() => csv_field(s),
+                _ => return None,
+            });
+        }
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    Some(csv.into_bytes())
+}
+
+// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Wait `delay` between two batches of a write. A zero delay returns
+/// immediately, without yielding to the runtime.
+pub async fn pause_between_batches(delay: Duration) {
+    if !delay.is_zero() {
+        sleep(delay).await;
+    }
+}
+
+// Indexes fetched per request when listing them
+const INDEX_PAGE_SIZE: usize = 100;
+
+// How long to wait for the writes to an index to be processed, e.g. those
+// of a full reindex before it's swapped in
+const TASK_WAIT_TIMEOUT: Duration = Duration::from_secs(3600);
+
+// A batch of documents ready to be sent in the configured upload format
+enum DocumentsBody {
+    Json(Vec<serde_json::Value>),
+    Ndjson(Vec<u8>),
+    Csv(Vec<u8>),
 }
 
 pub struct MeilisearchClient {
-    client: Arc<Client>,
+    client: Arc<Client<HttpTransport>>,
+    // The SDK only sends typed settings, raw ones go through its HTTP client
+    http_client: HttpTransport,
     // Default batch sizes
     default_add_batch_size: usize,
     default_delete_batch_size: usize,
+    // Documents fetched per request by get_all_documents
+    document_page_size: usize,
+    ndjson_upload: bool,
+    upload_format: UploadFormat,
+    inter_batch_delay: Duration,
 }
 
 impl MeilisearchClient {
+    #[allow(dead_code)]
     pub fn new(host: &str, api_key: Option<&str>) -> Result<Self> {
-        let client = Client::new(host, api_key)?;
+        Self::new_with_proxy(host, api_key, None)
+    }
+
+    /// A client sending its requests through the HTTP(S) proxy at
+    /// `proxy_url`, see `HttpTransport::new`
+    pub fn new_with_proxy(host: &str, api_key: Option<&str>, proxy_url: Option<&str>) -> Result<Self> {
+        let http_client = HttpTransport::new(api_key, proxy_url)?;
+        let client = Client::new_with_client(host, api_key, http_client.clone());
         Ok(Self {
             client: Arc::new(client),
+            http_client,
             default_add_batch_size: 100,
             default_delete_batch_size: 1000,
+            document_page_size: 1000,
+            ndjson_upload: false,
+            upload_format: UploadFormat::default(),
+            inter_batch_delay: Duration::from_millis(100),
         })
     }
+
+    /// Upload document batches as NDJSON, serialized straight from the
+    /// documents instead of copying each batch for the SDK first
+    pub fn with_ndjson_upload(mut self, ndjson_upload: bool) -> Self {
+        self.ndjson_upload = ndjson_upload;
+        self
+    }
+
+    /// Upload batches of flat documents as CSV with `UploadFormat::Csv`.
+    /// Batches `to_csv` can't represent exactly still go out as JSON.
+    pub fn with_upload_format(mut self, upload_format: UploadFormat) -> Self {
+        self.upload_format = upload_format;
+        self
+    }
+
+    /// Fetch this many documents per request when reading a whole index
+    pub fn with_document_page_size(mut self, page_size: usize) -> Self {
+        self.document_page_size = page_size.max(1);
+        self
+    }
+
+    /// Pause this long between consecutive batches of a write, zero to disable
+    pub fn with_inter_batch_delay(mut self, delay: Duration) -> Self {
+        self.inter_batch_delay = delay;
+        self
+    }
+}
+
+impl MeilisearchClient {
+    // Update typed settings, falling back to one setting at a time when the
+    // server doesn't know one of them
+    async fn set_settings(&self, index_name: &str, settings: &Settings) -> Result<()> {
+        match self.client.index(index_name).set_settings(settings).await {
+            Ok(_) => Ok(()),
+            Err(e) if is_unknown_setting(&e) => {
+                let settings = serde_json::to_value(settings)
+                    .map_err(|e| ConnectorError::Meilisearch(format!("Failed to serialize index settings: {}", e)))?;
+                self.patch_settings_separately(index_name, &settings).await
+            }
+            Err(e) => Err(ConnectorError::from(e)),
+        }
+    }
+
+    // One page of documents with their vectors, which the SDK's documents
+    // query can't ask for
+    async fn get_documents_page_with_vectors(
+        &self,
+        index_name: &str,
+        offset: usize,
+        limit: usize,
+    ) -> std::result::Result<(Vec<serde_json::Value>, usize), MeilisearchError> {
+        #[derive(serde::Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Query {
+            offset: usize,
+            limit: usize,
+            retrieve_vectors: bool,
+        }
+        #[derive(serde::Deserialize)]
+        struct Page {
+            results: Vec<serde_json::Value>,
+            total: usize,
+        }
+        let url = format!("{}/indexes/{}/documents", self.client.get_host(), index_name);
+        let query = Query { offset, limit, retrieve_vectors: true };
+        let page = self.http_client
+            .request::<Query, (), Page>(&url, Method::Get { query }, 200)
+            .await?;
+        Ok((page.results, page.total))
+    }
+
+    // PATCH a raw settings object and wait for Meilisearch to apply it
+    async fn patch_settings(&self, index_name: &str, settings: &serde_json::Value) -> std::result::Result<(), MeilisearchError> {
+        let url = format!("{}/indexes/{}/settings", self.client.get_host(), index_name);
+        let task = self.http_client
+            .request::<(), &serde_json::Value, TaskInfo>(&url, Method::Patch { query: (), body: settings }, 202)
+            .await?
+            .wait_for_completion(&self.client, None, None)
+            .await?;
+        if let Task::Failed { content } = task {
+            return Err(MeilisearchError::Meilisearch(content.error));
+        }
+        Ok(())
+    }
+
+    // Apply each setting of `settings` on its own, skipping with a warning
+    // those the server doesn't know instead of failing the whole update
+    async fn patch_settings_separately(&self, index_name: &str, settings: &serde_json::Value) -> Result<()> {
+        let version = self.version().await.ok().flatten().unwrap_or_else(|| "of unknown version".to_string());
+        for (name, value) in settings.as_object().into_iter().flatten() {
+            let setting = serde_json::json!({ name: value });
+            match self.patch_settings(index_name, &setting).await {
+                Ok(()) => {}
+                Err(e) if is_unknown_setting(&e) => {
+                    warn!("Meilisearch {} doesn't support the {} setting, skipping it for index {}", version, name, index_name);
+                }
+                Err(e) => return Err(ConnectorError::Meilisearch(format!(
+                    "Setting {} of index {} was rejected: {}", name, index_name, e
+                ))),
+            }
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl MeilisearchClientTrait for MeilisearchClient {
     async fn setup_index(&self, index_name: &str, settings: Settings, primary_key: Option<&str>) -> Result<()> {
-        let index = self.client.index(index_name);
-        
         // Create or update the index with primary key
         if let Some(pk) = primary_key {
             info!("Creating/updating index {} with primary key {}", index_name, pk);
@@ -45,19 +413,19 @@ impl MeilisearchClientTrait for MeilisearchClient {
             match self.client.get_index(index_name).await {
                 Ok(_) => {
                     // Index exists, update settings
-                    index.set_settings(&settings).await.map_err(ConnectorError::from)?;
+                    self.set_settings(index_name, &settings).await?;
                 },
                 Err(_) => {
                     // Index doesn't exist, create it with primary key
                     self.client.create_index(index_name, Some(pk)).await.map_err(ConnectorError::from)?;
                     
                     // Then set other settings
-                    index.set_settings(&settings).await.map_err(ConnectorError::from)?;
+                    self.set_settings(index_name, &settings).await?;
                 }
             }
         } else {
             // Just update settings if no primary key specified
-            index.set_settings(&settings).await.map_err(ConnectorError::from)?;
+            self.set_settings(index_name, &settings).await?;
         }
         
         // Wait a moment for settings to apply
@@ -66,17 +434,145 @@ impl MeilisearchClientTrait for MeilisearchClient {
         Ok(())
     }
 
+    async fn index_primary_key(&self, index_name: &str) -> Result<Option<String>> {
+        match self.client.get_index(index_name).await {
+            Ok(index) => Ok(index.primary_key),
+            Err(MeilisearchError::Meilisearch(e)) if e.error_code == ErrorCode::IndexNotFound => Ok(None),
+            Err(e) => Err(ConnectorError::from(e)),
+        }
+    }
+
+    async fn delete_index(&self, index_name: &str) -> Result<()> {
+        info!("Deleting index {}", index_name);
+        self.client.delete_index(index_name).await?
+            .wait_for_completion(&self.client, None, None).await?;
+        Ok(())
+    }
+
+    async fn list_indexes(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        loop {
+            let page = IndexesQuery::new(&self.client)
+                .with_offset(names.len())
+                .with_limit(INDEX_PAGE_SIZE)
+                .execute()
+                .await?;
+            let count = page.results.len();
+            names.extend(page.results.into_iter().map(|index| index.uid));
+            if count < INDEX_PAGE_SIZE || names.len() >= page.total as usize {
+                return Ok(names);
+            }
+        }
+    }
+
+    async fn swap_indexes(&self, first: &str, second: &str) -> Result<()> {
+        info!("Swapping indexes {} and {}", first, second);
+        let swap = SwapIndexes { indexes: (first.to_string(), second.to_string()) };
+        let task = self.client.swap_indexes([&swap]).await?
+            .wait_for_completion(&self.client, None, None).await?;
+        if let Task::Failed { content } = task {
+            return Err(ConnectorError::Meilisearch(format!(
+                "Swapping indexes {} and {} failed: {}", first, second, content.error
+            )));
+        }
+        Ok(())
+    }
+
+    async fn wait_for_tasks(&self, index_name: &str) -> Result<()> {
+        // Tasks are processed in the order they were enqueued, so the newest
+        // pending one finishes last
+        let mut query = TasksSearchQuery::new(&self.client);
+        query.with_index_uids([index_name]).with_statuses(["enqueued", "processing"]).with_limit(1);
+        if let Some(task) = self.client.get_tasks_with(&query).await?.results.into_iter().next() {
+            info!("Waiting for the pending tasks of index {}", index_name);
+            task.wait_for_completion(&self.client, None, Some(TASK_WAIT_TIMEOUT)).await?;
+        }
+        let mut query = TasksSearchQuery::new(&self.client);
+        query.with_index_uids([index_name]).with_statuses(["failed"]).with_limit(1);
+        if let Some(Task::Failed { content }) = self.client.get_tasks_with(&query).await?.results.into_iter().next() {
+            return Err(ConnectorError::Meilisearch(format!(
+                "Task {} of index {} failed: {}", content.task.uid, index_name, content.error
+            )));
+        }
+        Ok(())
+    }
+
+    async fn add_documents_checked(&self, index_name: &str, documents: Vec<serde_json::Value>) -> Result<()> {
+        let task = match self.client.index(index_name).add_documents(&documents, None).await {
+            Ok(task) => task,
+            Err(e @ MeilisearchError::Meilisearch(_)) => return Err(ConnectorError::DocumentsRejected(e.to_string())),
+            Err(e) => return Err(e.into()),
+        };
+        match task.wait_for_completion(&self.client, None, Some(TASK_WAIT_TIMEOUT)).await? {
+            Task::Failed { content } => Err(ConnectorError::DocumentsRejected(content.error.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    async fn set_raw_settings(&self, index_name: &str, settings: &serde_json::Value) -> Result<()> {
+        info!("Applying raw settings to index {}", index_name);
+        match self.patch_settings(index_name, settings).await {
+            Err(e) if is_unknown_setting(&e) => self.patch_settings_separately(index_name, settings).await,
+            Err(e @ MeilisearchError::Meilisearch(_)) => Err(ConnectorError::Meilisearch(format!(
+                "Raw settings for index {} were rejected: {}", index_name, e
+            ))),
+            result => result.map_err(ConnectorError::from),
+        }
+    }
+
+    async fn get_document(&self, index_name: &str, id: &str) -> Result<Option<serde_json::Value>> {
+        match self.client.index(index_name).get_document::<serde_json::Value>(id).await {
+            Ok(document) => Ok(Some(document)),
+            Err(MeilisearchError::Meilisearch(e)) if e.error_code == ErrorCode::DocumentNotFound => Ok(None),
+            Err(e) => Err(ConnectorError::from(e)),
+        }
+    }
+
+    async fn health(&self) -> Result<()> {
+        self.client.health().await?;
+        Ok(())
+    }
+
+    async fn version(&self) -> Result<Option<String>> {
+        Ok(Some(self.client.get_version().await?.pkg_version))
+    }
+
     async fn get_all_documents(&self, index_name: &str) -> Result<Vec<serde_json::Value>> {
         let index = self.client.index(index_name);
-        
-        // Get documents in a single request
-        // Note: This might need to be improved for very large document sets
-        let result = index.get_documents().await.map_err(ConnectorError::from)?;
-        
-        // Log the document count
-        info!("Retrieved {} documents from index {}", result.results.len(), index_name);
-        
-        Ok(result.results)
+
+        // A single request only returns the first page of documents
+        let documents = fetch_all_pages(self.document_page_size, |offset, limit| {
+            let mut query = DocumentsQuery::new(&index);
+            query.with_offset(offset).with_limit(limit);
+            async move {
+                let page = query.execute::<serde_json::Value>().await?;
+                Ok((page.results, page.total as usize))
+            }
+        })
+        .await?;
+
+        info!("Retrieved {} documents from index {}", documents.len(), index_name);
+        Ok(documents)
+    }
+
+    async fn get_all_documents_with_vectors(&self, index_name: &str) -> Result<Vec<serde_json::Value>> {
+        let documents = fetch_all_pages(self.document_page_size, |offset, limit| {
+            self.get_documents_page_with_vectors(index_name, offset, limit)
+        })
+        .await;
+        match documents {
+            Ok(documents) => {
+                info!("Retrieved {} documents with their vectors from index {}", documents.len(), index_name);
+                Ok(documents)
+            }
+            // Servers without `retrieveVectors` return documents without them,
+            // whose vectors then aren't compared
+            Err(MeilisearchError::Meilisearch(e)) if e.error_code != ErrorCode::IndexNotFound => {
+                warn!("Meilisearch can't return the vectors of index {}, changed embeddings won't be detected: {}", index_name, e);
+                self.get_all_documents(index_name).await
+            }
+            Err(e) => Err(ConnectorError::from(e)),
+        }
     }
 
     async fn add_or_update_documents(&self, index_name: &str, documents: Vec<serde_json::Value>, batch_size: Option<usize>) -> Result<()> {
@@ -92,28 +588,50 @@ impl MeilisearchClientTrait for MeilisearchClient {
         // Debug the first document to see its structure
         if !documents.is_empty() {
             let sample_doc = &documents[0];
-            info!("Sample document for {}: {}", index_name, serde_json::to_string_pretty(&sample_doc).unwrap_or_default());
+            debug!("Sample document for {}: {}", index_name, serde_json::to_string_pretty(&sample_doc).unwrap_or_default());
         }
         
         for (i, chunk) in documents.chunks(batch_size).enumerate() {
             if total_docs > batch_size {
                 info!("Processing batch {}/{} for index {}", 
-                     i + 1, (total_docs + batch_size - 1) / batch_size, index_name);
+                     i + 1, total_docs.div_ceil(batch_size), index_name);
             }
             
-            // Process the batch
-            match index.add_documents(chunk, None).await {
+            // Process the batch, splitting it if Meilisearch finds it too large
+            let ndjson_upload = self.ndjson_upload;
+            let upload_format = self.upload_format;
+            let send = |docs: &[serde_json::Value]| {
+                let index = index.clone();
+                let csv = match upload_format {
+                    UploadFormat::Csv => to_csv(docs),
+                    UploadFormat::Json => None,
+                };
+                let body = match csv {
+                    Some(csv) => DocumentsBody::Csv(csv),
+                    None if ndjson_upload => DocumentsBody::Ndjson(to_ndjson(docs)),
+                    None => DocumentsBody::Json(docs.to_vec()),
+                };
+                async move {
+                    match body {
+                        DocumentsBody::Ndjson(ndjson) => index.add_documents_ndjson(Cursor::new(ndjson), None).await.map(|_| ()),
+                        DocumentsBody::Csv(csv) => index.add_documents_csv(Cursor::new(csv), None).await.map(|_| ()),
+                        DocumentsBody::Json(docs) => index.add_documents(&docs, None).await.map(|_| ()),
+                    }
+                }
+            };
+            let span = info_span!("batch", index = index_name, batch = i + 1, documents = chunk.len());
+            match add_splitting_oversized(chunk, send).instrument(span).await {
                 Ok(_) => {
                     // Log success but don't wait for task completion
                     // This avoids compatibility issues with different versions of the SDK
                     if total_docs > batch_size {
                         info!("Successfully submitted batch {}/{} to index {}", 
-                            i + 1, (total_docs + batch_size - 1) / batch_size, index_name);
+                            i + 1, total_docs.div_ceil(batch_size), index_name);
                     }
                 },
                 Err(e) => {
                     warn!("Error adding batch {}/{} to index {}: {}", 
-                         i + 1, (total_docs + batch_size - 1) / batch_size, index_name, e);
+                         i + 1, total_docs.div_ceil(batch_size), index_name, e);
                     // Log a sample document for debugging
                     if !chunk.is_empty() {
                         warn!("Sample document in failed batch: {}", 
@@ -125,7 +643,7 @@ impl MeilisearchClientTrait for MeilisearchClient {
             
             // Small delay between batches to avoid overwhelming the server
             if i < documents.chunks(batch_size).count() - 1 {
-                sleep(Duration::from_millis(100)).await;
+                pause_between_batches(self.inter_batch_delay).await;
             }
         }
         
@@ -143,23 +661,24 @@ impl MeilisearchClientTrait for MeilisearchClient {
         }
         
         for (i, chunk) in ids.chunks(batch_size).enumerate() {
-            match index.delete_documents(chunk).await {
+            let span = info_span!("batch", index = index_name, batch = i + 1, deletes = chunk.len());
+            match index.delete_documents(chunk).instrument(span).await {
                 Ok(_) => {
                     if total_ids > batch_size {
                         info!("Successfully deleted batch {}/{} from index {}", 
-                            i + 1, (total_ids + batch_size - 1) / batch_size, index_name);
+                            i + 1, total_ids.div_ceil(batch_size), index_name);
                     }
                 },
                 Err(e) => {
                     warn!("Error deleting batch {}/{} from index {}: {}", 
-                          i + 1, (total_ids + batch_size - 1) / batch_size, index_name, e);
+                          i + 1, total_ids.div_ceil(batch_size), index_name, e);
                     return Err(ConnectorError::from(e));
                 }
             }
             
             // Small delay between batches to avoid overwhelming the server
             if i < ids.chunks(batch_size).count() - 1 {
-                sleep(Duration::from_millis(100)).await;
+                pause_between_batches(self.inter_batch_delay).await;
             }
         }
         
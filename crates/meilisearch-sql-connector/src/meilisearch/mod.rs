@@ -1,2 +1,4 @@
 pub mod client;
-pub use client::{MeilisearchClient, MeilisearchClientTrait}; 
\ No newline at end of file
+pub mod transport;
+pub use client::{MeilisearchClient, MeilisearchClientTrait};
+pub use transport::HttpTransport;
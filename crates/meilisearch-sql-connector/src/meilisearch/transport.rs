@@ -0,0 +1,100 @@
+use crate::error::{ConnectorError, Result};
+use async_trait::async_trait;
+use futures_util::io::{AsyncRead, AsyncReadExt};
+use meilisearch_sdk::errors::Error;
+use meilisearch_sdk::request::{parse_response, HttpClient, Method};
+use meilisearch_sdk::reqwest::qualified_version;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
+use reqwest::{NoProxy, Proxy};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::{field, instrument, Span};
+
+// Bytes read from a streamed request body at a time
+const BODY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// HTTP client the Meilisearch SDK sends its requests with, which the SDK's
+/// own can't be configured for: requests can go through a proxy.
+///
+/// Without a proxy, the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`
+/// environment variables apply, as with the SDK's client.
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    client: reqwest::Client,
+}
+
+impl HttpTransport {
+    /// `proxy_url` is an `http://` or `https://` URL, with the proxy's
+    /// credentials as its user and password if it requires them. Hosts listed
+    /// in `NO_PROXY` are still reached directly.
+    pub fn new(api_key: Option<&str>, proxy_url: Option<&str>) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        // The SDK's own user agent, which Meilisearch recognizes
+        if let Ok(user_agent) = HeaderValue::from_str(&qualified_version()) {
+            headers.insert(USER_AGENT, user_agent);
+        }
+        if let Some(api_key) = api_key {
+            let value = HeaderValue::from_str(&format!("Bearer {}", api_key))
+                .map_err(|_| ConnectorError::Config("meilisearch.api_key is not a valid header value".to_string()))?;
+            headers.insert(AUTHORIZATION, value);
+        }
+        let mut builder = reqwest::Client::builder().default_headers(headers);
+        if let Some(proxy_url) = proxy_url {
+            let proxy = Proxy::all(proxy_url)
+                .map_err(|e| ConnectorError::Config(format!("Invalid meilisearch.proxy_url: {}", e)))?
+                .no_proxy(NoProxy::from_env());
+            builder = builder.proxy(proxy);
+        }
+        let client = builder.build()
+            .map_err(|e| ConnectorError::Meilisearch(format!("Failed to build the HTTP client: {}", e)))?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl HttpClient for HttpTransport {
+    #[instrument(name = "meilisearch_request", skip_all, fields(method = field::Empty, url = field::Empty, status = field::Empty))]
+    async fn stream_request<
+        Query: Serialize + Send + Sync,
+        Body: AsyncRead + Send + Sync + 'static,
+        Output: DeserializeOwned + 'static,
+    >(
+        &self,
+        url: &str,
+        method: Method<Query, Body>,
+        content_type: &str,
+        expected_status_code: u16,
+    ) -> std::result::Result<Output, Error> {
+        let query = yaup::to_string(method.query())?;
+        let url = format!("{}{}", url, query);
+        let verb = match &method {
+            Method::Get { .. } => reqwest::Method::GET,
+            Method::Delete { .. } => reqwest::Method::DELETE,
+            Method::Post { .. } => reqwest::Method::POST,
+            Method::Put { .. } => reqwest::Method::PUT,
+            Method::Patch { .. } => reqwest::Method::PATCH,
+        };
+        Span::current().record("method", verb.as_str()).record("url", url.as_str());
+
+        let mut request = self.client.request(verb, &url);
+        if let Some(body) = method.into_body() {
+            // Sent as it is read, so large uploads aren't copied first
+            let chunks = futures_util::stream::try_unfold(Box::pin(body), |mut body| async move {
+                let mut chunk = vec![0; BODY_CHUNK_SIZE];
+                let read = body.read(&mut chunk).await?;
+                chunk.truncate(read);
+                Ok::<_, std::io::Error>((read > 0).then_some((chunk, body)))
+            });
+            request = request.header(CONTENT_TYPE, content_type).body(reqwest::Body::wrap_stream(chunks));
+        }
+
+        let response = self.client.execute(request.build()?).await?;
+        let status = response.status().as_u16();
+        Span::current().record("status", status);
+        let mut body = response.text().await?;
+        if body.is_empty() {
+            body = "null".to_string();
+        }
+        parse_response(status, expected_status_code, &body, url)
+    }
+}
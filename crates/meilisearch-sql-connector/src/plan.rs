@@ -0,0 +1,295 @@
+use crate::config::TableConfig;
+use crate::database::DatabaseAdapter;
+use crate::error::Result;
+use crate::meilisearch::client::MeilisearchClientTrait;
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use meilisearch_sdk::settings::Settings;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// Changes a sync would make to every index, computed by a dry run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncPlan {
+    /// When the plan was computed, RFC 3339
+    pub created_at: String,
+    /// Whether documents were left out, keeping only their ids. Such plans
+    /// can't be applied.
+    pub summary_only: bool,
+    /// The rows each table had when the plan was computed, left out of
+    /// summaries
+    #[serde(default)]
+    pub tables: Vec<TableSignature>,
+    pub indexes: Vec<IndexPlan>,
+}
+
+/// Fingerprint of a table's rows as a sync reads them, independent of their
+/// order, to tell whether they changed between a plan and its apply
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableSignature {
+    pub table: String,
+    pub rows: u64,
+    pub checksum: String,
+}
+
+impl TableSignature {
+    /// Read every row of `table`, streamed so only one is held at a time
+    pub async fn compute(table: &TableConfig, adapter: &dyn DatabaseAdapter) -> Result<Self> {
+        let columns = table.selected_columns();
+        let mut rows = adapter.fetch_records_stream(&table.name, columns.as_deref());
+        let (mut count, mut checksum) = (0u64, 0u128);
+        while let Some(row) = rows.try_next().await? {
+            // A sum of the rows' hashes doesn't depend on the order they come in
+            let digest = Sha256::digest(serde_json::to_vec(&row).unwrap_or_default());
+            let mut prefix = [0; 16];
+            prefix.copy_from_slice(&digest[..16]);
+            checksum = checksum.wrapping_add(u128::from_be_bytes(prefix));
+            count += 1;
+        }
+        Ok(Self { table: table.name.clone(), rows: count, checksum: format!("{:032x}", checksum) })
+    }
+}
+
+/// Changes to one index, each list ordered by document id
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexPlan {
+    pub index: String,
+    pub primary_key: Option<String>,
+    /// Documents the index doesn't have yet
+    pub added: Vec<PlannedDocument>,
+    /// Indexed documents that would be replaced
+    pub updated: Vec<PlannedUpdate>,
+    /// Ids of the documents that would be deleted
+    pub deleted: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedDocument {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedUpdate {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+}
+
+impl SyncPlan {
+    /// Whether applying the plan would change nothing
+    pub fn is_empty(&self) -> bool {
+        self.indexes.iter().all(|index| index.added.is_empty() && index.updated.is_empty() && index.deleted.is_empty())
+    }
+}
+
+// What the recorder has seen of one index
+#[derive(Default)]
+struct IndexRecord {
+    primary_key: Option<String>,
+    // Documents read from the index, by id; null in summary mode
+    indexed: HashMap<String, Value>,
+    // Latest document sent for each id, and the ids deleted
+    written: BTreeMap<String, Value>,
+    deleted: BTreeSet<String>,
+}
+
+/// Meilisearch client for dry runs: reads go to `inner`, while index setup
+/// and document writes are recorded instead of sent, to be turned into a
+/// `SyncPlan`
+pub struct PlanRecorder {
+    inner: Arc<dyn MeilisearchClientTrait>,
+    summary_only: bool,
+    indexes: Mutex<BTreeMap<String, IndexRecord>>,
+}
+
+impl PlanRecorder {
+    /// With `summary_only`, the plan lists document ids without the documents
+    pub fn new(inner: Arc<dyn MeilisearchClientTrait>, summary_only: bool) -> Self {
+        Self { inner, summary_only, indexes: Mutex::new(BTreeMap::new()) }
+    }
+
+    /// The changes recorded so far
+    pub fn plan(&self) -> SyncPlan {
+        let indexes = self.indexes.lock().unwrap();
+        let keep = |document: &Value| (!self.summary_only).then(|| document.clone());
+        let indexes = indexes.iter()
+            .filter(|(_, record)| !record.written.is_empty() || !record.deleted.is_empty())
+            .map(|(index, record)| {
+                let mut plan = IndexPlan {
+                    index: index.clone(),
+                    primary_key: record.primary_key.clone(),
+                    deleted: record.deleted.iter().cloned().collect(),
+                    ..Default::default()
+                };
+                for (id, document) in &record.written {
+                    match record.indexed.get(id) {
+                        Some(before) => plan.updated.push(PlannedUpdate { id: id.clone(), before: keep(before), after: keep(document) }),
+                        None => plan.added.push(PlannedDocument { id: id.clone(), document: keep(document) }),
+                    }
+                }
+                plan
+            })
+            .collect();
+        SyncPlan { created_at: chrono::Utc::now().to_rfc3339(), summary_only: self.summary_only, tables: Vec::new(), indexes }
+    }
+
+    // Primary key of an index: the one it was set up with, else the server's
+    async fn primary_key(&self, index_name: &str) -> Result<Option<String>> {
+        if let Some(primary_key) = self.indexes.lock().unwrap().get(index_name).and_then(|r| r.primary_key.clone()) {
+            return Ok(Some(primary_key));
+        }
+        let primary_key = self.inner.index_primary_key(index_name).await?;
+        self.indexes.lock().unwrap().entry(index_name.to_string()).or_default().primary_key.clone_from(&primary_key);
+        Ok(primary_key)
+    }
+
+    // Indexes Meilisearch doesn't have, which a dry run doesn't create, and
+    // indexes without a primary key have no documents to read
+    async fn has_documents(&self, index_name: &str) -> Result<bool> {
+        Ok(self.inner.index_primary_key(index_name).await?.is_some())
+    }
+
+    async fn record_indexed(&self, index_name: &str, documents: &[Value]) -> Result<()> {
+        let Some(primary_key) = self.primary_key(index_name).await? else {
+            return Ok(());
+        };
+        let mut indexes = self.indexes.lock().unwrap();
+        let record = indexes.entry(index_name.to_string()).or_default();
+        for document in documents {
+            if let Some(id) = document_id(document, &primary_key) {
+                let document = if self.summary_only { Value::Null } else { document.clone() };
+                record.indexed.insert(id, document);
+            }
+        }
+        Ok(())
+    }
+
+    async fn record_written(&self, index_name: &str, documents: Vec<Value>) -> Result<()> {
+        let primary_key = self.primary_key(index_name).await?;
+        let mut indexes = self.indexes.lock().unwrap();
+        let record = indexes.entry(index_name.to_string()).or_default();
+        for document in documents {
+            // Without a known primary key, documents can only be told apart by position
+            let id = match &primary_key {
+                Some(primary_key) => document_id(&document, primary_key),
+                None => None,
+            };
+            let id = id.unwrap_or_else(|| record.written.len().to_string());
+            record.deleted.remove(&id);
+            record.written.insert(id, document);
+        }
+        Ok(())
+    }
+}
+
+// String form of a document's primary key, as Meilisearch ids are
+fn document_id(document: &Value, primary_key: &str) -> Option<String> {
+    match document.get(primary_key)? {
+        Value::String(id) => Some(id.clone()),
+        Value::Null => None,
+        id => Some(id.to_string()),
+    }
+}
+
+#[async_trait]
+impl MeilisearchClientTrait for PlanRecorder {
+    async fn setup_index(&self, index_name: &str, _settings: Settings, primary_key: Option<&str>) -> Result<()> {
+        info!("Dry run: not setting up index {}", index_name);
+        let mut indexes = self.indexes.lock().unwrap();
+        let record = indexes.entry(index_name.to_string()).or_default();
+        if let Some(primary_key) = primary_key {
+            record.primary_key = Some(primary_key.to_string());
+        }
+        Ok(())
+    }
+
+    async fn get_all_documents(&self, index_name: &str) -> Result<Vec<Value>> {
+        if !self.has_documents(index_name).await? {
+            return Ok(Vec::new());
+        }
+        let documents = self.inner.get_all_documents(index_name).await?;
+        self.record_indexed(index_name, &documents).await?;
+        Ok(documents)
+    }
+
+    async fn get_all_documents_with_vectors(&self, index_name: &str) -> Result<Vec<Value>> {
+        if !self.has_documents(index_name).await? {
+            return Ok(Vec::new());
+        }
+        let documents = self.inner.get_all_documents_with_vectors(index_name).await?;
+        self.record_indexed(index_name, &documents).await?;
+        Ok(documents)
+    }
+
+    async fn get_all_documents_ordered(&self, index_name: &str, primary_key: &str) -> Result<Option<Vec<Value>>> {
+        if !self.has_documents(index_name).await? {
+            return Ok(Some(Vec::new()));
+        }
+        let documents = self.inner.get_all_documents_ordered(index_name, primary_key).await?;
+        if let Some(documents) = &documents {
+            self.record_indexed(index_name, documents).await?;
+        }
+        Ok(documents)
+    }
+
+    async fn add_or_update_documents(&self, index_name: &str, documents: Vec<Value>, _batch_size: Option<usize>) -> Result<()> {
+        self.record_written(index_name, documents).await
+    }
+
+    async fn delete_documents(&self, index_name: &str, ids: &[String], _batch_size: Option<usize>) -> Result<()> {
+        let mut indexes = self.indexes.lock().unwrap();
+        let record = indexes.entry(index_name.to_string()).or_default();
+        for id in ids {
+            record.written.remove(id);
+            record.deleted.insert(id.clone());
+        }
+        Ok(())
+    }
+
+    async fn add_documents_checked(&self, index_name: &str, documents: Vec<Value>) -> Result<()> {
+        self.record_written(index_name, documents).await
+    }
+
+    async fn index_primary_key(&self, index_name: &str) -> Result<Option<String>> {
+        self.inner.index_primary_key(index_name).await
+    }
+
+    async fn delete_index(&self, index_name: &str) -> Result<()> {
+        info!("Dry run: not deleting index {}", index_name);
+        Ok(())
+    }
+
+    async fn set_raw_settings(&self, index_name: &str, _settings: &Value) -> Result<()> {
+        info!("Dry run: not updating the settings of index {}", index_name);
+        Ok(())
+    }
+
+    async fn list_indexes(&self) -> Result<Vec<String>> {
+        self.inner.list_indexes().await
+    }
+
+    async fn swap_indexes(&self, first: &str, second: &str) -> Result<()> {
+        info!("Dry run: not swapping indexes {} and {}", first, second);
+        Ok(())
+    }
+
+    async fn get_document(&self, index_name: &str, id: &str) -> Result<Option<Value>> {
+        self.inner.get_document(index_name, id).await
+    }
+
+    async fn health(&self) -> Result<()> {
+        self.inner.health().await
+    }
+
+    async fn version(&self) -> Result<Option<String>> {
+        self.inner.version().await
+    }
+}
@@ -0,0 +1,105 @@
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Row, SqlitePool};
+use tracing::info;
+
+/// A document operation waiting to be sent to Meilisearch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QueuedOperation {
+    Add { documents: Vec<Value> },
+    Delete { ids: Vec<String> },
+}
+
+/// On-disk queue of pending index operations, kept in a small SQLite file.
+///
+/// Operations are written before they are sent and removed once Meilisearch
+/// accepted them, so nothing computed by a sync is lost when Meilisearch is
+/// unreachable or the connector restarts.
+#[derive(Debug)]
+pub struct DurableQueue {
+    pool: SqlitePool,
+}
+
+impl DurableQueue {
+    /// Open the queue at `path`, creating the file if needed
+    pub async fn open(path: &str) -> Result<Self> {
+        let options = SqliteConnectOptions::new().filename(path).create_if_missing(true);
+        // A single connection keeps writes ordered
+        let pool = SqlitePoolOptions::new().max_connections(1).connect_with(options).await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS pending_operations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                index_name TEXT NOT NULL,
+                operation TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        let queue = Self { pool };
+        let pending = queue.len().await?;
+        if pending > 0 {
+            info!("Durable queue at {} has {} pending operations", path, pending);
+        }
+        Ok(queue)
+    }
+
+    /// Append operations for an index, all or nothing
+    pub async fn push(&self, index_name: &str, operations: &[QueuedOperation]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for operation in operations {
+            sqlx::query("INSERT INTO pending_operations (index_name, operation) VALUES (?, ?)")
+                .bind(index_name)
+                .bind(serde_json::to_string(operation).unwrap_or_default())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Pending operations for an index, oldest first, with their queue ids
+    pub async fn pending(&self, index_name: &str) -> Result<Vec<(i64, QueuedOperation)>> {
+        let rows = sqlx::query("SELECT id, operation FROM pending_operations WHERE index_name = ? ORDER BY id")
+            .bind(index_name)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut operations = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id: i64 = row.try_get("id")?;
+            let operation: String = row.try_get("operation")?;
+            let operation = serde_json::from_str(&operation).map_err(|e| {
+                crate::error::ConnectorError::Database(format!("Corrupt durable queue entry {}: {}", id, e))
+            })?;
+            operations.push((id, operation));
+        }
+        Ok(operations)
+    }
+
+    /// Drop an operation once Meilisearch has accepted it
+    pub async fn remove(&self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM pending_operations WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Number of pending operations across all indices
+    pub async fn len(&self) -> Result<usize> {
+        let count: i64 = sqlx::query("SELECT COUNT(*) FROM pending_operations")
+            .fetch_one(&self.pool)
+            .await?
+            .try_get(0)?;
+        Ok(count as usize)
+    }
+
+    #[allow(dead_code)]
+    pub async fn is_empty(&self) -> Result<bool> {
+        Ok(self.len().await? == 0)
+    }
+}
@@ -0,0 +1,292 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+/// Time spent in each phase of a table sync.
+///
+/// The database and Meilisearch fetches run concurrently, so `total` is
+/// usually less than the sum of the individual phases.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncTimings {
+    pub db_fetch: Duration,
+    pub meilisearch_fetch: Duration,
+    pub diff: Duration,
+    pub delete: Duration,
+    pub add: Duration,
+    pub total: Duration,
+}
+
+impl SyncTimings {
+    /// One-line, human readable breakdown of the phases
+    pub fn summary(&self) -> String {
+        format!(
+            "db_fetch={}ms meilisearch_fetch={}ms diff={}ms delete={}ms add={}ms total={}ms",
+            self.db_fetch.as_millis(),
+            self.meilisearch_fetch.as_millis(),
+            self.diff.as_millis(),
+            self.delete.as_millis(),
+            self.add.as_millis(),
+            self.total.as_millis(),
+        )
+    }
+}
+
+/// Outcome of syncing one table into its index
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub table: String,
+    pub index: String,
+    pub documents_added: usize,
+    pub documents_deleted: usize,
+    /// Indexed documents whose rows changed since, part of `documents_added`
+    pub documents_changed: usize,
+    /// Batches of documents Meilisearch didn't accept, which are only logged
+    pub batches_failed: usize,
+    /// Documents Meilisearch refused, isolated from their batch and written
+    /// to `dead_letter_path`, part of `documents_added`
+    pub documents_rejected: usize,
+    /// Whether only the rows changed since the previous sync were read, with
+    /// `updated_at_column`
+    pub incremental: bool,
+    /// Size in bytes of the largest field in the documents sent this sync,
+    /// measured before truncation
+    pub max_field_bytes: usize,
+    pub max_field_name: Option<String>,
+    /// Number of fields larger than `warn_field_bytes`
+    pub oversized_fields: usize,
+    /// Documents left out because the transform rejected them, e.g. too
+    /// large ones, ones with a malformed vector or ones not matching the
+    /// table's `document_schema`
+    pub documents_skipped: usize,
+    /// Documents not matching `document_schema`, by field violating it
+    pub schema_violations: BTreeMap<String, usize>,
+    /// Whether `max_documents_per_sync` held back part of the changes
+    pub capped: bool,
+    /// Adds and deletes left for the next sync because of the cap
+    pub documents_deferred: usize,
+    /// Deletions refused because they exceeded `max_delete_ratio`
+    pub deletions_blocked: usize,
+    /// Deletions left for a later sync because the replica the rows were
+    /// read from lagged more than `max_replica_lag_seconds`
+    pub deletions_postponed: usize,
+    /// Deletions dropped because the primary still has the rows the replica
+    /// is missing, with `read_connection_string`
+    pub deletions_unconfirmed: usize,
+    /// Values per column that couldn't be decoded and were indexed as null
+    pub decode_errors: BTreeMap<String, usize>,
+    /// Primary key values converted to the table's `primary_key_type`
+    pub primary_keys_coerced: usize,
+    /// Rows skipped because their primary key is missing, null, an empty
+    /// string, or listed in `invalid_primary_keys` / not convertible to
+    /// `primary_key_type`
+    pub primary_keys_missing: usize,
+    pub primary_keys_null: usize,
+    pub primary_keys_empty: usize,
+    pub primary_keys_invalid: usize,
+    /// Rows of the table with a valid primary key
+    pub database_documents: usize,
+    /// Documents sent by the sync with the same content as another one it
+    /// sent, counted with `detect_duplicates` or `suppress_duplicates`
+    pub duplicate_documents: usize,
+    /// Whether the diff was a merge join over documents and rows ordered by
+    /// primary key rather than a comparison of id sets
+    pub sorted_diff: bool,
+    /// Whether the rows were read as a stream and sent a chunk at a time,
+    /// with `stream_records`
+    pub streamed: bool,
+    /// Rows read but left out because they are older than the table's
+    /// retention window. The query reading the rows already leaves out most
+    /// of them, see `RowFilters::retention_condition`.
+    pub outside_retention_window: usize,
+    /// Whether maintenance mode kept this sync from writing to Meilisearch
+    pub maintenance: bool,
+    /// Adds and deletes withheld because of maintenance mode
+    pub documents_withheld: usize,
+    pub timings: SyncTimings,
+}
+
+impl SyncReport {
+    /// Documents the transform or Meilisearch rejected plus values that
+    /// couldn't be decoded
+    pub fn error_count(&self) -> usize {
+        self.documents_skipped + self.documents_rejected + self.decode_errors.values().sum::<usize>()
+    }
+
+    /// Rows skipped for their primary key, whichever way it was unusable
+    pub fn primary_keys_skipped(&self) -> usize {
+        self.primary_keys_missing + self.primary_keys_null + self.primary_keys_empty + self.primary_keys_invalid
+    }
+}
+
+/// Order of the tables in a printed `RunReport`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportOrder {
+    /// The order of the configuration
+    #[default]
+    Config,
+    /// Table name
+    Name,
+    /// Rows of the table
+    Documents,
+    /// Rejected documents and undecodable values
+    Errors,
+    /// Time the sync took
+    Duration,
+}
+
+/// Outcome of `Connector::sync_all_once`, one pass over every enabled table
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunReport {
+    /// Reports of the tables that synced
+    pub reports: Vec<SyncReport>,
+    /// Error of each table whose sync failed, by table name
+    pub errors: BTreeMap<String, String>,
+    pub duration: Duration,
+}
+
+impl RunReport {
+    /// Whether every table synced
+    #[allow(dead_code)]
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Documents added and deleted across every table that synced
+    pub fn document_totals(&self) -> (usize, usize) {
+        self.reports.iter().fold((0, 0), |(added, deleted), report| {
+            (added + report.documents_added, deleted + report.documents_deleted)
+        })
+    }
+
+    /// Sort the reports by `order`, largest first for the counts and
+    /// durations. Ties keep the configuration order.
+    pub fn sort_reports(&mut self, order: ReportOrder) {
+        match order {
+            ReportOrder::Config => {}
+            ReportOrder::Name => self.reports.sort_by(|a, b| a.table.cmp(&b.table)),
+            ReportOrder::Documents => self.reports.sort_by_key(|report| Reverse(report.database_documents)),
+            ReportOrder::Errors => self.reports.sort_by_key(|report| Reverse(report.error_count())),
+            ReportOrder::Duration => self.reports.sort_by_key(|report| Reverse(report.timings.total)),
+        }
+    }
+}
+
+/// Outcome of comparing sampled rows of one table with its index
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    pub table: String,
+    pub index: String,
+    /// Number of rows compared
+    pub checked: usize,
+    pub mismatches: Vec<FieldMismatch>,
+}
+
+/// Outcome of rebuilding a table's index as a versioned copy and swapping it in
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CutoverReport {
+    pub table: String,
+    /// The stable index searches use
+    pub index: String,
+    /// The versioned index the table was rebuilt in, which holds the
+    /// previous documents after the swap
+    pub versioned_index: String,
+    /// Whether `versioned_index` was kept after the swap, for a rollback
+    pub kept_previous: bool,
+    /// Report of the full sync into `versioned_index`
+    pub sync: SyncReport,
+}
+
+/// Outcome of `Connector::reload`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReloadReport {
+    /// Tables that started syncing
+    pub added: Vec<String>,
+    /// Tables that stopped syncing
+    pub removed: Vec<String>,
+    /// Tables whose options changed, restarted with their index settings applied again
+    pub changed: Vec<String>,
+    /// Options outside `tables` that changed, as `section.option`, kept as
+    /// they were until a restart
+    pub restart_required: Vec<String>,
+    /// Error setting up the index of each added or changed table, by table name
+    pub errors: BTreeMap<String, String>,
+}
+
+/// Outcome of applying a plan computed earlier
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApplyReport {
+    /// Tables whose rows changed between computing and applying the plan
+    pub changed_tables: Vec<String>,
+    pub indexes: Vec<AppliedIndex>,
+}
+
+/// Changes a plan made to one index
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AppliedIndex {
+    pub index: String,
+    pub documents_added: usize,
+    pub documents_updated: usize,
+    pub documents_deleted: usize,
+}
+
+/// A field whose indexed value differs from the transformed database row
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldMismatch {
+    /// Document ID in the index
+    pub id: String,
+    /// `None` when the whole document is missing from the index
+    pub field: Option<String>,
+    pub database: Option<Value>,
+    pub index: Option<Value>,
+}
+
+/// Latest sync outcome of a table, as summarized by the heartbeat
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TableStatus {
+    /// When the last successful sync finished
+    pub last_sync: Option<SystemTime>,
+    /// Rows of the table at the last successful sync
+    pub documents: usize,
+    /// Documents added and deleted by the last successful sync
+    pub last_changes: usize,
+    pub last_added: usize,
+    pub last_deleted: usize,
+    /// Error of the last sync, cleared by the next successful one
+    pub last_error: Option<String>,
+}
+
+impl TableStatus {
+    /// Record the outcome of a sync
+    pub fn record(&mut self, result: Result<&SyncReport, String>) {
+        match result {
+            Ok(report) => {
+                self.last_sync = Some(SystemTime::now());
+                self.documents = report.database_documents;
+                self.last_changes = report.documents_added + report.documents_deleted;
+                self.last_added = report.documents_added;
+                self.last_deleted = report.documents_deleted;
+                self.last_error = None;
+            }
+            Err(e) => self.last_error = Some(e),
+        }
+    }
+
+    /// One-line summary of the table's state at `now`
+    pub fn summary(&self, now: SystemTime) -> String {
+        let mut summary = match self.last_sync {
+            Some(last_sync) => format!(
+                "last sync {}s ago, {} documents, {} changes",
+                now.duration_since(last_sync).unwrap_or_default().as_secs(),
+                self.documents,
+                self.last_changes,
+            ),
+            None => "not synced yet".to_string(),
+        };
+        if let Some(e) = &self.last_error {
+            summary.push_str(&format!(", last error: {}", e));
+        }
+        summary
+    }
+}
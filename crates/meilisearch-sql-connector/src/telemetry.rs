@@ -0,0 +1,50 @@
+use anyhow::{Context, Result};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{runtime, Resource};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+// Standard OpenTelemetry variables: the collector to export to, and the
+// service name the spans are reported under
+const ENDPOINT_VARS: [&str; 2] = ["OTEL_EXPORTER_OTLP_TRACES_ENDPOINT", "OTEL_EXPORTER_OTLP_ENDPOINT"];
+const SERVICE_NAME_VAR: &str = "OTEL_SERVICE_NAME";
+
+/// Flushes the spans not exported yet when dropped, at exit
+pub struct TelemetryGuard {
+    provider: TracerProvider,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("Failed to export the remaining spans: {}", e);
+        }
+    }
+}
+
+/// Layer exporting the connector's spans over OTLP/HTTP, when a collector
+/// endpoint is set in the environment. The exporter reads the endpoint, its
+/// headers and timeout from the standard `OTEL_EXPORTER_OTLP_*` variables.
+pub fn layer<S>() -> Result<Option<(OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>, TelemetryGuard)>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    if !ENDPOINT_VARS.iter().any(|var| std::env::var_os(var).is_some()) {
+        return Ok(None);
+    }
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .build()
+        .context("Failed to set up the OTLP exporter")?;
+    let service_name = std::env::var(SERVICE_NAME_VAR).unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
+    let resource = Resource::default().merge(&Resource::new([KeyValue::new("service.name", service_name)]));
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(resource)
+        .build();
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+    Ok(Some((tracing_opentelemetry::layer().with_tracer(tracer), TelemetryGuard { provider })))
+}
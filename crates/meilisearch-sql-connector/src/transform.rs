@@ -0,0 +1,167 @@
+use crate::config::HashAlgorithm;
+use crate::error::{ConnectorError, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha512};
+
+// Numbers at or above this magnitude are taken to be milliseconds rather than
+// seconds (1e11 seconds is roughly the year 5138)
+const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+// Text formats SQLite and most drivers produce for DATETIME/TIMESTAMP columns
+const NAIVE_DATETIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M"];
+
+/// Convert a date/time value to unix epoch seconds.
+///
+/// Accepts ISO-8601/RFC 3339 strings, SQLite text dates (`YYYY-MM-DD HH:MM:SS`
+/// and `YYYY-MM-DD`, read as UTC) and unix seconds or milliseconds, either as
+/// numbers or numeric strings. Returns `None` when the value can't be parsed.
+pub fn parse_timestamp(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)).map(normalize_epoch),
+        Value::String(s) => parse_timestamp_str(s.trim()),
+        _ => None,
+    }
+}
+
+fn parse_timestamp_str(s: &str) -> Option<i64> {
+    if s.is_empty() {
+        return None;
+    }
+    if let Ok(n) = s.parse::<i64>() {
+        return Some(normalize_epoch(n));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.timestamp());
+    }
+    for format in NAIVE_DATETIME_FORMATS {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, format) {
+            return Some(dt.and_utc().timestamp());
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc().timestamp())
+}
+
+fn normalize_epoch(n: i64) -> i64 {
+    if n.abs() >= MILLIS_THRESHOLD {
+        n / 1000
+    } else {
+        n
+    }
+}
+
+/// Convert an embedding column to a vector of floats.
+///
+/// Accepts a JSON array of numbers, or a string holding one, as stored by
+/// databases without a native array or JSON type. Returns `None` for anything
+/// else, including empty arrays and arrays with non-numeric entries.
+pub fn parse_vector(value: &Value) -> Option<Vec<f64>> {
+    let parsed;
+    let items = match value {
+        Value::Array(items) => items,
+        Value::String(s) => {
+            parsed = serde_json::from_str::<Value>(s).ok()?;
+            parsed.as_array()?
+        }
+        _ => return None,
+    };
+    if items.is_empty() {
+        return None;
+    }
+    items.iter().map(Value::as_f64).collect()
+}
+
+/// Convert a multi-valued column to a JSON array for faceting.
+///
+/// Strings are split on `separator` and each element is trimmed; elements
+/// that are empty after trimming are dropped, so an empty string gives an
+/// empty array. Arrays are kept as they are and null becomes an empty array.
+/// Returns `None` for other values.
+pub fn split_array(value: &Value, separator: &str) -> Option<Vec<Value>> {
+    match value {
+        Value::Array(items) => Some(items.clone()),
+        Value::Null => Some(Vec::new()),
+        Value::String(s) => Some(s.split(separator)
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(Value::from)
+            .collect()),
+        _ => None,
+    }
+}
+
+/// Hash a sensitive column into a lowercase hex digest of `salt` followed by
+/// the value.
+///
+/// Strings are hashed as they are, other values as their JSON text, so the
+/// same input always gives the same digest and can be looked up by hashing
+/// the query value the same way. Returns `None` for null.
+pub fn hash_value(value: &Value, algorithm: HashAlgorithm, salt: Option<&str>) -> Option<String> {
+    let text = match value {
+        Value::Null => return None,
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let input = format!("{}{}", salt.unwrap_or_default(), text);
+    Some(match algorithm {
+        HashAlgorithm::Sha256 => format!("{:x}", Sha256::digest(input.as_bytes())),
+        HashAlgorithm::Sha512 => format!("{:x}", Sha512::digest(input.as_bytes())),
+        HashAlgorithm::Blake3 => blake3::hash(input.as_bytes()).to_hex().to_string(),
+    })
+}
+
+/// Columns a reference template such as `https://cdn.example.com/{id}`
+/// refers to, the names between braces
+pub fn template_placeholders(template: &str) -> Vec<&str> {
+    template.split('{').skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+/// Fill in a reference template with the values of a row's columns, strings
+/// as they are and other values as their JSON text. Returns `None` when a
+/// placeholder's column is missing or null, since the reference would be
+/// incomplete.
+pub fn render_template(template: &str, record: &serde_json::Map<String, Value>) -> Option<String> {
+    let mut rendered = template.to_string();
+    for name in template_placeholders(template) {
+        let text = match record.get(name)? {
+            Value::Null => return None,
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        rendered = rendered.replace(&format!("{{{}}}", name), &text);
+    }
+    Some(rendered)
+}
+
+/// Compile the JSON Schema file of a table's `document_schema`
+pub fn load_document_schema(path: &str) -> Result<jsonschema::Validator> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| ConnectorError::Config(format!("Could not read document schema {}: {}", path, e)))?;
+    let schema: Value = serde_json::from_str(&contents)
+        .map_err(|e| ConnectorError::Config(format!("Document schema {} is not valid JSON: {}", path, e)))?;
+    jsonschema::validator_for(&schema)
+        .map_err(|e| ConnectorError::Config(format!("Document schema {} is not a valid JSON Schema: {}", path, e)))
+}
+
+/// Fields of `document` violating `schema`, each with the reason. A missing
+/// required field is named after itself; violations of the document as a
+/// whole are named `(document)`.
+pub fn schema_violations(schema: &jsonschema::Validator, document: &Value) -> Vec<(String, String)> {
+    schema.iter_errors(document)
+        .map(|error| {
+            let field = match &error.kind {
+                jsonschema::error::ValidationErrorKind::Required { property } => {
+                    property.as_str().map(String::from).unwrap_or_else(|| property.to_string())
+                }
+                _ => error.instance_path.as_str().split('/').nth(1).unwrap_or_default().to_string(),
+            };
+            let field = if field.is_empty() { "(document)".to_string() } else { field };
+            (field, error.to_string())
+        })
+        .collect()
+}
@@ -0,0 +1,87 @@
+use crate::report::TableStatus;
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+// ANSI sequence clearing from the cursor to the end of the screen
+const CLEAR_BELOW: &str = "\x1b[J";
+
+/// Console view of every table's latest sync for `run --watch`. On a
+/// terminal the status lines are redrawn in place; otherwise a line is
+/// written whenever a table's status changes, which suits pipes and log files.
+pub struct StatusView {
+    redraw: bool,
+    // Lines drawn by the last redraw
+    drawn: usize,
+    // Status last written for each table, when not redrawing
+    written: BTreeMap<String, TableStatus>,
+}
+
+impl StatusView {
+    pub fn new(redraw: bool) -> Self {
+        Self { redraw, drawn: 0, written: BTreeMap::new() }
+    }
+
+    /// Text to write to show `status` as of `now`, empty when nothing changed
+    /// since the last call in plain mode
+    pub fn render(&mut self, status: &BTreeMap<String, TableStatus>, now: SystemTime) -> String {
+        let mut output = String::new();
+        if self.redraw {
+            if self.drawn > 0 {
+                // Back to the first line of the previous redraw
+                output.push_str(&format!("\x1b[{}A", self.drawn));
+            }
+            output.push_str(CLEAR_BELOW);
+            let width = status.keys().map(|table| table.chars().count()).max().unwrap_or_default();
+            for (table, table_status) in status {
+                let table = format!("{:<width$}", table, width = width);
+                output.push_str(&format!("{}  {}\n", table.bold(), live_line(table_status, now)));
+            }
+            self.drawn = status.len();
+            return output;
+        }
+        for (table, table_status) in status {
+            if self.written.get(table) == Some(table_status) {
+                continue;
+            }
+            output.push_str(&format!("{}: {}\n", table, plain_line(table_status)));
+            self.written.insert(table.clone(), table_status.clone());
+        }
+        output
+    }
+}
+
+fn live_line(status: &TableStatus, now: SystemTime) -> String {
+    let mut line = match status.last_sync {
+        Some(last_sync) => format!(
+            "{} {}  {} documents  synced {}s ago",
+            format!("+{}", status.last_added).green(),
+            format!("-{}", status.last_deleted).red(),
+            status.documents,
+            now.duration_since(last_sync).unwrap_or_default().as_secs(),
+        ),
+        None => "not synced yet".dimmed().to_string(),
+    };
+    if let Some(e) = &status.last_error {
+        line.push_str(&format!("  {}", format!("error: {}", e).red().bold()));
+    }
+    line
+}
+
+fn plain_line(status: &TableStatus) -> String {
+    let mut line = match status.last_sync {
+        Some(last_sync) => format!(
+            "+{} -{}, {} documents, synced at {}",
+            status.last_added,
+            status.last_deleted,
+            status.documents,
+            DateTime::<Local>::from(last_sync).format("%Y-%m-%d %H:%M:%S"),
+        ),
+        None => "not synced yet".to_string(),
+    };
+    if let Some(e) = &status.last_error {
+        line.push_str(&format!(", error: {}", e));
+    }
+    line
+}
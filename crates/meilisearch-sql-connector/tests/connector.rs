@@ -2,50 +2,162 @@
 mod utils;
 use utils::start_meilisearch;
 use meilisearch_sql_connector::{
-    config::{Config, DatabaseConfig, MeilisearchConfig, TableConfig},
+    breaker::BreakerState,
+    common::{InMemoryDatabaseAdapter, InMemoryMeilisearchClient},
+    plan::{PlannedDocument, PlannedUpdate, SyncPlan},
+    config::{
+        Config, DatabaseConfig, HashAlgorithm, LookupConfig, LookupMissing, MeilisearchConfig, NullHandling, OperationOrder,
+        PrimaryKeyType, TableConfig, TransformErrorPolicy,
+    },
+    connector::Connector,
     error::{ConnectorError, Result},
     meilisearch::MeilisearchClientTrait,
-    database::DatabaseAdapter,
+    database::{sqlite::SqliteAdapter, DatabaseAdapter, RowFilters},
+    queue::DurableQueue,
+    report::{ReportOrder, RunReport, SyncReport},
+    transform::hash_value,
 };
 use async_trait::async_trait;
+use futures_util::stream::BoxStream;
 use meilisearch_sdk::settings::Settings;
 use serde_json::{json, Value};
-use std::sync::Arc;
+use sqlx::Connection;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 // --- Mock implementations ---
-pub struct MockMeilisearchClient;
-
-impl MockMeilisearchClient {
-    pub fn new() -> Self {
-        Self
-    }
+// Keeps documents in memory and can be told to reject writes
+#[derive(Default)]
+pub struct FlakyMeilisearchClient {
+    documents: Mutex<Vec<Value>>,
+    fail_writes: AtomicBool,
+    primary_key: Mutex<Option<String>>,
+    raw_settings: Mutex<Option<Value>>,
+    // "add <ids>" / "delete <ids>" for every write, in the order received
+    operations: Mutex<Vec<String>>,
+    // Ids of documents add_documents_checked refuses, with the rest of their batch
+    rejected_ids: Mutex<Vec<String>>,
 }
 
 #[async_trait]
-impl MeilisearchClientTrait for MockMeilisearchClient {
-    async fn setup_index(&self, _index_name: &str, _settings: Settings, _primary_key: Option<&str>) -> Result<()> {
+impl MeilisearchClientTrait for FlakyMeilisearchClient {
+    async fn setup_index(&self, _index_name: &str, _settings: Settings, primary_key: Option<&str>) -> Result<()> {
+        let mut current = self.primary_key.lock().unwrap();
+        if current.is_none() {
+            *current = primary_key.map(str::to_string);
+        }
+        Ok(())
+    }
+
+    async fn index_primary_key(&self, _index_name: &str) -> Result<Option<String>> {
+        Ok(self.primary_key.lock().unwrap().clone())
+    }
+
+    async fn delete_index(&self, _index_name: &str) -> Result<()> {
+        self.documents.lock().unwrap().clear();
+        *self.primary_key.lock().unwrap() = None;
+        Ok(())
+    }
+
+    async fn set_raw_settings(&self, _index_name: &str, settings: &Value) -> Result<()> {
+        *self.raw_settings.lock().unwrap() = Some(settings.clone());
         Ok(())
     }
 
     async fn get_all_documents(&self, _index_name: &str) -> Result<Vec<Value>> {
-        Ok(vec![])
+        Ok(self.documents.lock().unwrap().clone())
     }
 
-    async fn add_or_update_documents(&self, _index_name: &str, _documents: Vec<Value>, _batch_size: Option<usize>) -> Result<()> {
+    async fn get_document(&self, _index_name: &str, id: &str) -> Result<Option<Value>> {
+        Ok(self.documents.lock().unwrap().iter()
+            .find(|doc| doc["id"].to_string().trim_matches('"') == id)
+            .cloned())
+    }
+
+    async fn add_or_update_documents(&self, _index_name: &str, documents: Vec<Value>, _batch_size: Option<usize>) -> Result<()> {
+        if self.fail_writes.load(Ordering::SeqCst) {
+            return Err(ConnectorError::Meilisearch("unreachable".to_string()));
+        }
+        let ids: Vec<String> = documents.iter().map(|doc| doc["id"].to_string()).collect();
+        self.operations.lock().unwrap().push(format!("add {}", ids.join(",")));
+        self.documents.lock().unwrap().extend(documents);
         Ok(())
     }
 
-    async fn delete_documents(&self, _index_name: &str, _ids: &[String], _batch_size: Option<usize>) -> Result<()> {
+    async fn add_documents_checked(&self, index_name: &str, documents: Vec<Value>) -> Result<()> {
+        if self.fail_writes.load(Ordering::SeqCst) {
+            return Err(ConnectorError::Meilisearch("unreachable".to_string()));
+        }
+        let rejected_ids = self.rejected_ids.lock().unwrap().clone();
+        if documents.iter().any(|doc| rejected_ids.contains(&doc["id"].to_string())) {
+            return Err(ConnectorError::DocumentsRejected("invalid document".to_string()));
+        }
+        self.add_or_update_documents(index_name, documents, None).await
+    }
+
+    async fn delete_documents(&self, _index_name: &str, ids: &[String], _batch_size: Option<usize>) -> Result<()> {
+        if self.fail_writes.load(Ordering::SeqCst) {
+            return Err(ConnectorError::Meilisearch("unreachable".to_string()));
+        }
+        self.operations.lock().unwrap().push(format!("delete {}", ids.join(",")));
+        self.documents.lock().unwrap().retain(|doc| !ids.contains(&doc["id"].to_string()));
+        Ok(())
+    }
+    async fn health(&self) -> Result<()> {
+        if self.fail_writes.load(Ordering::SeqCst) {
+            return Err(ConnectorError::Meilisearch("unreachable".to_string()));
+        }
         Ok(())
     }
 }
 
+// Lets a test keep a handle on an adapter it hands to the connector
+struct SharedAdapter<T>(Arc<T>);
+
+#[async_trait]
+impl<T: DatabaseAdapter> DatabaseAdapter for SharedAdapter<T> {
+    async fn fetch_all_records(&self, table: &str) -> Result<Vec<Value>> {
+        self.0.fetch_all_records(table).await
+    }
+
+    fn fetch_records_stream<'a>(&'a self, table: &'a str, columns: Option<&'a [String]>) -> BoxStream<'a, Result<Value>> {
+        self.0.fetch_records_stream(table, columns)
+    }
+
+    async fn fetch_records_ordered(&self, table: &str, columns: Option<&[String]>, order_by: &str) -> Result<Option<Vec<Value>>> {
+        self.0.fetch_records_ordered(table, columns, order_by).await
+    }
+
+    async fn fetch_records_since(&self, table: &str, columns: Option<&[String]>, column: &str, since: &Value) -> Result<Option<Vec<Value>>> {
+        self.0.fetch_records_since(table, columns, column, since).await
+    }
+
+    async fn get_all_tables(&self) -> Result<Vec<String>> {
+        self.0.get_all_tables().await
+    }
+
+    async fn get_table_columns(&self, table: &str) -> Result<Vec<(String, String, bool)>> {
+        self.0.get_table_columns(table).await
+    }
+
+    async fn get_primary_key(&self, table: &str) -> Result<String> {
+        self.0.get_primary_key(table).await
+    }
+
+    async fn replication_lag(&self) -> Result<Option<Duration>> {
+        self.0.replication_lag().await
+    }
+}
+
 // Simple mock for the database adapter
 pub struct MockSqliteAdapter {
     get_all_tables_result: Vec<String>,
     get_table_columns_result: Vec<(String, String, bool)>,
     get_primary_key_result: String,
     fetch_all_records_result: Vec<Value>,
+    fetch_failures: AtomicUsize,
+    fetch_calls: Arc<AtomicUsize>,
 }
 
 impl MockSqliteAdapter {
@@ -64,6 +176,8 @@ impl MockSqliteAdapter {
                 "field1": "test value",
                 "field2": "another test"
             })],
+            fetch_failures: AtomicUsize::new(0),
+            fetch_calls: Arc::new(AtomicUsize::new(0)),
         }
     }
     
@@ -72,6 +186,21 @@ impl MockSqliteAdapter {
         self.get_all_tables_result = vec![];
         self
     }
+
+    pub fn with_records(mut self, records: Vec<Value>) -> Self {
+        self.fetch_all_records_result = records;
+        self
+    }
+
+    // Fail the first `count` fetches, as a flaky database would
+    pub fn with_fetch_failures(self, count: usize) -> Self {
+        self.fetch_failures.store(count, Ordering::SeqCst);
+        self
+    }
+
+    pub fn fetch_calls(&self) -> Arc<AtomicUsize> {
+        self.fetch_calls.clone()
+    }
 }
 
 #[async_trait]
@@ -89,15 +218,19 @@ impl DatabaseAdapter for MockSqliteAdapter {
     }
     
     async fn fetch_all_records(&self, _table: &str) -> Result<Vec<Value>> {
+        self.fetch_calls.fetch_add(1, Ordering::SeqCst);
+        if self.fetch_failures.load(Ordering::SeqCst) > 0 {
+            self.fetch_failures.fetch_sub(1, Ordering::SeqCst);
+            return Err(ConnectorError::Database("connection reset".to_string()));
+        }
         Ok(self.fetch_all_records_result.clone())
     }
 }
 // --- End mock implementations ---
 
-#[allow(dead_code)]
 fn create_test_config() -> Config {
     Config {
-        meilisearch: MeilisearchConfig { host: "http://localhost:7701".to_string(), api_key: None },
+        meilisearch: MeilisearchConfig { host: "http://localhost:7701".to_string(), api_key: None, ..Default::default() },
         database: DatabaseConfig {
             type_: "sqlite".to_string(),
             connection_string: "test.db".to_string(),
@@ -106,15 +239,17 @@ fn create_test_config() -> Config {
                 name: "test".to_string(),
                 primary_key: "id".to_string(),
                 index_name: Some("test_index".to_string()),
-                fields_to_index: vec!["id".to_string()],
+                fields_to_index: Vec::new(),
                 watch_for_changes: true,
                 searchable_attributes: Some(vec!["field1".to_string()]),
                 ranking_rules: None,
                 typo_tolerance: None,
+                ..Default::default()
             }],
             connection_pool_size: 5,
             max_concurrent_batches: 5,
             document_batch_size: 100,
+            ..Default::default()
         },
     }
 }
@@ -127,6 +262,7 @@ async fn test_connector_initialization() -> Result<()> {
         meilisearch: MeilisearchConfig {
             host: "http://localhost:7701".to_string(),
             api_key: Some("test_key".to_string()),
+            ..Default::default()
         },
         database: DatabaseConfig {
             type_: "sqlite".to_string(),
@@ -141,16 +277,18 @@ async fn test_connector_initialization() -> Result<()> {
                 searchable_attributes: Some(vec!["field1".to_string()]),
                 ranking_rules: None,
                 typo_tolerance: None,
+                ..Default::default()
             }],
             connection_pool_size: 5,
             max_concurrent_batches: 5,
             document_batch_size: 100,
+            ..Default::default()
         },
     };
 
     // Use our mock with default successful configuration
     let _mock_db = MockSqliteAdapter::new().await;
-    let _mock_meili = Arc::new(MockMeilisearchClient::new());
+    let _mock_meili = Arc::new(InMemoryMeilisearchClient::new());
     
     println!("Creating connector...");
     // Just test that we can create the connector
@@ -162,10 +300,11 @@ async fn test_connector_initialization() -> Result<()> {
 
 #[tokio::test]
 async fn test_connector_with_empty_tables() {
-    let _config = Config {
+    let config = Config {
         meilisearch: MeilisearchConfig { 
             host: "http://localhost:7701".to_string(), 
-            api_key: None 
+            api_key: None,
+            ..Default::default()
         },
         database: DatabaseConfig {
             type_: "sqlite".to_string(),
@@ -175,16 +314,30 @@ async fn test_connector_with_empty_tables() {
             connection_pool_size: 5,
             max_concurrent_batches: 5,
             document_batch_size: 100,
+            ..Default::default()
         },
     };
 
-    println!("Testing that empty tables configuration is accepted...");
-    let _mock_db = MockSqliteAdapter::new().await.with_empty_tables();
-    let _mock_meili = Arc::new(MockMeilisearchClient::new());
-    
-    // Test that connector creation succeeds
-    // let _connector = Connector::with_mocks(config, Box::new(mock_db), mock_meili);
-    println!("Successfully created connector with empty tables");
+    // By default a config without tables is rejected instead of idling
+    let connector = Connector::with_clients(
+        config.clone(),
+        Box::new(MockSqliteAdapter::new().await.with_empty_tables()),
+        Arc::new(InMemoryMeilisearchClient::new()),
+    );
+    match connector.start().await {
+        Err(ConnectorError::Config(msg)) => assert!(msg.contains("no syncable tables")),
+        other => panic!("Expected a config error, got {:?}", other),
+    }
+
+    // Embedders can opt into the permissive behavior, where the connector just waits
+    let connector = Connector::with_clients(
+        config,
+        Box::new(MockSqliteAdapter::new().await.with_empty_tables()),
+        Arc::new(InMemoryMeilisearchClient::new()),
+    )
+    .with_require_tables(false);
+    let result = tokio::time::timeout(std::time::Duration::from_millis(200), connector.start()).await;
+    assert!(result.is_err(), "Connector should keep running without tables");
 }
 
 #[tokio::test]
@@ -193,6 +346,7 @@ async fn test_connector_stop_mechanism() -> Result<()> {
         meilisearch: MeilisearchConfig {
             host: "http://localhost:7701".to_string(),
             api_key: Some("test_key".to_string()),
+            ..Default::default()
         },
         database: DatabaseConfig {
             type_: "sqlite".to_string(),
@@ -207,16 +361,18 @@ async fn test_connector_stop_mechanism() -> Result<()> {
                 searchable_attributes: Some(vec!["field1".to_string()]),
                 ranking_rules: None,
                 typo_tolerance: None,
+                ..Default::default()
             }],
             connection_pool_size: 5,
             max_concurrent_batches: 5,
             document_batch_size: 100,
+            ..Default::default()
         },
     };
 
     // Use our mock with default successful configuration
     let _mock_db = MockSqliteAdapter::new().await;
-    let _mock_meili = Arc::new(MockMeilisearchClient::new());
+    let _mock_meili = Arc::new(InMemoryMeilisearchClient::new());
     
     println!("Creating connector for stop test...");
     // let connector = Arc::new(Connector::with_mocks(config, Box::new(mock_db), mock_meili));
@@ -224,3 +380,1721 @@ async fn test_connector_stop_mechanism() -> Result<()> {
     // ... dependent logic ...
     Ok(())
 }
+
+#[tokio::test]
+async fn test_sync_reports_field_sizes() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.warn_field_bytes = Some(11);
+
+    let connector = Connector::with_clients(
+        config,
+        Box::new(MockSqliteAdapter::new().await),
+        Arc::new(InMemoryMeilisearchClient::new()),
+    );
+    let reports = connector.sync_once().await?;
+
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0];
+    assert_eq!(report.documents_added, 1);
+    // "another test" is the largest field and the only one above the threshold
+    assert_eq!(report.max_field_bytes, 12);
+    assert_eq!(report.max_field_name.as_deref(), Some("field2"));
+    assert_eq!(report.oversized_fields, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_transforms_large_batches_in_parallel() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.warn_field_bytes = Some(40);
+
+    // Enough rows to go through the parallel transform, with one oversized field
+    let records = (1..=2500)
+        .map(|id| {
+            let field1 = if id == 1234 { "x".repeat(50) } else { format!("row {}", id) };
+            json!({ "id": id, "field1": field1 })
+        })
+        .collect();
+    let connector = Connector::with_clients(
+        config,
+        Box::new(MockSqliteAdapter::new().await.with_records(records)),
+        Arc::new(InMemoryMeilisearchClient::new()),
+    );
+    let reports = connector.sync_once().await?;
+
+    let report = &reports[0];
+    assert_eq!(report.documents_added, 2500);
+    assert_eq!(report.max_field_bytes, 50);
+    assert_eq!(report.max_field_name.as_deref(), Some("field1"));
+    assert_eq!(report.oversized_fields, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_apply_settings() -> Result<()> {
+    let connector = Connector::with_clients(
+        create_test_config(),
+        Box::new(MockSqliteAdapter::new().await),
+        Arc::new(InMemoryMeilisearchClient::new()),
+    );
+
+    assert_eq!(connector.apply_settings(None).await?, vec!["test_index".to_string()]);
+    assert_eq!(connector.apply_settings(Some("test")).await?, vec!["test_index".to_string()]);
+    assert!(matches!(connector.apply_settings(Some("missing")).await, Err(ConnectorError::Config(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_cutoff_ms() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.tables[0].search_cutoff_ms = Some(150);
+    let config: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+    assert_eq!(config.database.tables[0].search_cutoff_ms, Some(150));
+
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(config, Box::new(MockSqliteAdapter::new().await), meilisearch.clone());
+    connector.apply_settings(None).await?;
+    assert_eq!(meilisearch.settings("test_index").unwrap().search_cutoff_ms, Some(150));
+
+    // Left unset, the index keeps Meilisearch's default
+    let connector = Connector::with_clients(
+        create_test_config(),
+        Box::new(MockSqliteAdapter::new().await),
+        meilisearch.clone(),
+    );
+    connector.apply_settings(None).await?;
+    assert_eq!(meilisearch.settings("test_index").unwrap().search_cutoff_ms, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_filterable_and_sortable_attributes() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.tables[0].filterable_attributes = vec!["status".to_string()];
+    config.database.tables[0].sortable_attributes = vec!["created_at".to_string()];
+    config.database.tables[0].constant_fields.insert("source".to_string(), json!("legacy_db"));
+
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(config, Box::new(MockSqliteAdapter::new().await), meilisearch.clone());
+    connector.apply_settings(None).await?;
+    let settings = meilisearch.settings("test_index").unwrap();
+    assert_eq!(settings.filterable_attributes, Some(vec!["source".to_string(), "status".to_string()]));
+    assert_eq!(settings.sortable_attributes, Some(vec!["created_at".to_string()]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_timestamp() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.tables[0].add_sync_timestamp = true;
+    let records = vec![json!({ "id": 1, "field1": "a" }), json!({ "id": 2, "field1": "b" })];
+
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let db = MockSqliteAdapter::new().await.with_records(records);
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    connector.apply_settings(None).await?;
+    let settings = meilisearch.settings("test_index").unwrap();
+    assert_eq!(settings.filterable_attributes, Some(vec!["_synced_at".to_string()]));
+    assert_eq!(settings.sortable_attributes, Some(vec!["_synced_at".to_string()]));
+
+    // Every document of a sync gets the same time
+    connector.sync_once().await?;
+    let first = meilisearch.get_document("test_index", "1").await?.unwrap();
+    let second = meilisearch.get_document("test_index", "2").await?.unwrap();
+    let synced_at = first["_synced_at"].as_str().unwrap();
+    assert!(chrono::DateTime::parse_from_rfc3339(synced_at).is_ok());
+    assert!(synced_at.ends_with('Z'));
+    assert_eq!(second["_synced_at"], first["_synced_at"]);
+
+    // The field isn't a column, so verify leaves it out
+    assert!(connector.verify(None, 10).await?[0].mismatches.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_source_table() -> Result<()> {
+    let records = vec![json!({ "id": 1, "field1": "a" })];
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let db = MockSqliteAdapter::new().await.with_records(records.clone());
+    let connector = Connector::with_clients(create_test_config(), Box::new(db), meilisearch.clone());
+    connector.setup().await?;
+    connector.sync_once().await?;
+    assert!(meilisearch.get_document("test_index", "1").await?.unwrap().get("_source_table").is_none());
+
+    let mut config = create_test_config();
+    config.database.tables[0].add_source_table = true;
+    let records = [records, vec![json!({ "id": 2, "field1": "b" })]].concat();
+    let db = MockSqliteAdapter::new().await.with_records(records);
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    connector.setup().await?;
+    let settings = meilisearch.settings("test_index").unwrap();
+    assert_eq!(settings.filterable_attributes, Some(vec!["_source_table".to_string()]));
+
+    // The field isn't part of change detection, so only the new row is sent
+    let run = connector.sync_all_once().await;
+    assert_eq!(run.document_totals(), (1, 0));
+    let document = meilisearch.get_document("test_index", "2").await?.unwrap();
+    assert_eq!(document["_source_table"], "test");
+    assert!(meilisearch.get_document("test_index", "1").await?.unwrap().get("_source_table").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_duplicate_documents() -> Result<()> {
+    // A join repeating the same order under two ids
+    let records = vec![
+        json!({ "id": 1, "field1": "order 7" }),
+        json!({ "id": 2, "field1": "order 7" }),
+        json!({ "id": 3, "field1": "order 8" }),
+    ];
+    let sync = |detect: bool, suppress: bool| {
+        let records = records.clone();
+        async move {
+            let mut config = create_test_config();
+            config.database.tables[0].detect_duplicates = detect;
+            config.database.tables[0].suppress_duplicates = suppress;
+            let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+            let db = MockSqliteAdapter::new().await.with_records(records);
+            let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+            connector.apply_settings(None).await?;
+            let report = connector.sync_once().await?.remove(0);
+            Ok::<_, ConnectorError>((report, meilisearch))
+        }
+    };
+
+    let (report, _) = sync(false, false).await?;
+    assert_eq!(report.duplicate_documents, 0);
+
+    // Detection only reports
+    let (report, meilisearch) = sync(true, false).await?;
+    assert_eq!(report.duplicate_documents, 1);
+    assert!(meilisearch.get_document("test_index", "1").await?.unwrap().get("_content_hash").is_none());
+    assert_eq!(meilisearch.settings("test_index").unwrap().distinct_attribute, None);
+
+    // Suppression makes the content hash the distinct attribute
+    let (report, meilisearch) = sync(false, true).await?;
+    assert_eq!(report.duplicate_documents, 1);
+    let hash = |id: &'static str| {
+        let meilisearch = meilisearch.clone();
+        async move { Ok::<_, ConnectorError>(meilisearch.get_document("test_index", id).await?.unwrap()["_content_hash"].clone()) }
+    };
+    assert_eq!(hash("1").await?, hash("2").await?);
+    assert_ne!(hash("1").await?, hash("3").await?);
+    assert_eq!(meilisearch.settings("test_index").unwrap().distinct_attribute, Some(Some("_content_hash".to_string())));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_durable_queue_survives_restart() -> Result<()> {
+    let tmp_dir = std::env::current_dir().unwrap().join("tmp");
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+    let queue_path = tmp_dir.join(format!("durable_queue_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&queue_path);
+    let queue_path = queue_path.to_str().unwrap().to_string();
+
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    meilisearch.fail_writes.store(true, Ordering::SeqCst);
+
+    // Meilisearch rejects the write, the document stays queued on disk
+    let connector = Connector::with_clients(
+        create_test_config(),
+        Box::new(MockSqliteAdapter::new().await),
+        meilisearch.clone(),
+    )
+    .with_durable_queue(DurableQueue::open(&queue_path).await?);
+    assert!(connector.sync_once().await.is_err());
+    assert_eq!(DurableQueue::open(&queue_path).await?.len().await?, 1);
+
+    // After a restart the queued operation is sent before diffing again
+    meilisearch.fail_writes.store(false, Ordering::SeqCst);
+    let connector = Connector::with_clients(
+        create_test_config(),
+        Box::new(MockSqliteAdapter::new().await),
+        meilisearch.clone(),
+    )
+    .with_durable_queue(DurableQueue::open(&queue_path).await?);
+    let reports = connector.sync_once().await?;
+
+    assert_eq!(reports[0].documents_added, 0);
+    assert_eq!(meilisearch.documents.lock().unwrap().len(), 1);
+    assert_eq!(DurableQueue::open(&queue_path).await?.len().await?, 0);
+
+    let _ = std::fs::remove_file(&queue_path);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_disabled_table_is_not_synced() -> Result<()> {
+    let mut config = create_test_config();
+    let mut disabled = config.database.tables[0].clone();
+    disabled.name = "archived".to_string();
+    disabled.index_name = Some("archived".to_string());
+    disabled.enabled = Some(false);
+    config.database.tables.push(disabled);
+
+    let connector = Connector::with_clients(
+        config,
+        Box::new(MockSqliteAdapter::new().await),
+        Arc::new(InMemoryMeilisearchClient::new()),
+    );
+
+    // Only the enabled table is synced or gets a sync task
+    let reports = connector.sync_once().await?;
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].table, "test");
+
+    let running = connector.clone();
+    let handle = tokio::spawn(async move { running.start().await });
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    assert_eq!(connector.active_sync_tasks(), 1);
+
+    connector.stop().await?;
+    handle.await.unwrap()?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_retries_within_poll_cycle() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.poll_interval_seconds = Some(60);
+    config.database.sync_retries = 2;
+    config.database.sync_retry_delay_seconds = 0;
+
+    let adapter = MockSqliteAdapter::new().await.with_fetch_failures(2);
+    let fetch_calls = adapter.fetch_calls();
+    let connector = Connector::with_clients(config, Box::new(adapter), Arc::new(InMemoryMeilisearchClient::new()));
+
+    let running = connector.clone();
+    let handle = tokio::spawn(async move { running.start().await });
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+
+    // Two failed attempts and the successful retry, all before the next poll
+    assert_eq!(fetch_calls.load(Ordering::SeqCst), 3);
+
+    connector.stop().await?;
+    handle.await.unwrap()?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_all_once() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.sync_retries = 0;
+    let mut second = config.database.tables[0].clone();
+    second.name = "second".to_string();
+    second.index_name = Some("second".to_string());
+    config.database.tables.push(second);
+
+    // The first table's fetch fails, which doesn't keep the second from syncing
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let adapter = MockSqliteAdapter::new().await.with_fetch_failures(1);
+    let connector = Connector::with_clients(config, Box::new(adapter), meilisearch.clone());
+    connector.setup().await?;
+    let run = connector.sync_all_once().await;
+    assert!(!run.is_success());
+    assert!(run.errors["test"].contains("connection reset"));
+    assert_eq!(run.reports.len(), 1);
+    assert_eq!(run.reports[0].table, "second");
+    assert_eq!(meilisearch.document_ids("second"), ["1"]);
+    assert_eq!(run.document_totals(), (1, 0));
+    assert!(connector.table_status()["test"].last_error.is_some());
+
+    // Nothing was spawned, and the next pass is up to the caller
+    assert_eq!(connector.active_sync_tasks(), 0);
+    let run = connector.sync_all_once().await;
+    assert!(run.is_success());
+    assert_eq!(run.reports.len(), 2);
+    assert_eq!(meilisearch.document_ids("test_index"), ["1"]);
+    Ok(())
+}
+
+#[test]
+fn test_sort_reports() {
+    let report = |table: &str, documents: usize, skipped: usize, millis: u64| {
+        let mut report = SyncReport { table: table.to_string(), database_documents: documents, documents_skipped: skipped, ..Default::default() };
+        report.timings.total = std::time::Duration::from_millis(millis);
+        report
+    };
+    let mut run = RunReport {
+        reports: vec![report("users", 10, 0, 30), report("orders", 500, 2, 10), report("audit", 80, 0, 900)],
+        ..Default::default()
+    };
+    run.reports[0].decode_errors.insert("bio".to_string(), 3);
+    let order = |run: &mut RunReport, order: ReportOrder| {
+        run.sort_reports(order);
+        run.reports.iter().map(|report| report.table.clone()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(order(&mut run, ReportOrder::Config), ["users", "orders", "audit"]);
+    assert_eq!(order(&mut run, ReportOrder::Name), ["audit", "orders", "users"]);
+    assert_eq!(order(&mut run, ReportOrder::Documents), ["orders", "audit", "users"]);
+    assert_eq!(order(&mut run, ReportOrder::Errors), ["users", "orders", "audit"]);
+    assert_eq!(order(&mut run, ReportOrder::Duration), ["audit", "users", "orders"]);
+}
+
+#[tokio::test]
+async fn test_constant_fields_added_to_documents() -> Result<()> {
+    let mut config = create_test_config();
+    let table = &mut config.database.tables[0];
+    table.constant_fields.insert("source".to_string(), json!("legacy_db"));
+    table.constant_fields.insert("field1".to_string(), json!("constant"));
+    table.constant_fields.insert("_index_version".to_string(), json!(3));
+
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let connector = Connector::with_clients(config, Box::new(MockSqliteAdapter::new().await), meilisearch.clone());
+    connector.sync_once().await?;
+
+    let documents = meilisearch.documents.lock().unwrap();
+    assert_eq!(documents[0]["source"], json!("legacy_db"));
+    assert_eq!(documents[0]["_index_version"], json!(3));
+    // Real columns win unless constant_fields_override is set
+    assert_eq!(documents[0]["field1"], json!("test value"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_primary_key_mismatch() -> Result<()> {
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    *meilisearch.primary_key.lock().unwrap() = Some("uuid".to_string());
+
+    // Without the opt-in the mismatch is reported instead of failing later on writes
+    let connector = Connector::with_clients(
+        create_test_config(),
+        Box::new(MockSqliteAdapter::new().await),
+        meilisearch.clone(),
+    );
+    match connector.apply_settings(None).await {
+        Err(ConnectorError::Config(message)) => assert!(message.contains("reindex_on_primary_key_change")),
+        other => panic!("expected a config error, got {:?}", other),
+    }
+
+    let mut config = create_test_config();
+    config.database.tables[0].reindex_on_primary_key_change = true;
+    let connector = Connector::with_clients(config, Box::new(MockSqliteAdapter::new().await), meilisearch.clone());
+    connector.apply_settings(None).await?;
+    assert_eq!(meilisearch.primary_key.lock().unwrap().as_deref(), Some("id"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_raw_settings_passthrough() -> Result<()> {
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let mut config = create_test_config();
+    config.database.tables[0].raw_settings = Some(json!({
+        "searchableAttributes": ["ignored"],
+        "proximityPrecision": "byAttribute",
+    }));
+
+    let connector = Connector::with_clients(config, Box::new(MockSqliteAdapter::new().await), meilisearch.clone());
+    connector.apply_settings(None).await?;
+
+    // searchable_attributes is set in the table config, so it wins over the raw value
+    assert_eq!(*meilisearch.raw_settings.lock().unwrap(), Some(json!({ "proximityPrecision": "byAttribute" })));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_operation_order() -> Result<()> {
+    for (order, expected) in [
+        (OperationOrder::DeleteThenAdd, ["delete 9", "add 2"]),
+        (OperationOrder::AddThenDelete, ["add 2", "delete 9"]),
+    ] {
+        // Row 1 is indexed and still in the database, row 9 was removed from it
+        let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+        meilisearch.documents.lock().unwrap().extend([json!({ "id": 1 }), json!({ "id": 9 })]);
+        let db = MockSqliteAdapter::new().await.with_records(vec![json!({ "id": 1 }), json!({ "id": 2 })]);
+
+        let mut config = create_test_config();
+        config.database.operation_order = order;
+        let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+        connector.sync_once().await?;
+
+        // IDs still in the database are never part of the delete set
+        assert_eq!(*meilisearch.operations.lock().unwrap(), expected);
+        let mut ids: Vec<String> = meilisearch.documents.lock().unwrap().iter().map(|doc| doc["id"].to_string()).collect();
+        ids.sort();
+        assert_eq!(ids, ["1", "2"]);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vectors_passthrough() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.tables[0].vector_field = Some("embedding".to_string());
+    config.database.tables[0].embedder_name = Some("default".to_string());
+
+    let db = MockSqliteAdapter::new().await.with_records(vec![
+        json!({ "id": 1, "embedding": [0.1, 0.2, 0.3] }),
+        json!({ "id": 2, "embedding": "[0.4, 0.5, 0.6]" }),
+        json!({ "id": 3, "embedding": "not a vector" }),
+        json!({ "id": 4, "embedding": [1.0] }),
+        json!({ "id": 5, "embedding": null }),
+    ]);
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    let reports = connector.sync_once().await?;
+
+    let mut documents = meilisearch.documents.lock().unwrap().clone();
+    documents.sort_by_key(|doc| doc["id"].as_i64());
+    // Malformed vectors and the odd dimension out are skipped, missing vectors are fine
+    assert_eq!(documents, vec![
+        json!({ "id": 1, "_vectors": { "default": [0.1, 0.2, 0.3] } }),
+        json!({ "id": 2, "_vectors": { "default": [0.4, 0.5, 0.6] } }),
+        json!({ "id": 5 }),
+    ]);
+    assert_eq!(reports[0].documents_skipped, 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_changed_vectors() -> Result<()> {
+    let db = Arc::new(InMemoryDatabaseAdapter::new().with_table("test", "id", vec![
+        json!({ "id": 1, "field1": "a", "embedding": [0.1, 0.2] }),
+    ]));
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let mut config = create_test_config();
+    config.database.tables[0].vector_field = Some("embedding".to_string());
+    config.database.tables[0].embedder_name = Some("default".to_string());
+    let connector = Connector::with_clients(config, Box::new(SharedAdapter(db.clone())), meilisearch.clone());
+    assert_eq!(connector.sync_once().await?.remove(0).documents_added, 1);
+
+    // Meilisearch returns the vectors it stores as 32 bit embeddings, which
+    // are the same vector
+    let stored = json!({ "id": 1, "field1": "a", "_vectors": { "default": { "embeddings": [[0.1f32, 0.2f32]], "regenerate": false } } });
+    meilisearch.add_or_update_documents("test_index", vec![stored], None).await?;
+    assert_eq!(connector.sync_once().await?.remove(0).documents_changed, 0);
+
+    // A row whose only change is its embedding is sent again
+    assert!(db.update("test", &json!(1), json!({ "id": 1, "field1": "a", "embedding": [0.3, 0.4] })));
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_added, report.documents_changed), (1, 1));
+    assert_eq!(meilisearch.get_document("test_index", "1").await?.unwrap()["_vectors"]["default"], json!([0.3, 0.4]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_on_transform_error() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.tables[0].vector_field = Some("embedding".to_string());
+    config.database.tables[0].embedder_name = Some("default".to_string());
+    config.database.on_transform_error = TransformErrorPolicy::Fail;
+
+    let db = MockSqliteAdapter::new().await.with_records(vec![
+        json!({ "id": 1, "embedding": [0.1, 0.2] }),
+        json!({ "id": 2, "embedding": "not a vector" }),
+    ]);
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    let err = connector.sync_once().await.unwrap_err();
+    assert!(matches!(err, ConnectorError::Config(msg) if msg.starts_with("1 documents of table test")));
+    // The valid document isn't sent either
+    assert!(meilisearch.document_ids("test_index").is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_id_prefix_shared_index() -> Result<()> {
+    let mut config = create_test_config();
+    let mut products = config.database.tables[0].clone();
+    config.database.tables[0].id_prefix = Some("user-".to_string());
+    products.name = "products".to_string();
+    products.id_prefix = Some("product-".to_string());
+    config.database.tables.push(products);
+
+    // Both tables have rows with ids 1 and 2, which would overwrite each other
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let records = vec![json!({ "id": 1 }), json!({ "id": 2 })];
+    let db = MockSqliteAdapter::new().await.with_records(records);
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    connector.sync_once().await?;
+    connector.sync_once().await?;
+
+    let mut ids: Vec<Value> = meilisearch.documents.lock().unwrap().iter().map(|doc| doc["id"].clone()).collect();
+    ids.sort_by_key(|id| id.to_string());
+    assert_eq!(ids, vec![json!("product-1"), json!("product-2"), json!("user-1"), json!("user-2")]);
+    // Each table only reconciles its own documents, so neither deletes the other's
+    assert!(meilisearch.operations.lock().unwrap().iter().all(|op| op.starts_with("add")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verify() -> Result<()> {
+    let records = vec![
+        json!({ "id": 1, "field1": "a" }),
+        json!({ "id": 2, "field1": "b" }),
+        json!({ "id": 3, "field1": "c" }),
+    ];
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let db = MockSqliteAdapter::new().await.with_records(records);
+    let connector = Connector::with_clients(create_test_config(), Box::new(db), meilisearch.clone());
+    connector.sync_once().await?;
+
+    let reports = connector.verify(None, 10).await?;
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].checked, 3);
+    assert!(reports[0].mismatches.is_empty());
+
+    // Drift the index: one stale field and one lost document
+    {
+        let mut documents = meilisearch.documents.lock().unwrap();
+        documents.retain(|doc| doc["id"] != json!(3));
+        documents.iter_mut().find(|doc| doc["id"] == json!(2)).unwrap()["field1"] = json!("stale");
+    }
+    let mut mismatches = connector.verify(Some("test"), 10).await?.remove(0).mismatches;
+    mismatches.sort_by(|a, b| a.id.cmp(&b.id));
+    assert_eq!(mismatches.len(), 2);
+    assert_eq!((mismatches[0].id.as_str(), mismatches[0].field.as_deref()), ("2", Some("field1")));
+    assert_eq!((&mismatches[0].database, &mismatches[0].index), (&Some(json!("b")), &Some(json!("stale"))));
+    assert_eq!((mismatches[1].id.as_str(), mismatches[1].field.as_deref()), ("3", None));
+
+    assert!(connector.verify(Some("missing"), 10).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_array_split() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.tables[0].array_split.insert("tags".to_string(), ",".to_string());
+
+    let db = MockSqliteAdapter::new().await.with_records(vec![
+        json!({ "id": 1, "tags": " rust, search ,sql" }),
+        json!({ "id": 2, "tags": "" }),
+        json!({ "id": 3, "tags": null }),
+        json!({ "id": 4, "tags": ["native", "array"] }),
+    ]);
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    connector.sync_once().await?;
+
+    let mut documents = meilisearch.documents.lock().unwrap().clone();
+    documents.sort_by_key(|doc| doc["id"].as_i64());
+    assert_eq!(documents, vec![
+        json!({ "id": 1, "tags": ["rust", "search", "sql"] }),
+        json!({ "id": 2, "tags": [] }),
+        json!({ "id": 3, "tags": [] }),
+        json!({ "id": 4, "tags": ["native", "array"] }),
+    ]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_field_map() -> Result<()> {
+    let mut config = create_test_config();
+    let table = &mut config.database.tables[0];
+    table.field_map.insert("usr_nm".to_string(), "username".to_string());
+    table.field_map.insert("id".to_string(), "user_id".to_string());
+
+    let db = Arc::new(InMemoryDatabaseAdapter::new().with_table("test", "id", vec![
+        json!({ "id": 1, "usr_nm": "ada", "email": "ada@example.com" }),
+        json!({ "id": 2, "usr_nm": "bob", "email": "bob@example.com" }),
+    ]));
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(config, Box::new(SharedAdapter(db.clone())), meilisearch.clone());
+    connector.setup().await?;
+    connector.sync_once().await?;
+
+    // Mapped columns are renamed, the others pass through
+    let document = meilisearch.get_document("test_index", "1").await?.unwrap();
+    let keys: Vec<&String> = document.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["email", "user_id", "username"]);
+    assert_eq!(document["username"], "ada");
+    assert_eq!(meilisearch.index_primary_key("test_index").await?.as_deref(), Some("user_id"));
+
+    // Indexed documents are matched to their rows by the renamed key
+    assert!(db.delete("test", &json!(2)));
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_added, report.documents_deleted), (0, 1));
+    assert_eq!(meilisearch.document_ids("test_index"), ["1"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fields_to_index_and_exclude_fields() -> Result<()> {
+    // Fields of the document a row is indexed as
+    async fn indexed_fields(config: Config) -> Result<Vec<String>> {
+        let db = InMemoryDatabaseAdapter::new().with_table("test", "id", vec![
+            json!({ "id": 1, "name": "ada", "email": "ada@example.com", "password_hash": "x1", "tags": "a,b" }),
+        ]);
+        let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+        Connector::with_clients(config, Box::new(db), meilisearch.clone()).sync_once().await?;
+        let document = meilisearch.get_document("test_index", "1").await?.unwrap();
+        Ok(document.as_object().unwrap().keys().cloned().collect())
+    }
+
+    // An allowlist keeps the primary key, and columns other options index
+    let mut config = create_test_config();
+    let table = &mut config.database.tables[0];
+    table.fields_to_index = vec!["name".to_string()];
+    table.array_split.insert("tags".to_string(), ",".to_string());
+    assert_eq!(indexed_fields(config).await?, ["id", "name", "tags"]);
+
+    // Excluded columns are left out of every document
+    let mut config = create_test_config();
+    let table = &mut config.database.tables[0];
+    table.fields_to_index.clear();
+    table.exclude_fields = vec!["password_hash".to_string()];
+    assert_eq!(indexed_fields(config).await?, ["email", "id", "name", "tags"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_null_handling() -> Result<()> {
+    let mut config = create_test_config();
+    let table = &mut config.database.tables[0];
+    table.null_handling = NullHandling::Omit;
+    table.field_null_handling.insert("middle_name".to_string(), NullHandling::Keep);
+    table.field_null_handling.insert("notes".to_string(), NullHandling::Empty);
+
+    let db = MockSqliteAdapter::new().await.with_records(vec![
+        json!({ "id": 1, "middle_name": null, "notes": null, "optional_score": null }),
+        json!({ "id": 2, "middle_name": "Ann", "notes": "n/a", "optional_score": 7 }),
+    ]);
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    connector.sync_once().await?;
+
+    let mut documents = meilisearch.documents.lock().unwrap().clone();
+    documents.sort_by_key(|doc| doc["id"].as_i64());
+    assert_eq!(documents, vec![
+        json!({ "id": 1, "middle_name": null, "notes": "" }),
+        json!({ "id": 2, "middle_name": "Ann", "notes": "n/a", "optional_score": 7 }),
+    ]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blob_reference_template() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.tables[0].id_prefix = Some("photo-".to_string());
+    config.database.tables[0].blob_reference_template
+        .insert("image".to_string(), "https://cdn.example.com/{folder}/{id}.jpg".to_string());
+
+    let db = MockSqliteAdapter::new().await.with_records(vec![
+        json!({ "id": 1, "folder": "a", "image": "BLOB(2048)" }),
+        json!({ "id": 2, "folder": "b", "image": null }),
+        json!({ "id": 3, "folder": null, "image": "BLOB(10)" }),
+    ]);
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    connector.sync_once().await?;
+
+    // Placeholders get the row's own values, not the prefixed document id
+    let mut documents = meilisearch.documents.lock().unwrap().clone();
+    documents.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+    assert_eq!(documents, vec![
+        json!({ "id": "photo-1", "folder": "a", "image": "https://cdn.example.com/a/1.jpg" }),
+        json!({ "id": "photo-2", "folder": "b" }),
+        json!({ "id": "photo-3", "folder": "" }),
+    ]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_document_schema() -> Result<()> {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let schema_path = temp_dir.path().join("schema.json");
+    std::fs::write(&schema_path, json!({
+        "type": "object",
+        "required": ["field1"],
+        "properties": { "field1": { "type": "string" }, "score": { "type": "number" } },
+    }).to_string()).unwrap();
+    let records = vec![
+        json!({ "id": 1, "field1": "a", "score": 1.5 }),
+        json!({ "id": 2, "field1": "b", "score": "high" }),
+        json!({ "id": 3, "score": 2 }),
+    ];
+    let mut config = create_test_config();
+    config.database.tables[0].document_schema = Some(schema_path.to_str().unwrap().to_string());
+
+    // Documents that don't match are skipped and counted by field
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let db = MockSqliteAdapter::new().await.with_records(records.clone());
+    let connector = Connector::with_clients(config.clone(), Box::new(db), meilisearch.clone());
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!(report.documents_skipped, 2);
+    assert_eq!(report.schema_violations.into_iter().collect::<Vec<_>>(), [("field1".to_string(), 1), ("score".to_string(), 1)]);
+    assert_eq!(*meilisearch.documents.lock().unwrap(), [json!({ "id": 1, "field1": "a", "score": 1.5 })]);
+
+    // Or fail the sync, with on_transform_error = "fail"
+    config.database.on_transform_error = TransformErrorPolicy::Fail;
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let db = MockSqliteAdapter::new().await.with_records(records);
+    let connector = Connector::with_clients(config.clone(), Box::new(db), meilisearch.clone());
+    assert!(connector.sync_once().await.is_err());
+    assert!(meilisearch.documents.lock().unwrap().is_empty());
+
+    // A schema that can't be read is a configuration error
+    config.database.tables[0].document_schema = Some(temp_dir.path().join("missing.json").to_str().unwrap().to_string());
+    let issues = Connector::validate_config(&config);
+    assert!(issues.iter().any(|issue| issue.to_string().contains("Could not read document schema")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_documents_per_sync() -> Result<()> {
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    meilisearch.documents.lock().unwrap().extend([json!({ "id": 8 }), json!({ "id": 9 })]);
+    let db = MockSqliteAdapter::new().await.with_records(vec![json!({ "id": 1 }), json!({ "id": 2 }), json!({ "id": 3 })]);
+
+    let mut config = create_test_config();
+    config.database.max_documents_per_sync = Some(3);
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+
+    // Five changes: the deletions go first, then as many adds as the cap allows
+    let report = connector.sync_once().await?.remove(0);
+    assert!(report.capped);
+    assert_eq!((report.documents_deleted, report.documents_added, report.documents_deferred), (2, 1, 2));
+    assert_eq!(*meilisearch.operations.lock().unwrap(), ["delete 8,9", "add 1"]);
+
+    // The deferred changes are picked up by the next sync
+    let report = connector.sync_once().await?.remove(0);
+    assert!(!report.capped);
+    assert_eq!((report.documents_added, report.documents_deferred), (2, 0));
+    assert_eq!(meilisearch.documents.lock().unwrap().len(), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_delete_ratio() -> Result<()> {
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    meilisearch.documents.lock().unwrap().extend([json!({ "id": 1 }), json!({ "id": 2 }), json!({ "id": 3 })]);
+
+    // The database suddenly returns no rows: every document would be deleted
+    let mut config = create_test_config();
+    config.database.max_delete_ratio = Some(0.5);
+    let db = MockSqliteAdapter::new().await.with_records(vec![]);
+    let connector = Connector::with_clients(config.clone(), Box::new(db), meilisearch.clone());
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_deleted, report.deletions_blocked), (0, 3));
+    assert_eq!(meilisearch.documents.lock().unwrap().len(), 3);
+
+    // Deleting up to the ratio goes through
+    let db = MockSqliteAdapter::new().await.with_records(vec![json!({ "id": 1 }), json!({ "id": 2 })]);
+    let connector = Connector::with_clients(config.clone(), Box::new(db), meilisearch.clone());
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_deleted, report.deletions_blocked), (1, 0));
+
+    // The explicit override lets the wipe happen
+    let db = MockSqliteAdapter::new().await.with_records(vec![]);
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone()).with_allow_mass_delete(true);
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_deleted, report.deletions_blocked), (2, 0));
+    assert!(meilisearch.documents.lock().unwrap().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hash_fields() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.tables[0].hash_fields = vec!["email".to_string()];
+    config.database.tables[0].hash_salt = Some("pepper".to_string());
+
+    let db = MockSqliteAdapter::new().await.with_records(vec![json!({ "id": 1, "email": "ada@example.com", "field1": "Ada" })]);
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    connector.sync_once().await?;
+
+    let document = meilisearch.documents.lock().unwrap()[0].clone();
+    let expected = hash_value(&json!("ada@example.com"), HashAlgorithm::Sha256, Some("pepper")).unwrap();
+    assert_eq!(document, json!({ "id": 1, "email": expected, "field1": "Ada" }));
+    assert!(!document.to_string().contains("ada@example.com"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_primary_key_type() -> Result<()> {
+    let records = vec![json!({ "id": 1, "field1": "a" }), json!({ "id": "2", "field1": "b" }), json!({ "id": "x3", "field1": "c" })];
+    let ids = |meilisearch: &FlakyMeilisearchClient| {
+        let mut ids: Vec<Value> = meilisearch.documents.lock().unwrap().iter().map(|d| d["id"].clone()).collect();
+        ids.sort_by_key(|id| id.to_string());
+        ids
+    };
+
+    // Auto keeps each value as it is
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let db = MockSqliteAdapter::new().await.with_records(records.clone());
+    let connector = Connector::with_clients(create_test_config(), Box::new(db), meilisearch.clone());
+    let reports = connector.sync_once().await?;
+    assert_eq!(reports[0].primary_keys_coerced, 0);
+    assert_eq!(ids(&meilisearch), [json!("2"), json!("x3"), json!(1)]);
+
+    let mut config = create_test_config();
+    config.database.tables[0].primary_key_type = PrimaryKeyType::String;
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let db = MockSqliteAdapter::new().await.with_records(records.clone());
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    let reports = connector.sync_once().await?;
+    assert_eq!(reports[0].primary_keys_coerced, 1);
+    assert_eq!(ids(&meilisearch), [json!("1"), json!("2"), json!("x3")]);
+
+    // Strings that aren't integers can't be indexed under an integer key
+    let mut config = create_test_config();
+    config.database.tables[0].primary_key_type = PrimaryKeyType::Integer;
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let db = MockSqliteAdapter::new().await.with_records(records);
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    let reports = connector.sync_once().await?;
+    assert_eq!(reports[0].primary_keys_coerced, 1);
+    assert_eq!(reports[0].primary_keys_invalid, 1);
+    assert_eq!(ids(&meilisearch), [json!(1), json!(2)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_primary_keys() -> Result<()> {
+    let records = vec![
+        json!({ "id": 0, "field1": "a" }),
+        json!({ "id": "0", "field1": "b" }),
+        json!({ "id": "", "field1": "c" }),
+        json!({ "id": null, "field1": "d" }),
+        json!({ "field1": "e" }),
+        json!({ "id": -1, "field1": "f" }),
+    ];
+
+    // Zero is a valid id; only missing, null and empty keys are skipped
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let db = MockSqliteAdapter::new().await.with_records(records.clone());
+    let connector = Connector::with_clients(create_test_config(), Box::new(db), meilisearch.clone());
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.primary_keys_missing, report.primary_keys_null, report.primary_keys_empty), (1, 1, 1));
+    assert_eq!(report.primary_keys_invalid, 0);
+    assert_eq!(report.primary_keys_skipped(), 3);
+    assert_eq!(report.database_documents, 3);
+    assert_eq!(meilisearch.document_ids("test_index"), ["-1", "0"]);
+
+    // Values listed in invalid_primary_keys are skipped too
+    let mut config = create_test_config();
+    config.database.tables[0].invalid_primary_keys = vec!["-1".to_string()];
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let db = MockSqliteAdapter::new().await.with_records(records);
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!(report.primary_keys_invalid, 1);
+    assert_eq!(meilisearch.document_ids("test_index"), ["0"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_table_status() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.poll_interval_seconds = Some(60);
+    config.database.heartbeat_interval_seconds = 1;
+
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let db = MockSqliteAdapter::new().await;
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+
+    let running = connector.clone();
+    let handle = tokio::spawn(async move { running.start().await });
+    // Index setup waits a second before the table's first sync
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+
+    assert_eq!(connector.active_sync_tasks(), 1);
+    let status = &connector.table_status()["test"];
+    assert!(status.last_sync.is_some());
+    assert_eq!((status.documents, status.last_changes), (1, 1));
+    assert!(status.summary(std::time::SystemTime::now()).ends_with("ago, 1 documents, 1 changes"));
+
+    connector.stop().await?;
+    handle.await.unwrap()?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reload() -> Result<()> {
+    let tmp_dir = std::env::current_dir().unwrap().join("tmp");
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+    let db_path = tmp_dir.join(format!("connector_reload_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    std::fs::File::create(&db_path)?;
+    let mut conn = sqlx::SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE test (id INTEGER PRIMARY KEY, field1 TEXT)").execute(&mut conn).await?;
+    sqlx::query("INSERT INTO test VALUES (1, 'a')").execute(&mut conn).await?;
+    sqlx::query("CREATE TABLE other (id INTEGER PRIMARY KEY, name TEXT)").execute(&mut conn).await?;
+    sqlx::query("INSERT INTO other VALUES (1, 'b'), (2, 'c'), (3, 'd')").execute(&mut conn).await?;
+    drop(conn);
+
+    let mut config = create_test_config();
+    config.database.poll_interval_seconds = Some(60);
+    let row_filters = RowFilters::new(&config.database.tables);
+    let db = SqliteAdapter::new(db_path_str).await?.with_row_filters(row_filters.clone());
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(config.clone(), Box::new(db), meilisearch.clone())
+        .with_row_filters(row_filters);
+
+    let running = connector.clone();
+    let handle = tokio::spawn(async move { running.start().await });
+    // Index setup waits a second before the table's first sync
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    let first_sync = connector.table_status()["test"].last_sync;
+    assert!(first_sync.is_some());
+
+    // An added table starts syncing, the others carry on, and other options wait for a restart
+    let other = TableConfig {
+        name: "other".to_string(),
+        primary_key: "id".to_string(),
+        index_name: Some("other_index".to_string()),
+        watch_for_changes: true,
+        filter: Some("id < 3".to_string()),
+        ..Default::default()
+    };
+    config.database.tables.push(other);
+    config.meilisearch.inter_batch_delay_ms += 50;
+    let report = connector.reload(config.clone()).await?;
+    assert_eq!(report.added, ["other"]);
+    assert!(report.removed.is_empty() && report.changed.is_empty());
+    assert_eq!(report.restart_required, ["meilisearch.inter_batch_delay_ms"]);
+    assert_eq!(connector.config().meilisearch.inter_batch_delay_ms, config.meilisearch.inter_batch_delay_ms - 50);
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    assert_eq!(connector.active_sync_tasks(), 2);
+    assert_eq!(meilisearch.document_ids("other_index"), ["1", "2"]);
+    assert_eq!(connector.table_status()["test"].last_sync, first_sync);
+
+    // A removed table stops, a changed one restarts with its settings and
+    // filter applied again
+    config.database.tables.remove(0);
+    config.database.tables[0].searchable_attributes = Some(vec!["name".to_string()]);
+    config.database.tables[0].filter = Some("id > 1".to_string());
+    let report = connector.reload(config).await?;
+    assert_eq!((report.removed.as_slice(), report.changed.as_slice()), (["test".to_string()].as_slice(), ["other".to_string()].as_slice()));
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    assert_eq!(connector.active_sync_tasks(), 1);
+    assert_eq!(meilisearch.document_ids("other_index"), ["2", "3"]);
+    assert!(!connector.table_status().contains_key("test"));
+    let tables: Vec<String> = connector.config().database.tables.into_iter().map(|t| t.name).collect();
+    assert_eq!(tables, ["other"]);
+
+    // An invalid configuration leaves everything as it was
+    let mut invalid = create_test_config();
+    invalid.database.tables[0].primary_key = String::new();
+    assert!(connector.reload(invalid).await.is_err());
+    assert_eq!(connector.active_sync_tasks(), 1);
+
+    connector.stop().await?;
+    handle.await.unwrap()?;
+    std::fs::remove_file(&db_path).unwrap_or_default();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reload_resets_watermarks() -> Result<()> {
+    let tmp_dir = std::env::current_dir().unwrap().join("tmp");
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+    let db_path = tmp_dir.join(format!("connector_reload_watermarks_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    std::fs::File::create(&db_path)?;
+    let mut conn = sqlx::SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE test (id INTEGER PRIMARY KEY, field1 TEXT, updated_at INTEGER)").execute(&mut conn).await?;
+    sqlx::query("INSERT INTO test VALUES (1, 'a', 10), (2, 'b', 20)").execute(&mut conn).await?;
+    drop(conn);
+
+    let mut config = create_test_config();
+    config.database.poll_interval_seconds = Some(60);
+    config.database.tables[0].updated_at_column = Some("updated_at".to_string());
+    let db = SqliteAdapter::new(db_path_str).await?;
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(config.clone(), Box::new(db), meilisearch.clone());
+
+    let running = connector.clone();
+    let handle = tokio::spawn(async move { running.start().await });
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let indexed = meilisearch.get_document("test_index", "1").await?.unwrap();
+    assert_eq!(indexed["field1"], "a");
+
+    // Rows not modified since the last sync take the changed options too
+    config.database.tables[0].field_map.insert("field1".to_string(), "title".to_string());
+    let report = connector.reload(config).await?;
+    assert_eq!(report.changed, ["test"]);
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    for id in ["1", "2"] {
+        let indexed = meilisearch.get_document("test_index", id).await?.unwrap();
+        assert!(indexed.get("title").is_some() && indexed.get("field1").is_none(), "document {}: {}", id, indexed);
+    }
+
+    connector.stop().await?;
+    handle.await.unwrap()?;
+    std::fs::remove_file(&db_path).unwrap_or_default();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_maintenance_mode() -> Result<()> {
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let db = MockSqliteAdapter::new().await;
+    let connector = Connector::with_clients(create_test_config(), Box::new(db), meilisearch.clone());
+
+    // Changes are diffed but not sent
+    connector.enter_maintenance();
+    assert!(connector.in_maintenance());
+    let reports = connector.sync_once().await?;
+    assert!(reports[0].maintenance);
+    assert_eq!(reports[0].documents_withheld, 1);
+    assert!(meilisearch.operations.lock().unwrap().is_empty());
+
+    connector.exit_maintenance();
+    let reports = connector.sync_once().await?;
+    assert!(!reports[0].maintenance);
+    assert_eq!(reports[0].documents_added, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_maintenance_on_failed_health_checks() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.poll_interval_seconds = Some(60);
+    config.meilisearch.health_check_interval_seconds = 1;
+    config.meilisearch.maintenance_after_failures = 1;
+
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    meilisearch.fail_writes.store(true, Ordering::SeqCst);
+    let db = MockSqliteAdapter::new().await;
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+
+    let running = connector.clone();
+    let handle = tokio::spawn(async move { running.start().await });
+    // Health checks start after the index setup second, the first one fails
+    tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+    assert!(connector.in_maintenance());
+
+    // The next passing check resumes writes
+    meilisearch.fail_writes.store(false, Ordering::SeqCst);
+    tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
+    assert!(!connector.in_maintenance());
+
+    connector.stop().await?;
+    handle.await.unwrap()?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_circuit_breaker() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.document_batch_size = 1;
+    config.meilisearch.circuit_breaker_failures = 2;
+    config.meilisearch.circuit_breaker_cooldown_seconds = 1;
+
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    meilisearch.fail_writes.store(true, Ordering::SeqCst);
+    let records = vec![json!({ "id": 1, "field1": "a" }), json!({ "id": 2, "field1": "b" })];
+    let db = MockSqliteAdapter::new().await.with_records(records);
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+
+    // Both single-document batches fail, which opens the circuit
+    assert_eq!(connector.sync_once().await?[0].batches_failed, 2);
+    let status = &connector.breaker_status()["meilisearch"];
+    assert_eq!((status.state, status.consecutive_failures, status.times_opened), (BreakerState::Open, 2, 1));
+    assert_eq!(connector.breaker_status()["database"].state, BreakerState::Closed);
+
+    // Calls fail fast while it is open, even though Meilisearch recovered
+    meilisearch.fail_writes.store(false, Ordering::SeqCst);
+    assert!(matches!(connector.sync_once().await, Err(ConnectorError::CircuitOpen(_))));
+    assert_eq!(connector.breaker_status()["meilisearch"].rejected_calls, 1);
+
+    // After the cooldown a trial call closes it again
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let reports = connector.sync_once().await?;
+    assert_eq!(reports[0].documents_added, 2);
+    let status = &connector.breaker_status()["meilisearch"];
+    assert_eq!((status.state, status.consecutive_failures, status.total_failures), (BreakerState::Closed, 0, 2));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_meilisearch_version() -> Result<()> {
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new().with_version("1.9.0"));
+    let db = MockSqliteAdapter::new().await;
+    let connector = Connector::with_clients(create_test_config(), Box::new(db), meilisearch);
+    assert_eq!(connector.meilisearch_version(), None);
+
+    connector.sync_once().await?;
+    assert_eq!(connector.meilisearch_version().as_deref(), Some("1.9.0"));
+
+    // Clients that can't tell leave it unknown without failing the sync
+    let db = MockSqliteAdapter::new().await;
+    let connector = Connector::with_clients(create_test_config(), Box::new(db), Arc::new(InMemoryMeilisearchClient::new()));
+    connector.sync_once().await?;
+    assert_eq!(connector.meilisearch_version(), None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retention_window() -> Result<()> {
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let now = chrono::Utc::now();
+    let records = vec![
+        json!({ "id": 1, "field1": "a", "updated_at": now.to_rfc3339() }),
+        json!({ "id": 2, "field1": "b", "updated_at": (now - chrono::Duration::days(2)).to_rfc3339() }),
+        json!({ "id": 3, "field1": "c", "updated_at": null }),
+    ];
+    let db = MockSqliteAdapter::new().await.with_records(records.clone());
+    let connector = Connector::with_clients(create_test_config(), Box::new(db), meilisearch.clone());
+    connector.sync_once().await?;
+    assert_eq!(meilisearch.document_ids("test_index"), ["1", "2", "3"]);
+
+    // With a one day window, the old and undated rows leave the index
+    let mut config = create_test_config();
+    config.database.tables[0].retention_column = Some("updated_at".to_string());
+    config.database.tables[0].retention_window_seconds = Some(86400);
+    let db = MockSqliteAdapter::new().await.with_records(records);
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    let reports = connector.sync_once().await?;
+    assert_eq!(reports[0].outside_retention_window, 2);
+    assert_eq!(reports[0].documents_deleted, 2);
+    assert_eq!(meilisearch.document_ids("test_index"), ["1"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reconciliation() -> Result<()> {
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let records = vec![
+        json!({ "id": 1, "field1": "a" }),
+        json!({ "id": 2, "field1": "b" }),
+        json!({ "id": 3, "field1": "c" }),
+    ];
+    let db = MockSqliteAdapter::new().await.with_records(records);
+    let connector = Connector::with_clients(create_test_config(), Box::new(db), meilisearch.clone());
+    connector.sync_once().await?;
+    assert_eq!(meilisearch.document_ids("test_index"), ["1", "2", "3"]);
+
+    // Row 2 was deleted and row 4 inserted
+    let records = vec![
+        json!({ "id": 1, "field1": "a" }),
+        json!({ "id": 3, "field1": "c" }),
+        json!({ "id": 4, "field1": "d" }),
+    ];
+    let db = MockSqliteAdapter::new().await.with_records(records);
+    let connector = Connector::with_clients(create_test_config(), Box::new(db), meilisearch.clone());
+    let reports = connector.sync_once().await?;
+    assert_eq!((reports[0].documents_added, reports[0].documents_deleted), (1, 1));
+    assert_eq!(meilisearch.document_ids("test_index"), ["1", "3", "4"]);
+
+    // Nothing left to do
+    let reports = connector.sync_once().await?;
+    assert_eq!((reports[0].documents_added, reports[0].documents_deleted), (0, 0));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_after_database_changes() -> Result<()> {
+    let db = Arc::new(InMemoryDatabaseAdapter::new().with_table("test", "id", vec![
+        json!({ "id": 1, "field1": "a" }),
+        json!({ "id": 2, "field1": "b" }),
+    ]));
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(create_test_config(), Box::new(SharedAdapter(db.clone())), meilisearch.clone());
+    connector.sync_once().await?;
+    assert_eq!(meilisearch.document_ids("test_index"), ["1", "2"]);
+
+    db.insert("test", json!({ "id": 3, "field1": "c" }));
+    assert!(db.delete("test", &json!(1)));
+    assert!(!db.delete("test", &json!(9)));
+    let reports = connector.sync_once().await?;
+    assert_eq!((reports[0].documents_added, reports[0].documents_deleted), (1, 1));
+    assert_eq!(meilisearch.document_ids("test_index"), ["2", "3"]);
+    assert_eq!(meilisearch.get_document("test_index", "3").await?, Some(json!({ "id": 3, "field1": "c" })));
+
+    assert!(db.update("test", &json!(2), json!({ "id": 2, "field1": "b2" })));
+    assert_eq!(db.fetch_all_records("test").await?[0]["field1"], "b2");
+    assert!(db.get_table_columns("test").await?.contains(&("id".to_string(), "INTEGER".to_string(), true)));
+    let reports = connector.sync_once().await?;
+    assert_eq!((reports[0].documents_added, reports[0].documents_changed), (1, 1));
+    assert_eq!(meilisearch.get_document("test_index", "2").await?, Some(json!({ "id": 2, "field1": "b2" })));
+
+    // Unchanged rows aren't sent again
+    let reports = connector.sync_once().await?;
+    assert_eq!((reports[0].documents_added, reports[0].documents_deleted), (0, 0));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dry_run_plans_changes_without_writing() -> Result<()> {
+    let db = Arc::new(InMemoryDatabaseAdapter::new().with_table("test", "id", vec![
+        json!({ "id": 1, "field1": "a" }),
+        json!({ "id": 2, "field1": "b" }),
+    ]));
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(create_test_config(), Box::new(SharedAdapter(db.clone())), meilisearch.clone());
+
+    // Against an index that doesn't exist yet, every row is an addition
+    let (plan, run) = connector.plan(false).await?;
+    assert!(run.is_success());
+    assert_eq!(plan.indexes.len(), 1);
+    assert_eq!(plan.indexes[0].primary_key.as_deref(), Some("id"));
+    assert_eq!(plan.indexes[0].added[1], PlannedDocument { id: "2".to_string(), document: Some(json!({ "id": 2, "field1": "b" })) });
+    assert!(meilisearch.list_indexes().await?.is_empty());
+
+    connector.sync_once().await?;
+    db.insert("test", json!({ "id": 3, "field1": "c" }));
+    assert!(db.delete("test", &json!(1)));
+    assert!(db.update("test", &json!(2), json!({ "id": 2, "field1": "b2" })));
+    let (plan, run) = connector.plan(false).await?;
+    assert_eq!((run.reports[0].documents_added, run.reports[0].documents_deleted), (2, 1));
+    let index = &plan.indexes[0];
+    assert_eq!(index.added, [PlannedDocument { id: "3".to_string(), document: Some(json!({ "id": 3, "field1": "c" })) }]);
+    assert_eq!(index.updated, [PlannedUpdate {
+        id: "2".to_string(),
+        before: Some(json!({ "id": 2, "field1": "b" })),
+        after: Some(json!({ "id": 2, "field1": "b2" })),
+    }]);
+    assert_eq!(index.deleted, ["1"]);
+    assert_eq!(meilisearch.document_ids("test_index"), ["1", "2"]);
+
+    // A summary keeps the ids only
+    let (plan, _) = connector.plan(true).await?;
+    let plan = serde_json::to_value(&plan).unwrap();
+    assert_eq!(plan["indexes"][0]["added"], json!([{ "id": "3" }]));
+    assert_eq!(plan["indexes"][0]["updated"], json!([{ "id": "2" }]));
+
+    // The plan is computed without moving the real syncs along
+    let reports = connector.sync_once().await?;
+    assert_eq!((reports[0].documents_added, reports[0].documents_deleted), (2, 1));
+    assert!(connector.plan(false).await?.0.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_apply_makes_the_planned_changes() -> Result<()> {
+    let db = Arc::new(InMemoryDatabaseAdapter::new().with_table("test", "id", vec![
+        json!({ "id": 1, "field1": "a" }),
+        json!({ "id": 2, "field1": "b" }),
+    ]));
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(create_test_config(), Box::new(SharedAdapter(db.clone())), meilisearch.clone());
+    connector.sync_once().await?;
+
+    assert!(db.delete("test", &json!(1)));
+    assert!(db.update("test", &json!(2), json!({ "id": 2, "field1": "b2" })));
+    let (plan, _) = connector.plan(false).await?;
+    assert_eq!(plan.tables.len(), 1);
+    // The plan survives being written out and read back
+    let plan: SyncPlan = serde_json::from_str(&serde_json::to_string(&plan).unwrap()).unwrap();
+
+    // Unchanged rows apply quietly
+    let report = connector.apply(&plan).await?;
+    assert!(report.changed_tables.is_empty());
+    assert_eq!((report.indexes[0].documents_updated, report.indexes[0].documents_deleted), (1, 1));
+    assert_eq!(meilisearch.document_ids("test_index"), ["2"]);
+    assert_eq!(meilisearch.get_document("test_index", "2").await?.unwrap()["field1"], "b2");
+
+    // Rows changed since are reported, while the plan's documents are what's written
+    db.insert("test", json!({ "id": 3, "field1": "c" }));
+    assert!(db.update("test", &json!(2), json!({ "id": 2, "field1": "b3" })));
+    let report = connector.apply(&plan).await?;
+    assert_eq!(report.changed_tables, ["test"]);
+    assert_eq!(meilisearch.document_ids("test_index"), ["2"]);
+    assert_eq!(meilisearch.get_document("test_index", "2").await?.unwrap()["field1"], "b2");
+
+    // A summary doesn't hold the documents to write
+    let (summary, _) = connector.plan(true).await?;
+    assert!(summary.tables.is_empty());
+    assert!(matches!(connector.apply(&summary).await, Err(ConnectorError::Config(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_incremental_sync_with_updated_at_column() -> Result<()> {
+    let db = Arc::new(InMemoryDatabaseAdapter::new().with_table("test", "id", vec![
+        json!({ "id": 1, "field1": "a", "updated_at": 1 }),
+        json!({ "id": 2, "field1": "b", "updated_at": 2 }),
+    ]));
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let mut config = create_test_config();
+    config.database.tables[0].updated_at_column = Some("updated_at".to_string());
+    let connector = Connector::with_clients(config, Box::new(SharedAdapter(db.clone())), meilisearch.clone());
+
+    // The first sync reads the whole table
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.incremental, report.documents_added), (false, 2));
+
+    // Later ones only send the rows changed since, and still find deletions
+    assert!(db.update("test", &json!(2), json!({ "id": 2, "field1": "b2", "updated_at": 4 })));
+    db.insert("test", json!({ "id": 3, "field1": "c", "updated_at": 3 }));
+    assert!(db.delete("test", &json!(1)));
+    let report = connector.sync_once().await?.remove(0);
+    assert!(report.incremental);
+    assert_eq!((report.documents_added, report.documents_changed, report.documents_deleted), (2, 1, 1));
+    assert_eq!(meilisearch.document_ids("test_index"), ["2", "3"]);
+    assert_eq!(meilisearch.get_document("test_index", "2").await?.unwrap()["field1"], "b2");
+
+    // The rows at the watermark are read again, and left alone as they are indexed
+    let report = connector.sync_once().await?.remove(0);
+    assert!(report.incremental);
+    assert_eq!((report.documents_added, report.documents_changed), (0, 0));
+
+    // A document missing from the index although its row didn't change
+    // makes the sync read the whole table
+    meilisearch.delete_documents("test_index", &["3".to_string()], None).await?;
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.incremental, report.documents_added), (false, 1));
+    assert_eq!(meilisearch.document_ids("test_index"), ["2", "3"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_changed_rows_update_indexed_documents() -> Result<()> {
+    let db = InMemoryDatabaseAdapter::new().with_table("test", "id", vec![
+        json!({ "id": 1, "field1": "edited" }),
+        json!({ "id": 2, "field1": "same" }),
+    ]);
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    meilisearch.documents.lock().unwrap().extend([
+        json!({ "id": 1, "field1": "original" }),
+        json!({ "id": 2, "field1": "same" }),
+    ]);
+    let connector = Connector::with_clients(create_test_config(), Box::new(db), meilisearch.clone());
+
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_added, report.documents_changed), (1, 1));
+    assert_eq!(*meilisearch.operations.lock().unwrap(), ["add 1"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_changed_rows_counted_once_in_field_stats() -> Result<()> {
+    // Every row is indexed with an older value, enough of them to go through the parallel transform
+    let rows = (1..=1500)
+        .map(|id| {
+            let field1 = if id == 700 { "x".repeat(50) } else { format!("edited {}", id) };
+            json!({ "id": id, "field1": field1 })
+        })
+        .collect();
+    let db = InMemoryDatabaseAdapter::new().with_table("test", "id", rows);
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    meilisearch.documents.lock().unwrap().extend((1..=1500).map(|id| json!({ "id": id, "field1": "original" })));
+    let mut config = create_test_config();
+    config.database.warn_field_bytes = Some(40);
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch);
+
+    // The oversized field of a changed row is counted once, not once per transform
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!(report.documents_changed, 1500);
+    assert_eq!(report.max_field_bytes, 50);
+    assert_eq!(report.max_field_name.as_deref(), Some("field1"));
+    assert_eq!(report.oversized_fields, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stream_records_sends_chunks_as_read() -> Result<()> {
+    let db = InMemoryDatabaseAdapter::new().with_table("test", "id", (1..=5).map(|id| json!({ "id": id, "field1": "new" })).collect());
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    meilisearch.documents.lock().unwrap().extend([
+        json!({ "id": 1, "field1": "new" }),
+        json!({ "id": 2, "field1": "old" }),
+        json!({ "id": 9, "field1": "gone" }),
+    ]);
+    let mut config = create_test_config();
+    config.database.document_batch_size = 2;
+    config.database.max_concurrent_batches = 1;
+    config.database.tables[0].stream_records = true;
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+
+    // Each chunk of two rows is sent before the next is read, deletions last
+    let report = connector.sync_once().await?.remove(0);
+    assert!(report.streamed);
+    assert_eq!((report.documents_added, report.documents_changed, report.documents_deleted), (4, 1, 1));
+    assert_eq!(report.database_documents, 5);
+    assert_eq!(*meilisearch.operations.lock().unwrap(), ["add 2", "add 3,4", "add 5", "delete 9"]);
+
+    // A second sync finds nothing to send
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_added, report.documents_deleted), (0, 0));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refused_documents_go_to_dead_letter_file() -> Result<()> {
+    let db = InMemoryDatabaseAdapter::new().with_table("test", "id", (1..=5).map(|id| json!({ "id": id })).collect());
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    meilisearch.rejected_ids.lock().unwrap().extend(["3".to_string(), "5".to_string()]);
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dead_letter_path = temp_dir.path().join("rejected.ndjson");
+    let mut config = create_test_config();
+    config.database.dead_letter_path = Some(dead_letter_path.to_str().unwrap().to_string());
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+
+    // The batch is split down to the refused documents, the others are indexed
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_added, report.documents_rejected, report.batches_failed), (5, 2, 0));
+    let mut indexed: Vec<i64> = meilisearch.documents.lock().unwrap().iter().map(|doc| doc["id"].as_i64().unwrap()).collect();
+    indexed.sort();
+    assert_eq!(indexed, [1, 2, 4]);
+    let mut lines: Vec<Value> = std::fs::read_to_string(&dead_letter_path)?
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    lines.sort_by_key(|line| line["document"]["id"].as_i64());
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["document"], json!({ "id": 3 }));
+    assert_eq!((lines[0]["table"].as_str(), lines[0]["index"].as_str()), (Some("test"), Some("test_index")));
+    assert!(lines[0]["error"].as_str().unwrap().contains("invalid document"));
+    assert_eq!(lines[1]["document"], json!({ "id": 5 }));
+
+    // An unreachable Meilisearch fails the batch without blaming its documents
+    std::fs::remove_file(&dead_letter_path)?;
+    meilisearch.fail_writes.store(true, Ordering::SeqCst);
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_rejected, report.batches_failed), (0, 1));
+    assert!(!dead_letter_path.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dead_letter_write_failure_keeps_batch() -> Result<()> {
+    let db = InMemoryDatabaseAdapter::new().with_table("test", "id", (1..=3).map(|id| json!({ "id": id })).collect());
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    meilisearch.rejected_ids.lock().unwrap().push("2".to_string());
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut config = create_test_config();
+    // A directory that does not exist can't take the file
+    let dead_letter_path = temp_dir.path().join("missing").join("rejected.ndjson");
+    config.database.dead_letter_path = Some(dead_letter_path.to_str().unwrap().to_string());
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+
+    // The write fails, but the documents Meilisearch took stay counted
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_rejected, report.batches_failed), (1, 0));
+    assert_eq!(meilisearch.documents.lock().unwrap().len(), 2);
+    assert!(!dead_letter_path.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cutover_swaps_in_versioned_index() -> Result<()> {
+    let db = Arc::new(InMemoryDatabaseAdapter::new().with_table("test", "id", vec![
+        json!({ "id": 1, "field1": "a" }),
+        json!({ "id": 2, "field1": "b" }),
+    ]));
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let mut config = create_test_config();
+    assert!(matches!(
+        Connector::with_clients(config.clone(), Box::new(SharedAdapter(db.clone())), meilisearch.clone()).cutover(Some("test"), false).await,
+        Err(ConnectorError::Config(_))
+    ));
+    config.database.tables[0].use_versioned_indexes = true;
+    let connector = Connector::with_clients(config, Box::new(SharedAdapter(db.clone())), meilisearch.clone());
+
+    // A first cutover creates the index searches use
+    let reports = connector.cutover(None, false).await?;
+    assert_eq!((reports[0].versioned_index.as_str(), reports[0].sync.documents_added), ("test_index_v1", 2));
+    assert_eq!(meilisearch.document_ids("test_index"), ["1", "2"]);
+    assert_eq!(meilisearch.list_indexes().await?, ["test_index"]);
+
+    // The previous documents stay in the versioned index when asked to
+    assert!(db.delete("test", &json!(1)));
+    db.insert("test", json!({ "id": 3, "field1": "c" }));
+    let reports = connector.cutover(Some("test"), true).await?;
+    assert_eq!(reports[0].versioned_index, "test_index_v1");
+    assert_eq!(meilisearch.document_ids("test_index"), ["2", "3"]);
+    assert_eq!(meilisearch.document_ids("test_index_v1"), ["1", "2"]);
+
+    // The next version comes after the kept one
+    let reports = connector.cutover(None, false).await?;
+    assert_eq!(reports[0].versioned_index, "test_index_v2");
+    assert_eq!(meilisearch.list_indexes().await?, ["test_index", "test_index_v1"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deletions_checked_against_primary() -> Result<()> {
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let rows = vec![json!({ "id": 1, "field1": "a" }), json!({ "id": 2, "field1": "b" }), json!({ "id": 3, "field1": "c" })];
+    let primary = Arc::new(InMemoryDatabaseAdapter::new().with_table("test", "id", rows.clone()));
+    let replica = Arc::new(InMemoryDatabaseAdapter::new().with_table("test", "id", rows));
+    let connector = Connector::with_clients(create_test_config(), Box::new(SharedAdapter(replica.clone())), meilisearch.clone())
+        .with_primary_adapter(Box::new(SharedAdapter(primary.clone())));
+    connector.sync_once().await?;
+    assert_eq!(meilisearch.document_ids("test_index"), ["1", "2", "3"]);
+
+    // Row 3 was deleted and the lagging replica hasn't caught up with row 2 yet
+    assert!(primary.delete("test", &json!(3)));
+    assert!(replica.delete("test", &json!(2)));
+    assert!(replica.delete("test", &json!(3)));
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_deleted, report.deletions_unconfirmed), (1, 1));
+    assert_eq!(meilisearch.document_ids("test_index"), ["1", "2"]);
+
+    // Once the primary deletes it too, the document goes
+    assert!(primary.delete("test", &json!(2)));
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_deleted, report.deletions_unconfirmed), (1, 0));
+    assert_eq!(meilisearch.document_ids("test_index"), ["1"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deletions_wait_for_lagging_replica() -> Result<()> {
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let replica = Arc::new(InMemoryDatabaseAdapter::new().with_table("test", "id", vec![
+        json!({ "id": 1, "field1": "a" }),
+        json!({ "id": 2, "field1": "b" }),
+    ]));
+    let mut config = create_test_config();
+    config.database.max_replica_lag_seconds = Some(30);
+    let connector = Connector::with_clients(config, Box::new(SharedAdapter(replica.clone())), meilisearch.clone());
+    connector.sync_once().await?;
+
+    // While the replica lags, rows missing from it may not have arrived yet:
+    // upserts go through, deletions wait
+    replica.set_replication_lag(Some(Duration::from_secs(60)));
+    assert!(replica.delete("test", &json!(1)));
+    replica.insert("test", json!({ "id": 3, "field1": "c" }));
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_added, report.documents_deleted, report.deletions_postponed), (1, 0, 1));
+    assert_eq!(meilisearch.document_ids("test_index"), ["1", "2", "3"]);
+
+    // Caught up
+    replica.set_replication_lag(Some(Duration::from_secs(2)));
+    let report = connector.sync_once().await?.remove(0);
+    assert_eq!((report.documents_deleted, report.deletions_postponed), (1, 0));
+    assert_eq!(meilisearch.document_ids("test_index"), ["2", "3"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sorted_diff() -> Result<()> {
+    // Rows out of insertion order, one of them twice, against an index with
+    // documents whose rows were deleted at both ends of the key range
+    let rows = vec![
+        json!({ "id": 10, "field1": "ten" }),
+        json!({ "id": 2, "field1": "two" }),
+        json!({ "id": 9, "field1": "nine" }),
+        json!({ "id": 2, "field1": "two again" }),
+        json!({ "id": null, "field1": "no key" }),
+    ];
+    let indexed = vec![json!({ "id": 1 }), json!({ "id": 9, "field1": "nine" }), json!({ "id": 100 })];
+
+    let sync = |config: Config| {
+        let (rows, indexed) = (rows.clone(), indexed.clone());
+        async move {
+            let db = InMemoryDatabaseAdapter::new().with_table("test", "id", rows);
+            let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+            meilisearch.add_or_update_documents("test_index", indexed, None).await?;
+            let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+            let report = connector.sync_once().await?.remove(0);
+            Ok::<_, ConnectorError>((report, meilisearch.document_ids("test_index"), meilisearch.get_document("test_index", "2").await?))
+        }
+    };
+
+    // Both sides can be fetched in key order, so they are merged
+    let (report, ids, doc) = sync(create_test_config()).await?;
+    assert!(report.sorted_diff);
+    assert_eq!((report.documents_added, report.documents_deleted), (2, 2));
+    assert_eq!((report.database_documents, report.primary_keys_null), (4, 1));
+    assert_eq!(ids, ["10", "2", "9"]);
+    assert_eq!(doc.unwrap()["field1"], "two again");
+
+    // Converted keys are diffed by id, with the same outcome
+    let mut config = create_test_config();
+    config.database.tables[0].primary_key_type = PrimaryKeyType::Integer;
+    let (fallback, fallback_ids, fallback_doc) = sync(config).await?;
+    assert!(!fallback.sorted_diff);
+    assert_eq!((fallback.documents_added, fallback.documents_deleted), (2, 2));
+    assert_eq!((fallback.database_documents, fallback.primary_keys_null), (4, 1));
+    assert_eq!(fallback_ids, ids);
+    assert_eq!(fallback_doc.unwrap()["field1"], "two again");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_infer_primary_key() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.tables[0].primary_key = String::new();
+    config.database.tables[0].infer_primary_key = true;
+    assert!(config.validate().is_empty());
+
+    // Meilisearch infers the only field ending with "id", which the next syncs reconcile on
+    let db = Arc::new(InMemoryDatabaseAdapter::new().with_table("test", "skuId", vec![
+        json!({ "skuId": "a", "name": "first" }),
+        json!({ "skuId": "b", "name": "second" }),
+    ]));
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(config.clone(), Box::new(SharedAdapter(db.clone())), meilisearch.clone());
+    connector.sync_once().await?;
+    assert_eq!(meilisearch.index_primary_key("test_index").await?.as_deref(), Some("skuId"));
+    assert_eq!(meilisearch.document_ids("test_index"), ["a", "b"]);
+
+    assert!(db.delete("test", &json!("a")));
+    let reports = connector.sync_once().await?;
+    assert_eq!((reports[0].documents_added, reports[0].documents_deleted), (0, 1));
+    assert_eq!(meilisearch.document_ids("test_index"), ["b"]);
+
+    // Several candidates would make Meilisearch's inference fail, so nothing is sent
+    let db = InMemoryDatabaseAdapter::new().with_table("test", "id", vec![json!({ "id": 1, "owner_id": 7 })]);
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    let err = connector.sync_once().await.unwrap_err();
+    assert!(matches!(err, ConnectorError::Config(msg) if msg.contains("id, owner_id")));
+    assert!(meilisearch.document_ids("test_index").is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lookups() -> Result<()> {
+    let db = InMemoryDatabaseAdapter::new()
+        .with_table("test", "id", vec![
+            json!({ "id": 1, "category_id": 10 }),
+            json!({ "id": 2, "category_id": 99 }),
+            json!({ "id": 3, "category_id": null }),
+        ])
+        .with_table("categories", "id", vec![json!({ "id": "10", "name": "Books", "rank": 1 })]);
+    let mut config = create_test_config();
+    config.database.tables[0].lookups = vec![LookupConfig {
+        table: "categories".to_string(),
+        local_key: "category_id".to_string(),
+        foreign_key: "id".to_string(),
+        fields: [("category_name".to_string(), "name".to_string())].into(),
+        on_missing: LookupMissing::Omit,
+    }];
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(config.clone(), Box::new(db), meilisearch.clone());
+    connector.sync_once().await?;
+
+    // Keys match across types, rows without a match keep their own fields only
+    let documents = meilisearch.get_all_documents("test_index").await?;
+    assert_eq!(documents, [
+        json!({ "id": 1, "category_id": 10, "category_name": "Books" }),
+        json!({ "id": 2, "category_id": 99 }),
+        json!({ "id": 3, "category_id": "" }),
+    ]);
+
+    config.database.tables[0].lookups[0].on_missing = LookupMissing::Null;
+    let db = InMemoryDatabaseAdapter::new()
+        .with_table("test", "id", vec![json!({ "id": 2, "category_id": 99 })])
+        .with_table("categories", "id", vec![]);
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+    connector.sync_once().await?;
+    let documents = meilisearch.get_all_documents("test_index").await?;
+    assert_eq!(documents, [json!({ "id": 2, "category_id": 99, "category_name": "" })]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_auto_discover() -> Result<()> {
+    let mut config = create_test_config();
+    config.database.tables.clear();
+    config.database.poll_interval_seconds = Some(60);
+    config.database.auto_discover = true;
+    config.database.table_pattern = Some("test_*".to_string());
+
+    // The mock database has a single table, test_table, with one row
+    let meilisearch = Arc::new(FlakyMeilisearchClient::default());
+    let db = MockSqliteAdapter::new().await;
+    let connector = Connector::with_clients(config, Box::new(db), meilisearch.clone());
+
+    let running = connector.clone();
+    let handle = tokio::spawn(async move { running.start().await });
+    // Index setup waits a second before the table's first sync
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+
+    // The discovery loop and the sync task of the discovered table
+    assert_eq!(connector.active_sync_tasks(), 2);
+    assert_eq!(meilisearch.documents.lock().unwrap().len(), 1);
+
+    connector.stop().await?;
+    handle.await.unwrap()?;
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use meilisearch_sdk::errors::{Error, ErrorCode, ErrorType, MeilisearchCommunicationError, MeilisearchError};
+use meilisearch_sql_connector::meilisearch::client::{
+    add_splitting_oversized, fetch_all_pages, is_unknown_setting, pause_between_batches, to_csv, to_ndjson,
+};
+use meilisearch_sql_connector::meilisearch::{MeilisearchClient, MeilisearchClientTrait};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+// Stand-in for Meilisearch with an artificially tiny payload limit
+fn send_with_limit(limit: usize, accepted: &Mutex<Vec<Vec<Value>>>, docs: Vec<Value>) -> Result<(), Error> {
+    if serde_json::to_string(&docs).unwrap().len() > limit {
+        return Err(Error::MeilisearchCommunication(MeilisearchCommunicationError {
+            status_code: 413,
+            message: None,
+            url: "http://localhost:7700/indexes/test/documents".to_string(),
+        }));
+    }
+    accepted.lock().unwrap().push(docs);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_payload_too_large_splits_batch() {
+    let documents: Vec<Value> = (1..=8).map(|id| json!({ "id": id, "text": "0123456789" })).collect();
+    let accepted = Mutex::new(Vec::new());
+
+    // Room for about two documents per request
+    add_splitting_oversized(&documents, |docs| {
+        let result = send_with_limit(70, &accepted, docs.to_vec());
+        async move { result }
+    })
+    .await
+    .unwrap();
+
+    let batches = accepted.into_inner().unwrap();
+    assert_eq!(batches.len(), 4);
+    assert!(batches.iter().all(|batch| batch.len() == 2));
+    // Every document is sent exactly once, in the original order
+    assert_eq!(batches.concat(), documents);
+}
+
+#[tokio::test]
+async fn test_payload_too_large_single_document_fails() {
+    let documents = vec![json!({ "id": 1 }), json!({ "id": 2, "text": "x".repeat(500) })];
+    let accepted = Mutex::new(Vec::new());
+
+    let result = add_splitting_oversized(&documents, |docs| {
+        let result = send_with_limit(100, &accepted, docs.to_vec());
+        async move { result }
+    })
+    .await;
+
+    assert!(matches!(result, Err(Error::MeilisearchCommunication(e)) if e.status_code == 413));
+    assert_eq!(accepted.into_inner().unwrap(), vec![vec![json!({ "id": 1 })]]);
+}
+
+#[test]
+fn test_to_ndjson() {
+    let documents = vec![json!({ "id": 1, "text": "line\nbreak" }), json!({ "id": 2 })];
+    let ndjson = String::from_utf8(to_ndjson(&documents)).unwrap();
+
+    // Newlines inside values are escaped, so every line is one document
+    assert!(ndjson.ends_with('\n'));
+    let parsed: Vec<Value> = ndjson.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(parsed, documents);
+}
+
+#[test]
+fn test_to_csv() {
+    let documents = vec![
+        json!({ "id": 1, "name": "Ann, \"the\" first", "active": true, "score": 1.5 }),
+        json!({ "id": 2, "name": "Bob", "active": false, "score": 2 }),
+    ];
+    let csv = String::from_utf8(to_csv(&documents).unwrap()).unwrap();
+    assert_eq!(csv, "active:boolean,id:number,name:string,score:number\n\
+                     true,1,\"Ann, \"\"the\"\" first\",1.5\n\
+                     false,2,Bob,2\n");
+
+    // A nested value, or anything else CSV can't carry exactly, forces JSON
+    let nested = vec![documents[0].clone(), json!({ "id": 3, "name": "Cy", "active": true, "score": { "a": 1 } })];
+    assert!(to_csv(&nested).is_none());
+    assert!(to_csv(&[json!({ "id": 1, "tags": ["a"] })]).is_none());
+    assert!(to_csv(&[json!({ "id": 1, "name": "" })]).is_none());
+    assert!(to_csv(&[json!({ "id": 1, "name": "a" }), json!({ "id": "2", "name": "b" })]).is_none());
+}
+
+#[test]
+fn test_is_unknown_setting() {
+    let error = |error_code, error_message: &str| Error::Meilisearch(MeilisearchError {
+        error_message: error_message.to_string(),
+        error_code,
+        error_type: ErrorType::InvalidRequest,
+        error_link: String::new(),
+    });
+
+    // What servers older than a setting answer when they receive it
+    assert!(is_unknown_setting(&error(
+        ErrorCode::BadRequest,
+        "Unknown field `facetSearch`: expected one of `displayedAttributes`, `searchableAttributes`",
+    )));
+    // A known setting with a bad value is a real error
+    assert!(!is_unknown_setting(&error(ErrorCode::InvalidSettingsRankingRules, "Invalid value at `.rankingRules[0]`")));
+    assert!(!is_unknown_setting(&Error::InvalidRequest));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pause_between_batches() {
+    let start = tokio::time::Instant::now();
+    pause_between_batches(Duration::ZERO).await;
+    assert_eq!(start.elapsed(), Duration::ZERO);
+
+    pause_between_batches(Duration::from_millis(250)).await;
+    assert_eq!(start.elapsed(), Duration::from_millis(250));
+}
+
+#[tokio::test]
+async fn test_fetch_all_pages() {
+    let documents: Vec<Value> = (0..25).map(|id| json!({ "id": id })).collect();
+    let requests = Mutex::new(Vec::new());
+    let fetch = |offset: usize, limit: usize| {
+        requests.lock().unwrap().push((offset, limit));
+        let page = documents.iter().skip(offset).take(limit).cloned().collect();
+        let total = documents.len();
+        async move { Ok::<_, Error>((page, total)) }
+    };
+
+    // Pages follow each other until a short one
+    assert_eq!(fetch_all_pages(10, fetch).await.unwrap(), documents);
+    assert_eq!(*requests.lock().unwrap(), [(0, 10), (10, 10), (20, 10)]);
+
+    // A full last page ends at the total, without asking for an empty one
+    requests.lock().unwrap().clear();
+    assert_eq!(fetch_all_pages(5, fetch).await.unwrap(), documents);
+    assert_eq!(requests.lock().unwrap().len(), 5);
+}
+
+#[tokio::test]
+async fn test_client_through_proxy() {
+    // Stand-in for a proxy, answering the one request it gets itself
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = listener.local_addr().unwrap();
+    let received = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+        }
+        let body = r#"{"status":"available"}"#;
+        let response = format!("HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}", body.len(), body);
+        stream.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8(request).unwrap().to_lowercase()
+    });
+
+    // The host doesn't resolve, only the proxy can answer
+    let proxy_url = format!("http://user:secret@{}", proxy);
+    let client = MeilisearchClient::new_with_proxy("http://meilisearch.invalid:7700", Some("key"), Some(&proxy_url)).unwrap();
+    client.health().await.unwrap();
+    let request = received.await.unwrap();
+    assert!(request.starts_with("get http://meilisearch.invalid:7700/health http/1.1"));
+    // Basic credentials of user:secret for the proxy, the API key for Meilisearch
+    assert!(request.contains("proxy-authorization: basic dxnlcjpzzwnyzxq="));
+    assert!(request.contains("authorization: bearer key"));
+
+    assert!(MeilisearchClient::new_with_proxy("http://localhost:7700", None, Some("not a url")).is_err());
+}
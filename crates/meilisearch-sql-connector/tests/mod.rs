@@ -1,3 +1,6 @@
+// connector.rs, docs.rs and test_runner.rs each pull in utils.rs via #[path]
+#![allow(clippy::duplicate_mod)]
+
 mod config;
 mod connector;
 mod docs;
@@ -5,6 +8,11 @@ mod error;
 mod examples;
 mod integration;
 mod logging;
+mod meilisearch;
+mod mysql;
+mod postgres;
 mod sqlite;
 mod test_runner;
+mod transform;
 mod utils;
+mod watch;
@@ -0,0 +1,116 @@
+#![cfg(feature = "mysql")]
+
+use meilisearch_sql_connector::{
+    config::TableConfig,
+    database::{create_db_adapter, mysql::MySqlAdapter, AdapterOptions, DatabaseAdapter, RowFilters},
+    error::{ConnectorError, Result},
+};
+use futures_util::TryStreamExt;
+use serde_json::json;
+use sqlx::{Connection, MySqlConnection};
+
+// Database the tests create their tables in, e.g.
+// mysql://root@localhost/connector_test. Tests are skipped without it.
+const URL_VAR: &str = "MYSQL_TEST_URL";
+
+#[tokio::test]
+async fn test_mysql_adapter() -> Result<()> {
+    let Ok(url) = std::env::var(URL_VAR) else {
+        println!("Skipping the MySQL adapter test, {} is not set", URL_VAR);
+        return Ok(());
+    };
+    let table = format!("mysql_test_{}", std::process::id());
+    let keyless = format!("{}_keyless", table);
+
+    let mut conn = MySqlConnection::connect(&url).await?;
+    sqlx::query(&format!("DROP TABLE IF EXISTS {}, {}", table, keyless)).execute(&mut conn).await?;
+    sqlx::query(&format!(
+        "CREATE TABLE {} (id INT PRIMARY KEY, name VARCHAR(50), description TEXT, price DECIMAL(6, 2), \
+         active TINYINT(1), big BIGINT, created_at DATETIME)",
+        table
+    )).execute(&mut conn).await?;
+    sqlx::query(&format!(
+        "INSERT INTO {} VALUES (1, 'Widget', 'A widget', 9.99, 1, 9007199254740993, '2024-01-02 03:04:05'), \
+         (2, NULL, NULL, NULL, 0, 1, NULL)",
+        table
+    )).execute(&mut conn).await?;
+    sqlx::query(&format!("CREATE TABLE {} (value TEXT)", keyless)).execute(&mut conn).await?;
+
+    let result = check_adapter(&url, &table, &keyless).await;
+    sqlx::query(&format!("DROP TABLE {}, {}", table, keyless)).execute(&mut conn).await?;
+    result
+}
+
+async fn check_adapter(url: &str, table: &str, keyless: &str) -> Result<()> {
+    let adapter = MySqlAdapter::new_with_pool_size(url, 2).await?;
+
+    let tables = adapter.get_all_tables().await?;
+    assert!(tables.contains(&table.to_string()));
+    assert!(tables.contains(&keyless.to_string()));
+
+    let columns = adapter.get_table_columns(table).await?;
+    let names: Vec<&str> = columns.iter().map(|(name, _, _)| name.as_str()).collect();
+    assert_eq!(names, ["id", "name", "description", "price", "active", "big", "created_at"]);
+    assert_eq!(columns[0], ("id".to_string(), "int".to_string(), true));
+    assert!(columns[1..].iter().all(|(_, _, pk)| !pk));
+
+    assert_eq!(adapter.get_primary_key(table).await?, "id");
+    assert!(matches!(adapter.get_primary_key(keyless).await, Err(ConnectorError::NoPrimaryKey(_))));
+
+    // Each column type maps to its JSON counterpart, TINYINT(1) to a boolean
+    let mut records = adapter.fetch_all_records(table).await?;
+    records.sort_by_key(|record| record["id"].as_i64());
+    assert_eq!(records, vec![
+        json!({
+            "id": 1, "name": "Widget", "description": "A widget", "price": 9.99, "active": true,
+            "big": 9007199254740993u64, "created_at": "2024-01-02T03:04:05",
+        }),
+        json!({
+            "id": 2, "name": null, "description": null, "price": null, "active": false,
+            "big": 1, "created_at": null,
+        }),
+    ]);
+    assert!(adapter.take_decode_errors(table).is_empty());
+
+    let columns = ["id".to_string(), "name".to_string()];
+    let records = adapter.fetch_records(table, Some(&columns)).await?;
+    assert!(records.iter().all(|record| record.as_object().unwrap().len() == 2));
+    let streamed: Vec<_> = adapter.fetch_records_stream(table, Some(&columns)).try_collect().await?;
+    assert_eq!(streamed.len(), records.len());
+    let ordered = adapter.fetch_records_ordered(table, Some(&columns), "big").await?.unwrap();
+    assert_eq!(ordered.iter().map(|record| &record["id"]).collect::<Vec<_>>(), [&json!(2), &json!(1)]);
+
+    let since = json!("2024-01-01T00:00:00");
+    let records = adapter.fetch_records_since(table, Some(&columns), "created_at", &since).await?.unwrap();
+    assert_eq!(records, vec![json!({"id": 1, "name": "Widget"})]);
+
+    // A table's filter applies to every query reading its rows
+    let filter = TableConfig { name: table.to_string(), filter: Some("active = 1".to_string()), ..Default::default() };
+    let filtered = MySqlAdapter::new_with_pool_size(url, 2).await?.with_row_filters(RowFilters::new(&[filter]));
+    let records = filtered.fetch_records(table, Some(&columns)).await?;
+    assert_eq!(records, vec![json!({"id": 1, "name": "Widget"})]);
+    let since = json!("2000-01-01T00:00:00");
+    assert_eq!(filtered.fetch_records_since(table, Some(&columns), "created_at", &since).await?.unwrap().len(), 1);
+
+    // The retention window is part of the query, on date/time and epoch columns
+    let since_2024 = chrono::Utc::now().timestamp() as u64 - 1_704_067_200;
+    let window = |column: &str, seconds: u64| TableConfig {
+        name: table.to_string(),
+        retention_column: Some(column.to_string()),
+        retention_window_seconds: Some(seconds),
+        ..Default::default()
+    };
+    for (column, seconds, expected) in [("created_at", since_2024, 1), ("created_at", 86400, 0), ("big", 86400, 1)] {
+        let adapter = MySqlAdapter::new_with_pool_size(url, 2).await?.with_row_filters(RowFilters::new(&[window(column, seconds)]));
+        assert_eq!(adapter.fetch_records(table, Some(&columns)).await?.len(), expected, "{} within {}s", column, seconds);
+    }
+
+    // The connector's adapter applies the pool size and large integer threshold
+    let options = AdapterOptions { pool_size: Some(2), large_int_threshold: Some(1 << 53), ..Default::default() };
+    let adapter = create_db_adapter(url, &options).await?;
+    let records = adapter.fetch_all_records(table).await?;
+    let widget = records.iter().find(|record| record["id"] == 1).unwrap();
+    assert_eq!(widget["big"], "9007199254740993");
+
+    Ok(())
+}
@@ -0,0 +1,181 @@
+#![cfg(feature = "postgres")]
+
+use meilisearch_sql_connector::{
+    common::InMemoryMeilisearchClient,
+    config::{Config, DatabaseConfig, MeilisearchConfig, TableConfig},
+    database::{create_db_adapter, postgres::PostgresAdapter, AdapterOptions, DatabaseAdapter, RowFilters},
+    error::{ConnectorError, Result},
+    Connector,
+};
+use futures_util::TryStreamExt;
+use serde_json::json;
+use sqlx::{Connection, PgConnection};
+use std::sync::Arc;
+
+// Database the tests create their tables in, e.g.
+// postgres://postgres@localhost/connector_test. Tests are skipped without it.
+const URL_VAR: &str = "POSTGRES_TEST_URL";
+
+#[tokio::test]
+async fn test_postgres_adapter() -> Result<()> {
+    let Ok(url) = std::env::var(URL_VAR) else {
+        println!("Skipping the PostgreSQL adapter test, {} is not set", URL_VAR);
+        return Ok(());
+    };
+    let table = format!("pg_test_{}", std::process::id());
+    let keyless = format!("{}_keyless", table);
+
+    let mut conn = PgConnection::connect(&url).await?;
+    sqlx::query(&format!("DROP TABLE IF EXISTS {}, {}", table, keyless)).execute(&mut conn).await?;
+    sqlx::query(&format!(
+        "CREATE TABLE {} (id INTEGER PRIMARY KEY, name TEXT, price NUMERIC(6, 2), active BOOLEAN, \
+         tags TEXT[], big BIGINT, created_at TIMESTAMP)",
+        table
+    )).execute(&mut conn).await?;
+    sqlx::query(&format!(
+        "INSERT INTO {} VALUES (1, 'Widget', 9.99, true, ARRAY['a', 'b'], 9007199254740993, '2024-01-02 03:04:05'), \
+         (2, NULL, NULL, false, NULL, 1, NULL)",
+        table
+    )).execute(&mut conn).await?;
+    sqlx::query(&format!("CREATE TABLE {} (value TEXT)", keyless)).execute(&mut conn).await?;
+
+    let result = check_adapter(&url, &table, &keyless).await;
+    sqlx::query(&format!("DROP TABLE {}, {}", table, keyless)).execute(&mut conn).await?;
+    result
+}
+
+#[tokio::test]
+async fn test_postgres_reserved_word_table() -> Result<()> {
+    let Ok(url) = std::env::var(URL_VAR) else {
+        println!("Skipping the PostgreSQL reserved word test, {} is not set", URL_VAR);
+        return Ok(());
+    };
+    let mut conn = PgConnection::connect(&url).await?;
+    sqlx::query("DROP TABLE IF EXISTS \"user\"").execute(&mut conn).await?;
+    sqlx::query("CREATE TABLE \"user\" (id INTEGER PRIMARY KEY, name TEXT)").execute(&mut conn).await?;
+    sqlx::query("INSERT INTO \"user\" VALUES (1, 'Ada'), (2, 'Grace')").execute(&mut conn).await?;
+
+    // Unquoted, `user` is the current user rather than the table
+    let result = async {
+        let adapter = PostgresAdapter::new_with_pool_size(&url, 2).await?;
+        assert_eq!(adapter.get_primary_key("user").await?, "id");
+        let mut records = adapter.fetch_all_records("user").await?;
+        records.sort_by_key(|record| record["id"].as_i64());
+        assert_eq!(records, [json!({"id": 1, "name": "Ada"}), json!({"id": 2, "name": "Grace"})]);
+        Ok(())
+    }.await;
+    sqlx::query("DROP TABLE \"user\"").execute(&mut conn).await?;
+    result
+}
+
+#[tokio::test]
+async fn test_postgres_table_name_case() -> Result<()> {
+    let Ok(url) = std::env::var(URL_VAR) else {
+        println!("Skipping the PostgreSQL table name case test, {} is not set", URL_VAR);
+        return Ok(());
+    };
+    let table = format!("Pg_Case_{}", std::process::id());
+    let mut conn = PgConnection::connect(&url).await?;
+    sqlx::query(&format!("DROP TABLE IF EXISTS \"{}\"", table)).execute(&mut conn).await?;
+    sqlx::query(&format!("CREATE TABLE \"{}\" (id INTEGER PRIMARY KEY, name TEXT)", table)).execute(&mut conn).await?;
+    sqlx::query(&format!("INSERT INTO \"{}\" VALUES (1, 'Ada'), (2, 'Grace')", table)).execute(&mut conn).await?;
+
+    // Configured in lower case, the table is read under its own spelling
+    let configured = table.to_lowercase();
+    let result = async {
+        let adapter = PostgresAdapter::new_with_pool_size(&url, 2).await?;
+        let mut config = Config {
+            meilisearch: MeilisearchConfig { host: "http://localhost:7701".to_string(), ..Default::default() },
+            database: DatabaseConfig {
+                connection_string: url.clone(),
+                tables: vec![TableConfig { name: configured.clone(), primary_key: "id".to_string(), ..Default::default() }],
+                ..Default::default()
+            },
+        };
+        config.database.verify_tables(&adapter).await?;
+        assert_eq!(config.database.tables[0].name, table);
+
+        let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+        let connector = Connector::with_clients(config, Box::new(adapter), meilisearch.clone());
+        connector.sync_once().await?;
+        assert_eq!(meilisearch.document_ids(&configured), ["1", "2"]);
+        Ok(())
+    }.await;
+    sqlx::query(&format!("DROP TABLE \"{}\"", table)).execute(&mut conn).await?;
+    result
+}
+
+async fn check_adapter(url: &str, table: &str, keyless: &str) -> Result<()> {
+    let adapter = PostgresAdapter::new_with_pool_size(url, 2).await?;
+
+    let tables = adapter.get_all_tables().await?;
+    assert!(tables.contains(&table.to_string()));
+    assert!(tables.contains(&keyless.to_string()));
+
+    let columns = adapter.get_table_columns(table).await?;
+    let names: Vec<&str> = columns.iter().map(|(name, _, _)| name.as_str()).collect();
+    assert_eq!(names, ["id", "name", "price", "active", "tags", "big", "created_at"]);
+    assert_eq!(columns[0], ("id".to_string(), "integer".to_string(), true));
+    assert!(columns[1..].iter().all(|(_, _, pk)| !pk));
+
+    assert_eq!(adapter.get_primary_key(table).await?, "id");
+    assert!(matches!(adapter.get_primary_key(keyless).await, Err(ConnectorError::NoPrimaryKey(_))));
+    // Not a standby
+    assert_eq!(adapter.replication_lag().await?, None);
+
+    let mut records = adapter.fetch_all_records(table).await?;
+    records.sort_by_key(|record| record["id"].as_i64());
+    assert_eq!(records, vec![
+        json!({
+            "id": 1, "name": "Widget", "price": 9.99, "active": true, "tags": ["a", "b"],
+            "big": 9007199254740993u64, "created_at": "2024-01-02T03:04:05",
+        }),
+        json!({
+            "id": 2, "name": null, "price": null, "active": false, "tags": null,
+            "big": 1, "created_at": null,
+        }),
+    ]);
+
+    let columns = ["id".to_string(), "name".to_string()];
+    let records = adapter.fetch_records(table, Some(&columns)).await?;
+    assert!(records.iter().all(|record| record.as_object().unwrap().len() == 2));
+    let streamed: Vec<_> = adapter.fetch_records_stream(table, Some(&columns)).try_collect().await?;
+    assert_eq!(streamed.len(), records.len());
+    let ordered = adapter.fetch_records_ordered(table, Some(&columns), "big").await?.unwrap();
+    assert_eq!(ordered.iter().map(|record| &record["id"]).collect::<Vec<_>>(), [&json!(2), &json!(1)]);
+
+    // The watermark literal takes the column's type
+    let since = json!("2024-01-01T00:00:00");
+    let records = adapter.fetch_records_since(table, Some(&columns), "created_at", &since).await?.unwrap();
+    assert_eq!(records, vec![json!({"id": 1, "name": "Widget"})]);
+
+    // A table's filter applies to every query reading its rows
+    let filter = TableConfig { name: table.to_string(), filter: Some("active".to_string()), ..Default::default() };
+    let filtered = PostgresAdapter::new_with_pool_size(url, 2).await?.with_row_filters(RowFilters::new(&[filter]));
+    let records = filtered.fetch_records(table, Some(&columns)).await?;
+    assert_eq!(records, vec![json!({"id": 1, "name": "Widget"})]);
+    let since = json!("2000-01-01T00:00:00");
+    assert_eq!(filtered.fetch_records_since(table, Some(&columns), "created_at", &since).await?.unwrap().len(), 1);
+
+    // The retention window is part of the query, on date/time and epoch columns
+    let since_2024 = chrono::Utc::now().timestamp() as u64 - 1_704_067_200;
+    let window = |column: &str, seconds: u64| TableConfig {
+        name: table.to_string(),
+        retention_column: Some(column.to_string()),
+        retention_window_seconds: Some(seconds),
+        ..Default::default()
+    };
+    for (column, seconds, expected) in [("created_at", since_2024, 1), ("created_at", 86400, 0), ("big", 86400, 1)] {
+        let adapter = PostgresAdapter::new_with_pool_size(url, 2).await?.with_row_filters(RowFilters::new(&[window(column, seconds)]));
+        assert_eq!(adapter.fetch_records(table, Some(&columns)).await?.len(), expected, "{} within {}s", column, seconds);
+    }
+
+    // The connector's adapter applies the pool size and large integer threshold
+    let options = AdapterOptions { pool_size: Some(2), large_int_threshold: Some(1 << 53), ..Default::default() };
+    let adapter = create_db_adapter(url, &options).await?;
+    let records = adapter.fetch_all_records(table).await?;
+    let widget = records.iter().find(|record| record["id"] == 1).unwrap();
+    assert_eq!(widget["big"], "9007199254740993");
+
+    Ok(())
+}
@@ -1,11 +1,21 @@
 use meilisearch_sql_connector::{
-    database::{DatabaseAdapter, sqlite::SqliteAdapter},
-    error::Result,
+    common::InMemoryMeilisearchClient,
+    config::{Config, DatabaseConfig, MeilisearchConfig, TableConfig},
+    database::{
+        AdapterOptions, ColumnInfo, DatabaseAdapter, RowFilters, compare_primary_keys, create_db_adapter, limit_table_connections,
+        normalize_database_url, sqlite::SqliteAdapter,
+    },
+    error::{ConnectorError, Result},
+    Connector,
 };
 use sqlx::{Connection, SqliteConnection};
 use std::fs;
 use async_trait::async_trait;
-use serde_json::Value;
+use futures_util::TryStreamExt;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
 #[tokio::test]
 async fn test_sqlite_adapter() -> Result<()> {
@@ -61,6 +71,13 @@ async fn test_sqlite_adapter() -> Result<()> {
     assert_eq!(columns[1].1, "TEXT");
     assert!(!columns[1].2); // not primary key
 
+    // Test getting the table schema
+    let schema = adapter.get_table_schema("test").await?;
+    assert_eq!(schema, vec![
+        ("id".to_string(), "INTEGER".to_string()),
+        ("name".to_string(), "TEXT".to_string()),
+    ]);
+
     // Test getting primary key
     let primary_key = adapter.get_primary_key("test").await?;
     assert_eq!(primary_key, "id");
@@ -122,3 +139,685 @@ async fn test_mock_sqlite_adapter() -> Result<()> {
     
     Ok(())
 }
+
+#[tokio::test]
+async fn test_table_connection_limits() -> Result<()> {
+    // Records how many fetches run at once, per table and in total
+    #[derive(Default)]
+    struct SlowAdapter {
+        running: Mutex<HashMap<String, usize>>,
+        peak: Mutex<HashMap<String, usize>>,
+        running_total: AtomicUsize,
+        peak_total: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl DatabaseAdapter for SlowAdapter {
+        async fn fetch_all_records(&self, table: &str) -> Result<Vec<Value>> {
+            {
+                let mut running = self.running.lock().unwrap();
+                let count = running.entry(table.to_string()).or_default();
+                *count += 1;
+                let mut peak = self.peak.lock().unwrap();
+                let peak = peak.entry(table.to_string()).or_default();
+                *peak = (*peak).max(*count);
+            }
+            let total = self.running_total.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak_total.fetch_max(total, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            self.running_total.fetch_sub(1, Ordering::SeqCst);
+            *self.running.lock().unwrap().get_mut(table).unwrap() -= 1;
+            Ok(vec![])
+        }
+
+        async fn get_all_tables(&self) -> Result<Vec<String>> {
+            Ok(vec!["events".to_string(), "users".to_string()])
+        }
+
+        async fn get_table_columns(&self, _table: &str) -> Result<Vec<(String, String, bool)>> {
+            Ok(vec![])
+        }
+
+        async fn get_primary_key(&self, _table: &str) -> Result<String> {
+            Ok("id".to_string())
+        }
+    }
+
+    let inner = Arc::new(SlowAdapter::default());
+    let shared: Arc<Box<dyn DatabaseAdapter>> = Arc::new(Box::new(Shared(inner.clone())));
+    let events = TableConfig { name: "events".to_string(), max_connections: Some(2), ..Default::default() };
+    let users = TableConfig { name: "users".to_string(), ..Default::default() };
+    // Without limits the adapter is used as is
+    let adapter = limit_table_connections(shared.clone(), &[users.clone()], None);
+    assert!(Arc::ptr_eq(&adapter, &shared));
+
+    let adapter = limit_table_connections(shared.clone(), &[events.clone(), users.clone()], None);
+    let fetches = ["events", "events", "events", "events", "users", "users", "users"]
+        .map(|table| adapter.fetch_all_records(table));
+    futures_util::future::try_join_all(fetches).await?;
+    {
+        let peak = inner.peak.lock().unwrap();
+        assert_eq!(peak["events"], 2);
+        assert_eq!(peak["users"], 3);
+    }
+
+    // The query limit bounds the fetches of all tables together
+    inner.peak.lock().unwrap().clear();
+    inner.peak_total.store(0, Ordering::SeqCst);
+    let adapter = limit_table_connections(shared, &[events, users], Some(3));
+    let fetches = ["events", "events", "events", "events", "users", "users", "users"]
+        .map(|table| adapter.fetch_all_records(table));
+    futures_util::future::try_join_all(fetches).await?;
+    assert_eq!(inner.peak_total.load(Ordering::SeqCst), 3);
+    let peak = inner.peak.lock().unwrap();
+    assert_eq!(peak["events"], 2);
+
+    Ok(())
+}
+
+// Lets a test keep a handle on an adapter it wraps
+struct Shared<T>(Arc<T>);
+
+#[async_trait]
+impl<T: DatabaseAdapter> DatabaseAdapter for Shared<T> {
+    async fn fetch_all_records(&self, table: &str) -> Result<Vec<Value>> {
+        self.0.fetch_all_records(table).await
+    }
+
+    async fn get_all_tables(&self) -> Result<Vec<String>> {
+        self.0.get_all_tables().await
+    }
+
+    async fn get_table_columns(&self, table: &str) -> Result<Vec<(String, String, bool)>> {
+        self.0.get_table_columns(table).await
+    }
+
+    async fn get_primary_key(&self, table: &str) -> Result<String> {
+        self.0.get_primary_key(table).await
+    }
+}
+
+#[tokio::test]
+async fn test_large_int_as_string() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_path = tmp_dir.join(format!("sqlite_large_int_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    if db_path.exists() {
+        std::fs::remove_file(&db_path).unwrap();
+    }
+    std::fs::File::create(&db_path)?;
+
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE counters (id INTEGER PRIMARY KEY, small INTEGER, big INTEGER)")
+        .execute(&mut conn)
+        .await?;
+    sqlx::query("INSERT INTO counters (id, small, big) VALUES (1, 42, 9007199254740993)")
+        .execute(&mut conn)
+        .await?;
+    drop(conn);
+
+    // Without a threshold, large integers stay numbers
+    let adapter = SqliteAdapter::new(db_path_str).await?;
+    let records = adapter.fetch_all_records("counters").await?;
+    assert_eq!(records[0]["big"].as_i64(), Some(9007199254740993));
+
+    // With the default 2^53 threshold, only the unsafe value becomes a string
+    let adapter = SqliteAdapter::new(db_path_str).await?.with_large_int_threshold(Some(1 << 53));
+    let records = adapter.fetch_all_records("counters").await?;
+    assert_eq!(records[0]["id"].as_i64(), Some(1));
+    assert_eq!(records[0]["small"].as_i64(), Some(42));
+    assert_eq!(records[0]["big"].as_str(), Some("9007199254740993"));
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_decode_errors() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_path = tmp_dir.join(format!("sqlite_decode_errors_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    if db_path.exists() {
+        std::fs::remove_file(&db_path).unwrap();
+    }
+    std::fs::File::create(&db_path)?;
+
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE readings (id INTEGER PRIMARY KEY, value REAL)")
+        .execute(&mut conn)
+        .await?;
+    // Infinity has no JSON representation
+    sqlx::query("INSERT INTO readings (id, value) VALUES (1, 1.5), (2, 9e999), (3, -9e999)")
+        .execute(&mut conn)
+        .await?;
+    drop(conn);
+
+    let adapter = SqliteAdapter::new(db_path_str).await?;
+    let records = adapter.fetch_all_records("readings").await?;
+    assert_eq!(records[0]["value"].as_f64(), Some(1.5));
+    assert!(records[1]["value"].is_null());
+    assert_eq!(adapter.take_decode_errors("readings").into_iter().collect::<Vec<_>>(), [("value".to_string(), 2)]);
+    // Taking the counts resets them
+    assert!(adapter.take_decode_errors("readings").is_empty());
+
+    let adapter = adapter.with_strict_decoding(true);
+    let err = adapter.fetch_all_records("readings").await.unwrap_err();
+    assert!(matches!(err, ConnectorError::Database(msg) if msg.ends_with("table readings: value (2)")));
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_selected_columns() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_path = tmp_dir.join(format!("sqlite_selected_columns_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    if db_path.exists() {
+        std::fs::remove_file(&db_path).unwrap();
+    }
+    std::fs::File::create(&db_path)?;
+
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE events (id INTEGER PRIMARY KEY, \"event name\" TEXT, \"group\" TEXT, created_at TEXT, payload BLOB)")
+        .execute(&mut conn)
+        .await?;
+    sqlx::query("INSERT INTO events VALUES (1, 'signup', 'web', '2024-01-01', zeroblob(100000))")
+        .execute(&mut conn)
+        .await?;
+    drop(conn);
+
+    // The primary key and the columns other options use are read too
+    let table = TableConfig {
+        name: "events".to_string(),
+        // Names that aren't plain identifiers, a reserved word among them, are quoted
+        fields_to_index: vec!["event name".to_string(), "group".to_string(), "source".to_string()],
+        timestamp_fields: vec!["created_at".to_string()],
+        constant_fields: HashMap::from([("source".to_string(), Value::from("app"))]),
+        ..Default::default()
+    };
+    let columns = table.selected_columns().unwrap();
+    assert_eq!(columns, ["id", "event name", "group", "created_at"]);
+
+    let adapter = SqliteAdapter::new(db_path_str).await?;
+    let records = adapter.fetch_records("events", Some(&columns)).await?;
+    let fields: Vec<&String> = records[0].as_object().unwrap().keys().collect();
+    assert_eq!(fields, ["created_at", "event name", "group", "id"]);
+    assert!(records[0].get("payload").is_none());
+    let streamed: Vec<Value> = adapter.fetch_records_stream("events", Some(&columns)).try_collect().await?;
+    assert_eq!(streamed, records);
+
+    // Without an allowlist every column is read
+    let table = TableConfig { name: "events".to_string(), ..Default::default() };
+    assert_eq!(table.selected_columns(), None);
+    let records = adapter.fetch_records("events", None).await?;
+    assert!(records[0].get("payload").is_some());
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fetch_records_since() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_path = tmp_dir.join(format!("sqlite_since_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    if db_path.exists() {
+        std::fs::remove_file(&db_path).unwrap();
+    }
+    std::fs::File::create(&db_path)?;
+
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, updated_at DATETIME, version INTEGER)").execute(&mut conn).await?;
+    sqlx::query("INSERT INTO items VALUES (1, 'old', '2024-01-01 10:00:00', 1), (2, 'new', '2024-02-01 10:00:00', 5), \
+                 (3, 'never', NULL, NULL)")
+        .execute(&mut conn)
+        .await?;
+    drop(conn);
+
+    let adapter = SqliteAdapter::new(db_path_str).await?;
+    let columns = vec!["id".to_string(), "updated_at".to_string()];
+    let records = adapter.fetch_records_since("items", Some(&columns), "updated_at", &json!("2024-02-01 10:00:00")).await?.unwrap();
+    assert_eq!(records, [json!({ "id": 2, "updated_at": "2024-02-01 10:00:00" })]);
+
+    let records = adapter.fetch_records_since("items", None, "version", &json!(1)).await?.unwrap();
+    let ids: Vec<&Value> = records.iter().map(|record| &record["id"]).collect();
+    assert_eq!(ids, [&json!(1), &json!(2)]);
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fetch_records_stream() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_path = tmp_dir.join(format!("sqlite_stream_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    if db_path.exists() {
+        std::fs::remove_file(&db_path).unwrap();
+    }
+    std::fs::File::create(&db_path)?;
+
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, payload BLOB)").execute(&mut conn).await?;
+    sqlx::query("INSERT INTO items VALUES (1, 'a', x'00'), (2, 'b', NULL), (3, 'c', NULL)").execute(&mut conn).await?;
+    drop(conn);
+
+    // The stream yields what fetch_records returns
+    let adapter: Arc<Box<dyn DatabaseAdapter>> = Arc::new(Box::new(SqliteAdapter::new(db_path_str).await?));
+    let columns = vec!["id".to_string(), "name".to_string()];
+    let streamed: Vec<Value> = adapter.fetch_records_stream("items", Some(&columns)).try_collect().await?;
+    assert_eq!(streamed, adapter.fetch_records("items", Some(&columns)).await?);
+    assert_eq!(streamed[0], json!({ "id": 1, "name": "a" }));
+
+    // A table's connection limit holds until the stream is dropped
+    let items = TableConfig { name: "items".to_string(), max_connections: Some(1), ..Default::default() };
+    let limited = limit_table_connections(adapter, &[items], None);
+    let mut stream = limited.fetch_records_stream("items", None);
+    assert_eq!(stream.try_next().await?.unwrap()["id"], 1);
+    let blocked = tokio::time::timeout(std::time::Duration::from_millis(100), limited.fetch_records("items", None)).await;
+    assert!(blocked.is_err());
+    drop(stream);
+    assert_eq!(limited.fetch_records("items", None).await?.len(), 3);
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_row_filter() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_path = tmp_dir.join(format!("sqlite_row_filter_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    if db_path.exists() {
+        std::fs::remove_file(&db_path).unwrap();
+    }
+    std::fs::File::create(&db_path)?;
+
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE articles (id INTEGER PRIMARY KEY, title TEXT, published INTEGER, updated_at INTEGER)")
+        .execute(&mut conn)
+        .await?;
+    sqlx::query("INSERT INTO articles VALUES (1, 'Live', 1, 10), (2, 'Draft', 0, 20)").execute(&mut conn).await?;
+
+    let articles = TableConfig {
+        name: "articles".to_string(),
+        primary_key: "id".to_string(),
+        filter: Some("published = 1".to_string()),
+        ..Default::default()
+    };
+    let config = Config {
+        meilisearch: MeilisearchConfig { host: "http://localhost:7701".to_string(), ..Default::default() },
+        database: DatabaseConfig {
+            connection_string: db_path_str.to_string(),
+            tables: vec![articles],
+            ..Default::default()
+        },
+    };
+    let row_filters = RowFilters::new(&config.database.tables);
+    assert_eq!(row_filters.filtered_table("articles", Some("id > 0")), "\"articles\" WHERE (published = 1) AND id > 0");
+    assert_eq!(row_filters.filtered_table("authors", None), "\"authors\"");
+
+    // Every query reading the table's rows only sees the published one
+    let adapter = SqliteAdapter::new(db_path_str).await?.with_row_filters(row_filters);
+    assert_eq!(adapter.count_records("articles").await?, 1);
+    assert_eq!(adapter.fetch_random_records("articles", 10).await?.len(), 1);
+    let records = adapter.fetch_records_since("articles", None, "updated_at", &json!(0)).await?.unwrap();
+    assert_eq!(records, [json!({ "id": 1, "title": "Live", "published": 1, "updated_at": 10 })]);
+
+    let meilisearch = Arc::new(InMemoryMeilisearchClient::new());
+    let connector = Connector::with_clients(config, Box::new(adapter), meilisearch.clone());
+    connector.sync_once().await?;
+    assert_eq!(meilisearch.document_ids("articles"), ["1"]);
+
+    // A row that stops matching the filter is removed from the index
+    sqlx::query("UPDATE articles SET published = CASE id WHEN 1 THEN 0 ELSE 1 END").execute(&mut conn).await?;
+    drop(conn);
+    connector.sync_once().await?;
+    assert_eq!(meilisearch.document_ids("articles"), ["2"]);
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reserved_word_table() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_path = tmp_dir.join(format!("sqlite_reserved_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    if db_path.exists() {
+        std::fs::remove_file(&db_path).unwrap();
+    }
+    std::fs::File::create(&db_path)?;
+
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE \"order\" (id INTEGER PRIMARY KEY, total REAL)").execute(&mut conn).await?;
+    sqlx::query("INSERT INTO \"order\" VALUES (1, 9.5), (2, 20)").execute(&mut conn).await?;
+    drop(conn);
+
+    // Every query names the table quoted, so a keyword reads the table
+    let order = TableConfig { name: "order".to_string(), filter: Some("total > 10".to_string()), ..Default::default() };
+    let adapter = SqliteAdapter::new(db_path_str).await?.with_row_filters(RowFilters::new(&[order]));
+    assert_eq!(adapter.get_primary_key("order").await?, "id");
+    assert_eq!(adapter.get_table_columns("order").await?.len(), 2);
+    assert_eq!(adapter.fetch_all_records("order").await?, [json!({ "id": 2, "total": 20.0 })]);
+    assert_eq!(adapter.count_records("order").await?, 1);
+    let columns = vec!["id".to_string()];
+    assert_eq!(adapter.fetch_records_ordered("order", Some(&columns), "id").await?.unwrap(), [json!({ "id": 2 })]);
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retention_condition() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_path = tmp_dir.join(format!("sqlite_retention_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    if db_path.exists() {
+        std::fs::remove_file(&db_path).unwrap();
+    }
+    std::fs::File::create(&db_path)?;
+
+    // Each format parse_timestamp reads, within the last week and long before
+    let now = chrono::Utc::now();
+    let recent = now - chrono::Duration::days(2);
+    let old = now - chrono::Duration::days(30);
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE events (id INTEGER PRIMARY KEY, \"seen at\")").execute(&mut conn).await?;
+    let rows: Vec<(i64, Value)> = vec![
+        (1, json!(recent.format("%Y-%m-%d %H:%M:%S").to_string())),
+        (2, json!(recent.to_rfc3339())),
+        (3, json!(recent.timestamp())),
+        (4, json!(recent.timestamp_millis())),
+        (5, json!(format!(" {} ", recent.timestamp()))),
+        (6, json!(old.format("%Y-%m-%d %H:%M:%S").to_string())),
+        (7, json!(old.timestamp())),
+        (8, json!(old.timestamp_millis())),
+        (9, json!(null)),
+    ];
+    for (id, seen_at) in &rows {
+        let query = sqlx::query("INSERT INTO events VALUES (?, ?)").bind(id);
+        let query = match seen_at {
+            Value::String(s) => query.bind(s.clone()),
+            Value::Number(n) => query.bind(n.as_i64()),
+            _ => query.bind(None::<String>),
+        };
+        query.execute(&mut conn).await?;
+    }
+    drop(conn);
+
+    let events = TableConfig {
+        name: "events".to_string(),
+        primary_key: "id".to_string(),
+        retention_column: Some("seen at".to_string()),
+        retention_window_seconds: Some(7 * 86400),
+        ..Default::default()
+    };
+    let row_filters = RowFilters::new(&[events]);
+    assert!(row_filters.filtered_table("events", None).starts_with("\"events\" WHERE (CASE WHEN"));
+
+    // The database leaves out the rows older than the window
+    let adapter = SqliteAdapter::new(db_path_str).await?.with_row_filters(row_filters);
+    let mut ids: Vec<i64> = adapter.fetch_all_records("events").await?.iter().filter_map(|r| r["id"].as_i64()).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, [1, 2, 3, 4, 5]);
+    assert_eq!(adapter.count_records("events").await?, 5);
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fetch_records_ordered() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_path = tmp_dir.join(format!("sqlite_ordered_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    if db_path.exists() {
+        std::fs::remove_file(&db_path).unwrap();
+    }
+    std::fs::File::create(&db_path)?;
+
+    // Without a declared type the column keeps integers and strings apart
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE items (id, name TEXT)").execute(&mut conn).await?;
+    sqlx::query("INSERT INTO items VALUES (10, 'ten'), ('b', 'b'), (9, 'nine'), ('B', 'B'), ('10', 'text ten'), (-1, 'minus one')")
+        .execute(&mut conn)
+        .await?;
+    drop(conn);
+
+    let adapter = SqliteAdapter::new(db_path_str).await?;
+    let columns = vec!["id".to_string()];
+    let records = adapter.fetch_records_ordered("items", Some(&columns), "id").await?.unwrap();
+    let ids: Vec<&Value> = records.iter().map(|record| &record["id"]).collect();
+    assert_eq!(ids, [&json!(-1), &json!(9), &json!(10), &json!("10"), &json!("B"), &json!("b")]);
+    // The order SQLite returns is the one the connector merges on
+    assert!(ids.is_sorted_by(|a, b| compare_primary_keys(a, b).is_le()));
+    assert_eq!(records[0].as_object().unwrap().len(), 1);
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_views() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_path = tmp_dir.join(format!("sqlite_views_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    if db_path.exists() {
+        std::fs::remove_file(&db_path).unwrap();
+    }
+    std::fs::File::create(&db_path)?;
+
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE products (id INTEGER PRIMARY KEY, name TEXT, active INTEGER)")
+        .execute(&mut conn)
+        .await?;
+    sqlx::query("CREATE VIEW active_products AS SELECT id, name FROM products WHERE active = 1")
+        .execute(&mut conn)
+        .await?;
+    drop(conn);
+
+    // Views are listed separately from tables
+    let adapter = SqliteAdapter::new(db_path_str).await?;
+    assert_eq!(adapter.get_all_tables().await?, vec!["products".to_string()]);
+    assert_eq!(adapter.get_all_views().await?, vec!["active_products".to_string()]);
+
+    // A view with an explicit primary key passes validation
+    let mut database = DatabaseConfig {
+        tables: vec![TableConfig {
+            name: "active_products".to_string(),
+            primary_key: "id".to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    database.verify_tables(&adapter).await?;
+
+    // Generation can't detect a key for the view, so it is left for the user to add
+    let config = Config::generate_from_database_url(
+        &format!("sqlite://{}", db_path_str),
+        "http://localhost:7700",
+        60,
+        true,
+    ).await?;
+    let names: Vec<&str> = config.database.tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["products"]);
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_inspect_methods() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_path = tmp_dir.join(format!("sqlite_inspect_{}.db", std::process::id()));
+    let db_path_str = db_path.to_str().unwrap();
+    if db_path.exists() {
+        std::fs::remove_file(&db_path).unwrap();
+    }
+    std::fs::File::create(&db_path)?;
+
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path_str)).await?;
+    sqlx::query("CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL)")
+        .execute(&mut conn)
+        .await?;
+    for email in ["a@example.com", "b@example.com", "c@example.com"] {
+        sqlx::query("INSERT INTO users (email) VALUES (?)").bind(email).execute(&mut conn).await?;
+    }
+    drop(conn);
+
+    let adapter = SqliteAdapter::new(db_path_str).await?;
+    assert_eq!(adapter.get_column_details("users").await?, vec![
+        ColumnInfo { name: "id".to_string(), type_: "INTEGER".to_string(), primary_key: true, nullable: Some(true) },
+        ColumnInfo { name: "email".to_string(), type_: "TEXT".to_string(), primary_key: false, nullable: Some(false) },
+    ]);
+    assert_eq!(adapter.count_records("users").await?, 3);
+
+    let samples = adapter.fetch_sample_records("users", 2).await?;
+    assert_eq!(samples.len(), 2);
+    assert_eq!(samples[0]["email"], Value::from("a@example.com"));
+    let random = adapter.fetch_random_records("users", 2).await?;
+    assert_eq!(random.len(), 2);
+    assert_ne!(random[0]["id"], random[1]["id"]);
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[test]
+fn test_normalize_database_url() {
+    assert_eq!(normalize_database_url("./x.db", "sqlite"), "sqlite:./x.db");
+    assert_eq!(normalize_database_url("/abs/x.db", "sqlite"), "sqlite:/abs/x.db");
+    assert_eq!(normalize_database_url("x.db", "sqlite"), "sqlite:x.db");
+    assert_eq!(normalize_database_url("C:\\data\\x.db", "sqlite"), "sqlite:C:\\data\\x.db");
+    // URLs with a real scheme are left alone
+    assert_eq!(normalize_database_url("sqlite:///abs/x.db", "sqlite"), "sqlite:///abs/x.db");
+    assert_eq!(normalize_database_url("postgres://user@host/db", "sqlite"), "postgres://user@host/db");
+}
+
+#[tokio::test]
+async fn test_create_adapter_from_bare_path() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let db_name = format!("sqlite_bare_path_{}.db", std::process::id());
+    let db_path = tmp_dir.join(&db_name);
+    std::fs::File::create(&db_path)?;
+
+    let mut conn = SqliteConnection::connect(&format!("sqlite://{}", db_path.to_str().unwrap())).await?;
+    sqlx::query("CREATE TABLE items (id INTEGER PRIMARY KEY)").execute(&mut conn).await?;
+    drop(conn);
+
+    for path in [
+        format!("./tmp/{}", db_name),
+        format!("tmp/{}", db_name),
+        db_path.to_str().unwrap().to_string(),
+    ] {
+        let adapter = create_db_adapter(&path, &AdapterOptions::default()).await?;
+        assert_eq!(adapter.get_all_tables().await?, vec!["items".to_string()], "path: {}", path);
+    }
+
+    let config = Config::generate_from_database_url(db_path.to_str().unwrap(), "http://localhost:7700", 60, false).await?;
+    assert_eq!(config.database.tables[0].name, "items");
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_create_adapter_retries_until_database_exists() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+    let db_path = tmp_dir.join(format!("sqlite_cold_start_{}.db", std::process::id()));
+    let url = format!("sqlite://{}", db_path.to_str().unwrap());
+
+    // Without retries a missing database fails straight away
+    assert!(create_db_adapter(&url, &AdapterOptions::default()).await.is_err());
+
+    // The database "comes up" while the connector is waiting
+    let creator_path = db_path.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        std::fs::File::create(&creator_path).unwrap();
+    });
+    let options = AdapterOptions {
+        connect_retries: 5,
+        connect_retry_delay: std::time::Duration::from_millis(50),
+        ..Default::default()
+    };
+    let adapter = create_db_adapter(&url, &options).await?;
+    assert!(adapter.get_all_tables().await?.is_empty());
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_create_if_missing() -> Result<()> {
+    let current_dir = std::env::current_dir().unwrap();
+    let tmp_dir = current_dir.join("tmp");
+    fs::create_dir_all(&tmp_dir).unwrap();
+    let db_path = tmp_dir.join(format!("sqlite_create_if_missing_{}.db", std::process::id()));
+    let url = format!("sqlite://{}", db_path.to_str().unwrap());
+
+    // By default a missing file is an error naming the option, so a typo in the path isn't silently a new database
+    let err = create_db_adapter(&url, &AdapterOptions::default()).await.err().unwrap();
+    assert!(matches!(err, ConnectorError::Database(msg) if msg.contains("create_if_missing")));
+    assert!(!db_path.exists());
+
+    let options = AdapterOptions { create_if_missing: true, ..Default::default() };
+    let adapter = create_db_adapter(&url, &options).await?;
+    assert!(db_path.exists());
+    assert!(adapter.get_all_tables().await?.is_empty());
+
+    std::fs::remove_file(&db_path).unwrap_or_default();
+    Ok(())
+}
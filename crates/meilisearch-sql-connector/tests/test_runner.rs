@@ -3,6 +3,7 @@ mod utils;
 use std::process::Child;
 
 #[allow(dead_code)]
+#[derive(Default)]
 pub struct TestRunner {
     meilisearch: Option<Child>,
 }
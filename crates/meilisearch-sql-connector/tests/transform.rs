@@ -0,0 +1,78 @@
+use meilisearch_sql_connector::config::HashAlgorithm;
+use meilisearch_sql_connector::transform::{
+    hash_value, parse_timestamp, parse_vector, render_template, split_array, template_placeholders,
+};
+use serde_json::json;
+
+#[test]
+fn test_parse_timestamp_formats() {
+    // 2024-01-02T03:04:05Z
+    let expected = 1_704_164_645;
+    assert_eq!(parse_timestamp(&json!("2024-01-02T03:04:05Z")), Some(expected));
+    assert_eq!(parse_timestamp(&json!("2024-01-02T05:04:05+02:00")), Some(expected));
+    assert_eq!(parse_timestamp(&json!("2024-01-02 03:04:05")), Some(expected));
+    assert_eq!(parse_timestamp(&json!("2024-01-02 03:04:05.250")), Some(expected));
+    assert_eq!(parse_timestamp(&json!("2024-01-02")), Some(1_704_153_600));
+    assert_eq!(parse_timestamp(&json!(expected)), Some(expected));
+    assert_eq!(parse_timestamp(&json!(1_704_164_645_250_i64)), Some(expected));
+    assert_eq!(parse_timestamp(&json!("1704164645")), Some(expected));
+}
+
+#[test]
+fn test_parse_timestamp_invalid() {
+    assert_eq!(parse_timestamp(&json!("not a date")), None);
+    assert_eq!(parse_timestamp(&json!("")), None);
+    assert_eq!(parse_timestamp(&json!(true)), None);
+    assert_eq!(parse_timestamp(&json!(null)), None);
+}
+
+#[test]
+fn test_parse_vector() {
+    assert_eq!(parse_vector(&json!([0.5, 1, -2.25])), Some(vec![0.5, 1.0, -2.25]));
+    // JSON stored in a text column
+    assert_eq!(parse_vector(&json!("[0.5, 1, -2.25]")), Some(vec![0.5, 1.0, -2.25]));
+    assert_eq!(parse_vector(&json!([])), None);
+    assert_eq!(parse_vector(&json!([0.5, "1"])), None);
+    assert_eq!(parse_vector(&json!("not a vector")), None);
+    assert_eq!(parse_vector(&json!(0.5)), None);
+}
+
+#[test]
+fn test_split_array() {
+    assert_eq!(split_array(&json!("rust, search ,sql"), ","), Some(vec![json!("rust"), json!("search"), json!("sql")]));
+    assert_eq!(split_array(&json!("a|b||"), "|"), Some(vec![json!("a"), json!("b")]));
+    assert_eq!(split_array(&json!(""), ","), Some(vec![]));
+    assert_eq!(split_array(&json!("  "), ","), Some(vec![]));
+    assert_eq!(split_array(&json!(null), ","), Some(vec![]));
+    // Native arrays are already multi-valued
+    assert_eq!(split_array(&json!(["x", 1]), ","), Some(vec![json!("x"), json!(1)]));
+    assert_eq!(split_array(&json!(42), ","), None);
+}
+
+#[test]
+fn test_hash_value() {
+    let sha256_abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    assert_eq!(hash_value(&json!("abc"), HashAlgorithm::Sha256, None).as_deref(), Some(sha256_abc));
+    // The salt is prepended to the value
+    assert_eq!(hash_value(&json!("c"), HashAlgorithm::Sha256, Some("ab")).as_deref(), Some(sha256_abc));
+    assert_eq!(hash_value(&json!("abc"), HashAlgorithm::Sha512, None).map(|h| h.len()), Some(128));
+    let blake3_abc = "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85";
+    assert_eq!(hash_value(&json!("abc"), HashAlgorithm::Blake3, None).as_deref(), Some(blake3_abc));
+    // Non-string values are hashed as their JSON text
+    assert_eq!(hash_value(&json!(42), HashAlgorithm::Sha256, None), hash_value(&json!("42"), HashAlgorithm::Sha256, None));
+    assert_eq!(hash_value(&json!(null), HashAlgorithm::Sha256, None), None);
+}
+
+#[test]
+fn test_render_template() {
+    assert_eq!(template_placeholders("https://cdn/{tenant}/{id}.png"), ["tenant", "id"]);
+    assert!(template_placeholders("/var/blobs/photo").is_empty());
+
+    let record = json!({ "id": 42, "tenant": "acme", "note": null });
+    let record = record.as_object().unwrap();
+    assert_eq!(render_template("https://cdn/{tenant}/{id}.png", record).as_deref(), Some("https://cdn/acme/42.png"));
+    assert_eq!(render_template("/var/blobs/photo", record).as_deref(), Some("/var/blobs/photo"));
+    // An incomplete reference is no reference
+    assert_eq!(render_template("https://cdn/{note}", record), None);
+    assert_eq!(render_template("https://cdn/{missing}", record), None);
+}
@@ -0,0 +1,54 @@
+use meilisearch_sql_connector::report::TableStatus;
+use meilisearch_sql_connector::watch::StatusView;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+fn synced(added: usize, deleted: usize, documents: usize, at: SystemTime) -> TableStatus {
+    TableStatus {
+        last_sync: Some(at),
+        documents,
+        last_changes: added + deleted,
+        last_added: added,
+        last_deleted: deleted,
+        last_error: None,
+    }
+}
+
+#[test]
+fn test_status_view_plain() {
+    let now = SystemTime::now();
+    let mut status = BTreeMap::new();
+    status.insert("orders".to_string(), synced(3, 1, 120, now));
+    status.insert("users".to_string(), TableStatus { last_error: Some("database is locked".to_string()), ..Default::default() });
+
+    let mut view = StatusView::new(false);
+    let output = view.render(&status, now);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("orders: +3 -1, 120 documents, synced at "));
+    assert_eq!(lines[1], "users: not synced yet, error: database is locked");
+
+    // Only tables whose status changed are written again
+    assert_eq!(view.render(&status, now + Duration::from_secs(5)), "");
+    status.insert("orders".to_string(), synced(0, 0, 120, now + Duration::from_secs(60)));
+    let output = view.render(&status, now + Duration::from_secs(60));
+    assert!(output.starts_with("orders: +0 -0, 120 documents"));
+    assert_eq!(output.lines().count(), 1);
+}
+
+#[test]
+fn test_status_view_redraw() {
+    colored::control::set_override(false);
+    let now = SystemTime::now();
+    let mut status = BTreeMap::new();
+    status.insert("orders".to_string(), synced(3, 1, 120, now - Duration::from_secs(4)));
+    status.insert("users".to_string(), TableStatus::default());
+
+    let mut view = StatusView::new(true);
+    assert_eq!(
+        view.render(&status, now),
+        "\x1b[Jorders  +3 -1  120 documents  synced 4s ago\nusers   not synced yet\n",
+    );
+    // Later redraws move back over the previous lines first
+    assert!(view.render(&status, now).starts_with("\x1b[2A\x1b[J"));
+}